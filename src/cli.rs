@@ -1,28 +1,57 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::{process, result};
+use std::{io, process, result};
 
-use chrono::{NaiveDate, NaiveDateTime};
+use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
 use clap::Parser;
 use codespan_reporting::files::SimpleFile;
 use directories::ProjectDirs;
+use tzfile::Tz;
 
-use crate::eval::{self, DateRange, Entry, EntryMode};
+use crate::error::Eprint;
+use crate::eval::{self, DateRange, Entry, EntryKind, EntryMode};
 use crate::files::cli::{CliDate, CliIdent, CliRange};
-use crate::files::{self, Files, ParseError};
+use crate::files::{self, FileSource, Files, ParseError};
 
+use self::config::Config;
 use self::error::{Error, Result};
+use self::flat::SortKey;
+use self::import::ImportFormat;
 use self::layout::line::LineLayout;
+use self::layout::LayoutOptions;
+use self::locale::Locale;
+use self::print::PrintOptions;
+use self::tags::TagFilter;
 
 mod cancel;
+mod config;
+mod count;
+mod day;
 mod done;
+mod dump_ast;
 mod error;
+mod explain;
+mod export;
+mod flat;
+mod group;
+mod heatmap;
+mod highlight;
+mod hook;
+mod import;
 mod layout;
+mod locale;
 mod log;
+mod markdown;
 mod new;
 mod print;
 mod show;
+mod since_last;
+mod streak;
+mod tags;
 mod util;
+mod validate;
+mod waiting;
 
 #[derive(Debug, clap::Parser)]
 pub struct Opt {
@@ -35,6 +64,91 @@ pub struct Opt {
     /// Range of days to focus on
     #[clap(short, long, default_value = "t-2d--t+2w")]
     range: String,
+    /// Only show the current day, overriding --range
+    #[clap(long)]
+    today: bool,
+    /// Print the changes made to each file when saving
+    #[clap(long)]
+    diff: bool,
+    /// How errors are printed
+    #[clap(long, value_enum, default_value_t = ErrorFormat::Human)]
+    error_format: ErrorFormat,
+    /// Print the changes that would be made without saving any files
+    #[clap(long)]
+    dry_run: bool,
+    /// Don't report which files are saved
+    #[clap(long)]
+    quiet_save: bool,
+    /// Show ISO week numbers in day headers
+    #[clap(long)]
+    week_numbers: bool,
+    /// Hide occurrences of repeating tasks before today unless they were done
+    #[clap(long)]
+    future_only: bool,
+    /// Plan ahead: hide done and canceled tasks unless --include-done is given
+    #[clap(long)]
+    planning: bool,
+    /// Show done and canceled tasks in the planning view
+    #[clap(long, requires = "planning")]
+    include_done: bool,
+    /// Don't show the line marking the current time
+    #[clap(long)]
+    no_now: bool,
+    /// Mark the last occurrence of repeating entries before their UNTIL
+    #[clap(long)]
+    mark_ends: bool,
+    /// Maximum number of occurrences a single command may generate
+    #[clap(long)]
+    max_occurrences: Option<usize>,
+    /// Maximum number of columns for lines showing the extent of entries
+    #[clap(long)]
+    max_spans: Option<usize>,
+    /// Maximum number of entries shown per day, summarizing the rest
+    #[clap(long)]
+    per_day_limit: Option<usize>,
+    /// Show entries' descriptions below them
+    #[clap(long)]
+    descriptions: bool,
+    /// Show Markdown in descriptions as it is instead of styling it
+    #[clap(long)]
+    no_markdown: bool,
+    /// Shorten entries to fit into this many columns, or into the terminal if
+    /// no number is given
+    #[clap(long, value_name = "COLUMNS", num_args = 0..=1)]
+    width: Option<Option<usize>>,
+    /// Only show entries with this tag, or without it if prefixed with `!`
+    #[clap(long = "tag")]
+    tags: Vec<String>,
+    /// Only show entries from this file
+    #[clap(long)]
+    source: Option<PathBuf>,
+    /// List entries grouped by the file they come from
+    #[clap(long, conflicts_with_all = ["flat", "count_only"])]
+    group_by_file: bool,
+    /// List entries as a flat list instead of by day
+    #[clap(long, conflicts_with_all = ["group_by_file", "count_only"])]
+    flat: bool,
+    /// Only print how many entries each day has
+    #[clap(long, conflicts_with_all = ["group_by_file", "flat"])]
+    count_only: bool,
+    /// What to sort the flat list by
+    #[clap(long, value_enum, default_value_t = SortKey::Date, requires = "flat")]
+    sort_by: SortKey,
+    /// Only show entries added, modified or completed since the last time this
+    /// flag was used
+    #[clap(long)]
+    since_last: bool,
+    /// Emphasize entries with this tag, or whose date satisfies this formula,
+    /// like `(wd = fri)`. Further rules are read from the highlight config file,
+    /// one per line
+    #[clap(long)]
+    highlight: Vec<String>,
+    /// Language of weekday and month names
+    #[clap(long, value_enum, default_value_t = Locale::En)]
+    locale: Locale,
+    /// Display times in this time zone instead of the files' time zone
+    #[clap(long)]
+    tz: Option<String>,
     #[clap(subcommand)]
     command: Option<Command>,
 }
@@ -47,6 +161,19 @@ pub enum Command {
         /// Entries and days to show
         #[clap(required = true)]
         identifiers: Vec<String>,
+        /// How many upcoming occurrences of recurring entries to list
+        #[clap(long, default_value = "5")]
+        upcoming: usize,
+    },
+    /// Shows all entries of a single day in detail
+    Day {
+        #[clap(default_value = "t")]
+        date: String,
+    },
+    /// Shows how many entries each day of a month has
+    Heatmap {
+        /// Any day of the month to show, defaults to today
+        month: Option<String>,
     },
     /// Create a new entry based on a template
     #[clap(alias = "n")]
@@ -60,6 +187,9 @@ pub enum Command {
         /// Entries to mark as done
         #[clap(required = true)]
         entries: Vec<usize>,
+        /// Date the entries were done on, if not today
+        #[clap(long)]
+        at: Option<String>,
     },
     /// Marks one or more entries as canceled
     #[clap(alias = "c")]
@@ -67,15 +197,62 @@ pub enum Command {
         /// Entries to mark as done
         #[clap(required = true)]
         entries: Vec<usize>,
+        /// Date the entries were canceled on, if not today
+        #[clap(long)]
+        at: Option<String>,
     },
     /// Edits or creates a log entry
     #[clap(alias = "l")]
     Log {
         #[clap(default_value = "t")]
         date: String,
+        /// Print all logs in the range, collapsing consecutive identical logs
+        #[clap(long, conflicts_with = "date")]
+        summary: bool,
+    },
+    /// Counts how many occurrences of a recurring task were done in a row
+    Streak {
+        /// Task to count the streak of
+        entry: usize,
+    },
+    /// Traces how an entry's dates are evaluated
+    Explain {
+        /// Entry to explain
+        entry: usize,
     },
     /// Reformats all loaded files
     Fmt,
+    /// Lists all tags and how many entries use them
+    Tags,
+    /// Lists all tasks that are waiting on someone or something
+    Waiting,
+    /// Checks the loaded files for problems that don't prevent evaluation
+    Validate,
+    /// Adds entries from a file in another format to the capture file
+    Import {
+        /// File to import
+        file: PathBuf,
+        /// Format of the file to import
+        #[clap(long, value_enum, default_value_t = ImportFormat::VCard)]
+        format: ImportFormat,
+    },
+    /// Prints entries as an iCalendar (.ics) file
+    Export {
+        /// Range of days to export, if different from --range
+        #[clap(long)]
+        export_range: Option<String>,
+    },
+    /// Prints the parsed syntax tree of all loaded files as JSON, including
+    /// source spans
+    DumpAst,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// Human-readable diagnostics
+    Human,
+    /// One JSON object per error
+    Json,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -95,6 +272,9 @@ pub enum Template {
     /// Adds an undated task marked as done today
     #[clap(alias = "d")]
     Done,
+    /// Adds an entry based on a TEMPLATE from the loaded files
+    #[clap(external_subcommand)]
+    Custom(Vec<String>),
 }
 
 fn default_file() -> PathBuf {
@@ -104,13 +284,113 @@ fn default_file() -> PathBuf {
         .join("main.today")
 }
 
-fn load_files(opt: &Opt, files: &mut Files) -> result::Result<(), files::Error> {
+/// Contains settings like how the current time is labeled.
+fn config_file() -> PathBuf {
+    ProjectDirs::from("", "", "today")
+        .expect("could not determine config dir")
+        .config_dir()
+        .join("config")
+}
+
+/// Contains the shell command to run after saving changed files.
+fn hook_file() -> PathBuf {
+    ProjectDirs::from("", "", "today")
+        .expect("could not determine config dir")
+        .config_dir()
+        .join("on-save")
+}
+
+/// Contains rules for entries to highlight in addition to `--highlight`.
+fn highlight_file() -> PathBuf {
+    ProjectDirs::from("", "", "today")
+        .expect("could not determine config dir")
+        .config_dir()
+        .join("highlight")
+}
+
+/// Where `--since-last` remembers what the files looked like.
+fn state_file() -> PathBuf {
+    ProjectDirs::from("", "", "today")
+        .expect("could not determine config dir")
+        .config_dir()
+        .join("last-seen")
+}
+
+fn load_files(opt: &Opt, config: &Config, files: &mut Files) -> result::Result<(), files::Error> {
     let file = opt.file.clone().unwrap_or_else(default_file);
+    files.set_max_occurrences(opt.max_occurrences);
+    files.set_times_of_day(config.times.clone());
     files.load(&file)
 }
 
-fn find_entries(files: &Files, range: DateRange) -> Result<Vec<Entry>> {
-    Ok(files.eval(EntryMode::Relevant, range)?)
+/// Options controlling which entries are found.
+#[derive(Debug)]
+struct EntryOptions {
+    /// Only find entries whose tags match this filter.
+    filter: TagFilter,
+    /// Only find entries from this file.
+    source: Option<FileSource>,
+    /// Drop occurrences of repeating tasks before this date unless they were
+    /// done.
+    future_from: Option<NaiveDate>,
+    /// Find done and canceled tasks.
+    include_done: bool,
+}
+
+impl Default for EntryOptions {
+    fn default() -> Self {
+        Self {
+            filter: TagFilter::default(),
+            source: None,
+            future_from: None,
+            include_done: true,
+        }
+    }
+}
+
+fn find_entries(files: &Files, range: DateRange, options: &EntryOptions) -> Result<Vec<Entry>> {
+    let entries = match options.future_from {
+        Some(today) => files.eval_future_only(EntryMode::Relevant, range, today)?,
+        None => files.eval(EntryMode::Relevant, range)?,
+    };
+    Ok(entries
+        .into_iter()
+        .filter(|entry| {
+            options.include_done
+                || !matches!(
+                    entry.kind,
+                    EntryKind::TaskDone(_) | EntryKind::TaskCanceled(_)
+                )
+        })
+        .filter(|entry| options.source.is_none() || options.source == Some(entry.source.file()))
+        .filter(|entry| {
+            let tags = files.command(entry.source).value.value.tags();
+            options.filter.matches(&tags)
+        })
+        .collect())
+}
+
+/// Convert all entries' times from the files' time zone to `tz`.
+fn convert_entries<Z: TimeZone>(files: &Files, entries: &[Entry], tz: &Z) -> Result<Vec<Entry>> {
+    let from = files.timezone();
+    entries
+        .iter()
+        .cloned()
+        .map(|mut entry| -> Result<Entry> {
+            if let Some(dates) = entry.dates {
+                let dates = dates.convert_timezone(&from, tz).map_err(|(date, time)| {
+                    eval::Error::NonexistentTime {
+                        index: entry.source.file(),
+                        span: files.command(entry.source).value.span,
+                        date,
+                        time,
+                    }
+                })?;
+                entry.dates = Some(dates);
+            }
+            Ok(entry)
+        })
+        .collect()
 }
 
 fn find_layout(
@@ -118,8 +398,18 @@ fn find_layout(
     entries: &[Entry],
     range: DateRange,
     now: NaiveDateTime,
-) -> LineLayout {
-    layout::layout(files, entries, range, now)
+    tz: Option<&Tz>,
+    options: LayoutOptions,
+) -> Result<LineLayout> {
+    // Converting the entries keeps their indices intact, so the layout can
+    // still be used to look up the original entries.
+    Ok(match tz {
+        Some(tz) => {
+            let entries = convert_entries(files, entries, &tz)?;
+            layout::layout(files, &entries, range, now, options)
+        }
+        None => layout::layout(files, entries, range, now, options),
+    })
 }
 
 fn parse_eval_arg<T, E, R>(name: &str, text: &str, eval: E) -> Result<R>
@@ -153,89 +443,556 @@ fn parse_show_idents(identifiers: &[String], today: NaiveDate) -> Result<Vec<sho
     Ok(idents)
 }
 
-fn run_command(opt: &Opt, files: &mut Files, range: DateRange, now: NaiveDateTime) -> Result<()> {
+fn find_range(opt: &Opt, today: NaiveDate) -> Result<DateRange> {
+    if opt.today {
+        return Ok(DateRange::new(today, today));
+    }
+
+    let range = parse_eval_arg("--range", &opt.range, |range: CliRange| {
+        range.eval((), today)
+    })?;
+    // The current day must always be visible since it contains "now" and all
+    // reminders.
+    Ok(range.containing(today))
+}
+
+/// Unlike [`find_range`], the export range doesn't need to contain the current
+/// day.
+fn find_export_range(
+    export_range: Option<&str>,
+    range: DateRange,
+    today: NaiveDate,
+) -> Result<DateRange> {
+    match export_range {
+        Some(export_range) => parse_eval_arg("--export-range", export_range, |range: CliRange| {
+            range.eval((), today)
+        }),
+        None => Ok(range),
+    }
+}
+
+fn find_descriptions(files: &Files, entries: &[Entry]) -> HashMap<usize, Vec<String>> {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| (index, files.command(entry.source).value.value.desc()))
+        .filter(|(_, desc)| !desc.is_empty())
+        .map(|(index, desc)| (index, desc.to_vec()))
+        .collect()
+}
+
+fn print_layout(
+    opt: &Opt,
+    files: &Files,
+    entries: &[Entry],
+    layout: &LineLayout,
+    options: &PrintOptions,
+) -> Result<()> {
+    let mut rules = highlight::load(&highlight_file())?;
+    for text in &opt.highlight {
+        rules.push(highlight::Rule::parse("--highlight", text)?);
+    }
+    let highlighted = highlight::find_highlighted(files, entries, &rules)?;
+    let descriptions = if opt.descriptions {
+        find_descriptions(files, entries)
+    } else {
+        HashMap::new()
+    };
+    print::print(layout, options, &highlighted, &descriptions);
+    Ok(())
+}
+
+fn run_command(
+    opt: &Opt,
+    config: &Config,
+    files: &mut Files,
+    range: DateRange,
+    now: NaiveDateTime,
+    tz: Option<&Tz>,
+) -> Result<()> {
+    let source = match &opt.source {
+        Some(path) => Some(
+            files
+                .file_source(path)
+                .ok_or_else(|| Error::NoSuchSource(path.clone()))?,
+        ),
+        None => None,
+    };
+    let future_from = opt.future_only.then(|| now.date());
+    let entry_options = EntryOptions {
+        filter: TagFilter::new(&opt.tags),
+        source,
+        future_from,
+        include_done: !opt.planning || opt.include_done,
+    };
+    let layout_options = LayoutOptions {
+        show_now: !opt.no_now,
+        mark_ends: opt.mark_ends,
+        max_spans: opt.max_spans,
+        per_day_limit: opt.per_day_limit,
+    };
+    let print_options = PrintOptions {
+        week_numbers: opt.week_numbers,
+        now_label: config.now_label.clone(),
+        now_inline: config.now_inline,
+        desc_indent: config.desc_indent,
+        desc_marker: config.desc_marker.clone(),
+        locale: opt.locale,
+        markdown: !opt.no_markdown,
+        column_gap: config.column_gap,
+        width: opt
+            .width
+            .map(|width| width.unwrap_or_else(util::terminal_width)),
+    };
     match &opt.command {
         None => {
-            let entries = find_entries(files, range)?;
-            let layout = find_layout(files, &entries, range, now);
-            print::print(&layout);
+            let mut entries = find_entries(files, range, &entry_options)?;
+            if opt.since_last {
+                let changes = since_last::changes_since_last(files, &state_file(), opt.dry_run)?;
+                entries.retain(|entry| changes.contains_key(&entry.source));
+            }
+            let layout = find_layout(files, &entries, range, now, tz, layout_options)?;
+            if opt.count_only {
+                print!("{}", count::fmt_counts(&entries, range, now));
+            } else if opt.group_by_file {
+                print!("{}", group::fmt_grouped(files, &entries, &layout));
+            } else if opt.flat {
+                print!("{}", flat::fmt_flat(&entries, &layout, opt.sort_by));
+            } else {
+                print_layout(opt, files, &entries, &layout, &print_options)?;
+            }
         }
-        Some(Command::Show { identifiers }) => {
-            let entries = find_entries(files, range)?;
-            let layout = find_layout(files, &entries, range, now);
+        Some(Command::Show {
+            identifiers,
+            upcoming,
+        }) => {
+            let entries = find_entries(files, range, &entry_options)?;
+            let layout = find_layout(files, &entries, range, now, tz, layout_options)?;
             let idents = parse_show_idents(identifiers, now.date())?;
-            show::show(files, &entries, &layout, &idents);
+            show::show(
+                files,
+                &entries,
+                &layout,
+                &idents,
+                *upcoming,
+                !opt.no_markdown,
+            );
+        }
+        Some(Command::Day { date }) => {
+            let date = parse_eval_date("date", date, now.date())?;
+            let entries = find_entries(files, DateRange::new(date, date), &entry_options)?;
+            let entries = match tz {
+                Some(tz) => convert_entries(files, &entries, &tz)?,
+                None => entries,
+            };
+            day::day(files, &entries, date, !opt.no_markdown);
+        }
+        Some(Command::Heatmap { month }) => {
+            let date = match month {
+                Some(month) => parse_eval_date("month", month, now.date())?,
+                None => now.date(),
+            };
+            let range = heatmap::month_range(date);
+            let entries = find_entries(files, range, &entry_options)?;
+            heatmap::heatmap(&entries, range, opt.locale);
         }
         Some(Command::New { template }) => match template {
             Template::Task { date: Some(date) } => {
                 let date = parse_eval_date("date", date, now.date())?;
-                new::task(files, Some(date))?
+                new::task(files, Some(date), now.date())?
             }
-            Template::Task { date: None } => new::task(files, None)?,
+            Template::Task { date: None } => new::task(files, None, now.date())?,
             Template::Note { date: Some(date) } => {
                 let date = parse_eval_date("date", date, now.date())?;
-                new::note(files, Some(date))?
+                new::note(files, Some(date), now.date())?
             }
-            Template::Note { date: None } => new::note(files, None)?,
+            Template::Note { date: None } => new::note(files, None, now.date())?,
             Template::Done => new::done(files, now.date())?,
+            Template::Custom(args) => match &args[..] {
+                [name] => new::template(files, name, now.date())?,
+                // Clap only runs external subcommands with at least a name
+                _ => return Err(Error::TemplateArguments(args[0].clone())),
+            },
         },
-        Some(Command::Done { entries: ns }) => {
-            let entries = find_entries(files, range)?;
-            let layout = find_layout(files, &entries, range, now);
-            done::done(files, &entries, &layout, ns, now)?;
-            let entries = find_entries(files, range)?;
-            let layout = find_layout(files, &entries, range, now);
-            print::print(&layout);
-        }
-        Some(Command::Cancel { entries: ns }) => {
-            let entries = find_entries(files, range)?;
-            let layout = find_layout(files, &entries, range, now);
-            cancel::cancel(files, &entries, &layout, ns, now)?;
-            let entries = find_entries(files, range)?;
-            let layout = find_layout(files, &entries, range, now);
-            print::print(&layout);
-        }
-        Some(Command::Log { date }) => {
+        Some(Command::Done { entries: ns, at }) => {
+            let at = match at {
+                Some(at) => parse_eval_date("at", at, now.date())?,
+                None => now.date(),
+            };
+            let entries = find_entries(files, range, &entry_options)?;
+            let layout = find_layout(files, &entries, range, now, tz, layout_options)?;
+            done::done(files, &entries, &layout, ns, at)?;
+            let entries = find_entries(files, range, &entry_options)?;
+            let layout = find_layout(files, &entries, range, now, tz, layout_options)?;
+            print_layout(opt, files, &entries, &layout, &print_options)?;
+        }
+        Some(Command::Cancel { entries: ns, at }) => {
+            let at = match at {
+                Some(at) => parse_eval_date("at", at, now.date())?,
+                None => now.date(),
+            };
+            let entries = find_entries(files, range, &entry_options)?;
+            let layout = find_layout(files, &entries, range, now, tz, layout_options)?;
+            cancel::cancel(files, &entries, &layout, ns, at)?;
+            let entries = find_entries(files, range, &entry_options)?;
+            let layout = find_layout(files, &entries, range, now, tz, layout_options)?;
+            print_layout(opt, files, &entries, &layout, &print_options)?;
+        }
+        Some(Command::Log { summary: true, .. }) => log::summary(files, range),
+        Some(Command::Log { date, .. }) => {
             let date = parse_eval_arg("date", date, |date: CliDate| date.eval((), now.date()))?;
             log::log(files, date)?
         }
+        Some(Command::Streak { entry }) => {
+            let entries = find_entries(files, range, &entry_options)?;
+            let layout = find_layout(files, &entries, range, now, tz, layout_options)?;
+            streak::streak(files, &entries, &layout, *entry, now.date())?
+        }
+        Some(Command::Explain { entry }) => {
+            let entries = find_entries(files, range, &entry_options)?;
+            let layout = find_layout(files, &entries, range, now, tz, layout_options)?;
+            explain::explain(files, &entries, &layout, *entry, range, future_from)?
+        }
         Some(Command::Fmt) => files.mark_all_dirty(),
+        Some(Command::Tags) => tags::tags(files),
+        Some(Command::Waiting) => waiting::waiting(files),
+        Some(Command::Validate) => {
+            let entries = find_entries(files, range, &entry_options)?;
+            validate::validate(files, &entries, opt.error_format)?
+        }
+        Some(Command::Import { file, format }) => import::import(files, *format, file)?,
+        Some(Command::Export { export_range }) => {
+            let range = find_export_range(export_range.as_deref(), range, now.date())?;
+            let entries = find_entries(files, range, &entry_options)?;
+            let entries = convert_entries(files, &entries, &Utc)?;
+            print!("{}", export::fmt_ical(&entries, Utc::now()));
+        }
+        Some(Command::DumpAst) => dump_ast::dump_ast(files),
     }
     Ok(())
 }
 
-fn run_with_files(opt: Opt, files: &mut Files) -> Result<()> {
-    let now = files.now().naive_local();
+fn run_with_files(opt: Opt, config: &Config, files: &mut Files) -> Result<()> {
+    let tz = match &opt.tz {
+        Some(name) => Some(Tz::named(name).map_err(|error| Error::ResolveTz {
+            tz: name.clone(),
+            error,
+        })?),
+        None => None,
+    };
+
+    let now = match &tz {
+        Some(tz) => files.now().with_timezone(&tz).naive_local(),
+        None => files.now().naive_local(),
+    };
     let today = parse_eval_arg("--date", &opt.date, |date: CliDate| {
         date.eval((), now.date())
     })?;
     let now = today.and_time(now.time());
 
-    let range = parse_eval_arg("--range", &opt.range, |range: CliRange| {
-        range.eval((), now.date())
-    })?;
+    let range = find_range(&opt, now.date())?;
 
-    run_command(&opt, files, range, now)?;
+    run_command(&opt, config, files, range, now, tz.as_ref())?;
 
     Ok(())
 }
 
+/// Save all changed files, reporting each file to `log` unless `quiet`.
+fn save_files(
+    files: &Files,
+    diff: bool,
+    quiet: bool,
+    log: &mut dyn io::Write,
+) -> result::Result<Vec<PathBuf>, files::Error> {
+    if quiet {
+        files.save(diff, &mut io::sink())
+    } else {
+        files.save(diff, log)
+    }
+}
+
+#[allow(single_use_lifetimes)]
+fn eprint_error<'a, E: Eprint<'a, Files>>(format: ErrorFormat, files: &'a Files, e: &E) {
+    match format {
+        ErrorFormat::Human => crate::error::eprint_error(files, e),
+        ErrorFormat::Json => crate::error::eprint_error_json(files, e),
+    }
+}
+
 pub fn run() {
     let opt = Opt::parse();
 
+    let error_format = opt.error_format;
     let mut files = Files::new();
-    if let Err(e) = load_files(&opt, &mut files) {
-        crate::error::eprint_error(&files, &e);
+    let config = match Config::load(&config_file()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprint_error(error_format, &files, &e);
+            process::exit(1);
+        }
+    };
+    if let Err(e) = load_files(&opt, &config, &mut files) {
+        eprint_error(error_format, &files, &e);
         process::exit(1);
     }
 
-    if let Err(e) = run_with_files(opt, &mut files) {
-        crate::error::eprint_error(&files, &e);
+    let diff = opt.diff;
+    let dry_run = opt.dry_run;
+    let quiet_save = opt.quiet_save;
+    if let Err(e) = run_with_files(opt, &config, &mut files) {
+        eprint_error(error_format, &files, &e);
         process::exit(1);
     }
 
-    if let Err(e) = files.save() {
-        crate::error::eprint_error(&files, &e);
+    if dry_run {
+        for diff in files.pending_diffs() {
+            print!("{diff}");
+        }
+        return;
+    }
+
+    let changed = match save_files(&files, diff, quiet_save, &mut io::stderr()) {
+        Ok(changed) => changed,
+        Err(e) => {
+            eprint_error(error_format, &files, &e);
+            process::exit(1);
+        }
+    };
+
+    let ran =
+        hook::load(&hook_file()).and_then(|command| hook::on_save(command.as_deref(), &changed));
+    if let Err(e) = ran {
+        eprint_error(error_format, &files, &e);
         process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, Utc};
+    use clap::Parser;
+    use tzfile::Tz;
+
+    use crate::eval::{self, DateRange};
+    use crate::files::{FileSource, Files};
+    use crate::testing::{self, TempDir};
+
+    use super::layout::line::LineEntry;
+    use super::{
+        convert_entries, export, find_entries, find_export_range, find_layout, find_range,
+        save_files, Command, EntryOptions, Error, LayoutOptions, Opt,
+    };
+
+    #[test]
+    fn today_overrides_range() {
+        let files = testing::load(
+            "TIMEZONE UTC\n\nTASK Laundry\nDATE 2024-01-05\n\nTASK Taxes\nDATE 2024-01-06\n",
+        );
+
+        let today = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        let opt = Opt::parse_from(["today", "--today", "--range", "t--t+1w"]);
+        let range = find_range(&opt, today).unwrap();
+        assert_eq!((range.from(), range.until()), (today, today));
+
+        let now = today.and_hms_opt(12, 0, 0).unwrap();
+        let entries = find_entries(&files, range, &EntryOptions::default()).unwrap();
+        let layout =
+            find_layout(&files, &entries, range, now, None, LayoutOptions::default()).unwrap();
+
+        let texts = layout
+            .lines()
+            .iter()
+            .filter_map(|line| match line {
+                LineEntry::Entry { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(texts, ["Laundry"]);
+        assert!(layout
+            .lines()
+            .iter()
+            .any(|line| matches!(line, LineEntry::Now { .. })));
+    }
+
+    #[test]
+    fn log_summary_without_date() {
+        let opt = Opt::parse_from(["today", "log", "--summary"]);
+        assert!(matches!(
+            opt.command,
+            Some(Command::Log { summary: true, .. })
+        ));
+        assert!(Opt::try_parse_from(["today", "log", "t-1d", "--summary"]).is_err());
+    }
+
+    #[test]
+    fn range_contains_today() {
+        let ymd = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        let today = ymd(5);
+
+        let opt = Opt::parse_from(["today", "--range", "2024-01-10--2024-01-12"]);
+        let range = find_range(&opt, today).unwrap();
+        assert_eq!((range.from(), range.until()), (today, ymd(12)));
+
+        let opt = Opt::parse_from(["today", "--range", "2024-01-01--2024-01-02"]);
+        let range = find_range(&opt, today).unwrap();
+        assert_eq!((range.from(), range.until()), (ymd(1), today));
+    }
+
+    #[test]
+    fn output_modes_conflict() {
+        assert!(Opt::try_parse_from(["today", "--flat", "--count-only"]).is_err());
+        assert!(Opt::try_parse_from(["today", "--flat", "--group-by-file"]).is_err());
+        assert!(Opt::try_parse_from(["today", "--group-by-file", "--count-only"]).is_err());
+        assert!(Opt::try_parse_from(["today", "--flat", "--sort-by", "title"]).is_ok());
+    }
+
+    #[test]
+    fn convert_skipped_time() {
+        // Samoa skipped 2011-12-30 entirely when moving across the date line
+        let files = testing::load("TIMEZONE Pacific/Apia\n\nNOTE Call\nDATE 2011-12-30 10:00\n");
+
+        let day = NaiveDate::from_ymd_opt(2011, 12, 30).unwrap();
+        let range = DateRange::new(day, day);
+        let entries = find_entries(&files, range, &EntryOptions::default()).unwrap();
+        let utc = Tz::named("UTC").unwrap();
+        assert!(matches!(
+            convert_entries(&files, &entries, &&utc),
+            Err(Error::Eval(eval::Error::NonexistentTime { .. }))
+        ));
+    }
+
+    #[test]
+    fn planning_hides_done() {
+        let files = testing::load(
+            "TIMEZONE UTC\n\nTASK Laundry\nDATE 2024-01-05\nDONE [2024-01-05]\n\n\
+             TASK Taxes\nDATE 2024-01-06\n",
+        );
+
+        let today = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        let titles = |args: &[&str]| {
+            let opt = Opt::parse_from(args);
+            let range = find_range(&opt, today).unwrap();
+            let options = EntryOptions {
+                include_done: !opt.planning || opt.include_done,
+                ..EntryOptions::default()
+            };
+            find_entries(&files, range, &options)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.title)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(titles(&["today", "--planning"]), ["Taxes"]);
+        assert_eq!(
+            titles(&["today", "--planning", "--include-done"]),
+            ["Laundry", "Taxes"]
+        );
+        assert_eq!(titles(&["today"]), ["Laundry", "Taxes"]);
+    }
+
+    #[test]
+    fn source_filter() {
+        // Sources are looked up by path, so the files must stay around
+        let dir = TempDir::new();
+        let other = dir.write("other.today", "TASK Taxes\nDATE 2024-01-05\n");
+        let path = dir.write(
+            "main.today",
+            "TIMEZONE UTC\nINCLUDE other.today\n\nTASK Laundry\nDATE 2024-01-05\n",
+        );
+
+        let mut files = Files::new();
+        files.load(&path).unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        let range = DateRange::new(date, date);
+        let titles = |source: Option<FileSource>| {
+            let options = EntryOptions {
+                source,
+                ..EntryOptions::default()
+            };
+            find_entries(&files, range, &options)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.title)
+                .collect::<Vec<_>>()
+        };
+
+        let source = files.file_source(&path);
+        assert!(source.is_some());
+        assert_eq!(titles(source), ["Laundry"]);
+        assert_eq!(titles(files.file_source(&other)), ["Taxes"]);
+        assert_eq!(titles(None), ["Laundry", "Taxes"]);
+
+        let missing = dir.path().join("missing.today");
+        assert_eq!(files.file_source(&missing), None);
+    }
+
+    #[test]
+    fn export_range() {
+        let files = testing::load(
+            "TIMEZONE UTC\n\nTASK Laundry\nDATE 2024-01-05\n\nTASK Taxes\nDATE 2024-05-31\n",
+        );
+
+        let today = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        let opt = Opt::parse_from([
+            "today",
+            "--range",
+            "t--t+2w",
+            "export",
+            "--export-range",
+            "2024-01-01--2024-12-31",
+        ]);
+        let range = find_range(&opt, today).unwrap();
+        let export_range = match &opt.command {
+            Some(Command::Export { export_range }) => export_range.as_deref(),
+            _ => panic!("expected export command"),
+        };
+        let export_range = find_export_range(export_range, range, today).unwrap();
+        assert_eq!(
+            (export_range.from(), export_range.until()),
+            (
+                NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 12, 31).unwrap()
+            )
+        );
+
+        let titles = |range| {
+            find_entries(&files, range, &EntryOptions::default())
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.title)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(titles(range), ["Laundry"]);
+        assert_eq!(titles(export_range), ["Laundry", "Taxes"]);
+
+        let entries = find_entries(&files, export_range, &EntryOptions::default()).unwrap();
+        let ical = export::fmt_ical(&entries, Utc::now());
+        assert!(ical.contains("SUMMARY:Taxes\r\nDTSTART;VALUE=DATE:20240531\r\n"));
+
+        // Without --export-range, the display range is exported
+        let export_range = find_export_range(None, range, today).unwrap();
+        assert_eq!(titles(export_range), ["Laundry"]);
+    }
+
+    #[test]
+    fn quiet_save() {
+        let dir = TempDir::new();
+        let path = dir.write("main.today", "TIMEZONE UTC\n");
+        let mut files = Files::new();
+        files.load(&path).unwrap();
+        files.mark_all_dirty();
+
+        let opt = Opt::parse_from(["today"]);
+        let mut log = vec![];
+        save_files(&files, false, opt.quiet_save, &mut log).unwrap();
+        let log = String::from_utf8(log).unwrap();
+        assert_eq!(log, format!("Unchanged file {path:?}\n"));
+
+        let opt = Opt::parse_from(["today", "--quiet-save"]);
+        let mut log = vec![];
+        save_files(&files, false, opt.quiet_save, &mut log).unwrap();
+        assert!(log.is_empty());
+    }
+}
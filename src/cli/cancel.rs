@@ -1,6 +1,6 @@
 use std::vec;
 
-use chrono::NaiveDateTime;
+use chrono::NaiveDate;
 
 use crate::eval::Entry;
 use crate::files::commands::{Done, DoneKind};
@@ -14,7 +14,7 @@ pub fn cancel(
     entries: &[Entry],
     layout: &LineLayout,
     numbers: &[usize],
-    now: NaiveDateTime,
+    done_at: NaiveDate,
 ) -> Result<()> {
     let mut not_tasks = vec![];
     for &number in numbers {
@@ -22,7 +22,8 @@ pub fn cancel(
         let done = Done {
             kind: DoneKind::Canceled,
             date: entry.dates.map(|dates| dates.into()),
-            done_at: now.date(),
+            done_at,
+            spent: None,
         };
         if !files.add_done(entry.source, done) {
             not_tasks.push(number);
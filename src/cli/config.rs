@@ -0,0 +1,234 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::result;
+use std::str::FromStr;
+
+use crate::files::primitives::{Time, TimeOfDay};
+use crate::files::TimesOfDay;
+
+use super::error::{Error, Result};
+
+/// Settings from the config file.
+///
+/// The file contains one `key = value` pair per line. Empty lines and lines
+/// starting with `#` are ignored. Values may be surrounded by double quotes to
+/// keep leading or trailing spaces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Label of the line marking the current time.
+    pub now_label: String,
+    /// Mark the current time next to the preceding entry instead of on its own
+    /// line.
+    pub now_inline: bool,
+    /// How far descriptions are indented.
+    pub desc_indent: usize,
+    /// Text at the start of each description line.
+    pub desc_marker: String,
+    /// How many spaces separate the number, span and entry columns.
+    pub column_gap: usize,
+    /// What times of day like `morning` resolve to, if not their defaults.
+    pub times: TimesOfDay,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            now_label: "now".to_string(),
+            now_inline: false,
+            desc_indent: 2,
+            desc_marker: String::new(),
+            column_gap: 1,
+            times: TimesOfDay::new(),
+        }
+    }
+}
+
+fn parse_value<T: FromStr>(key: &str, value: &str) -> result::Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value {value:?} for {key}"))
+}
+
+/// Parse a time like `08:30`.
+fn parse_time(key: &str, value: &str) -> result::Result<Time, String> {
+    let error = || format!("invalid time {value:?} for {key}");
+    let (hour, min) = value.split_once(':').ok_or_else(error)?;
+    let hour = hour.parse::<u8>().map_err(|_| error())?;
+    let min = min.parse::<u8>().map_err(|_| error())?;
+    let time = Time::new(hour.into(), min.into());
+    if time.in_normal_range() {
+        Ok(time)
+    } else {
+        Err(error())
+    }
+}
+
+impl Config {
+    fn set(&mut self, key: &str, value: &str) -> result::Result<(), String> {
+        match key {
+            "now-label" => self.now_label = value.to_string(),
+            "now-inline" => self.now_inline = parse_value(key, value)?,
+            "desc-indent" => self.desc_indent = parse_value(key, value)?,
+            "desc-marker" => self.desc_marker = value.to_string(),
+            "column-gap" => self.column_gap = parse_value(key, value)?,
+            "morning" | "noon" | "afternoon" | "evening" | "night" => {
+                let tod = match key {
+                    "morning" => TimeOfDay::Morning,
+                    "noon" => TimeOfDay::Noon,
+                    "afternoon" => TimeOfDay::Afternoon,
+                    "evening" => TimeOfDay::Evening,
+                    _ => TimeOfDay::Night,
+                };
+                self.times.insert(tod, parse_time(key, value)?);
+            }
+            _ => return Err(format!("unknown key {key}")),
+        }
+        Ok(())
+    }
+
+    /// Parse the contents of a config file. The `name` describes where the
+    /// text comes from, for error messages.
+    pub fn parse(name: &str, text: &str) -> Result<Self> {
+        let mut config = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let result = match line.split_once('=') {
+                Some((key, value)) => {
+                    let value = value.trim();
+                    let value = value
+                        .strip_prefix('"')
+                        .and_then(|value| value.strip_suffix('"'))
+                        .unwrap_or(value);
+                    config.set(key.trim(), value)
+                }
+                None => Err("expected key = value".to_string()),
+            };
+            result.map_err(|message| Error::InvalidConfig {
+                file: name.to_string(),
+                line: i + 1,
+                message,
+            })?;
+        }
+        Ok(config)
+    }
+
+    /// Load the config file at `path`.
+    ///
+    /// Returns the default config if the file doesn't exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&path.display().to_string(), &text),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(Error::ReadConfig {
+                file: path.to_path_buf(),
+                error,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::files::primitives::{Time, TimeOfDay};
+    use crate::files::TimesOfDay;
+    use crate::testing::TempDir;
+
+    use super::super::error::Error;
+    use super::Config;
+
+    #[test]
+    fn parse_keys() {
+        let config = Config::parse(
+            "config",
+            "# Shown in German\nnow-label = jetzt\n\nnow-inline = true\n",
+        )
+        .unwrap();
+        assert_eq!(config.now_label, "jetzt");
+        assert!(config.now_inline);
+    }
+
+    #[test]
+    fn quoted_values() {
+        let config = Config::parse("config", "now-label = \" now \"\n").unwrap();
+        assert_eq!(config.now_label, " now ");
+    }
+
+    #[test]
+    fn descriptions() {
+        let config = Config::parse("config", "desc-indent = 4\ndesc-marker = \"> \"\n").unwrap();
+        assert_eq!(config.desc_indent, 4);
+        assert_eq!(config.desc_marker, "> ");
+
+        let config = Config::parse("config", "").unwrap();
+        assert_eq!(config.desc_indent, 2);
+        assert_eq!(config.desc_marker, "");
+    }
+
+    #[test]
+    fn column_gap() {
+        assert_eq!(
+            Config::parse("config", "column-gap = 3\n")
+                .unwrap()
+                .column_gap,
+            3
+        );
+        assert_eq!(Config::parse("config", "").unwrap().column_gap, 1);
+        assert!(Config::parse("config", "column-gap = -1\n").is_err());
+    }
+
+    #[test]
+    fn times_of_day() {
+        let config = Config::parse("config", "morning = 07:30\nnight = 23:00\n").unwrap();
+        assert_eq!(
+            config.times,
+            TimesOfDay::from([
+                (TimeOfDay::Morning, Time::new(7, 30)),
+                (TimeOfDay::Night, Time::new(23, 0)),
+            ])
+        );
+
+        assert!(Config::parse("config", "noon = 12\n").is_err());
+        assert!(Config::parse("config", "noon = 25:00\n").is_err());
+        assert!(Config::parse("config", "noon = 12:60\n").is_err());
+    }
+
+    #[test]
+    fn invalid_lines() {
+        let error = |text| match Config::parse("config", text) {
+            Err(Error::InvalidConfig { line, message, .. }) => (line, message),
+            _ => panic!("expected invalid config"),
+        };
+        assert_eq!(
+            error("now-label = jetzt\nnow-inline = maybe\n"),
+            (2, "invalid value \"maybe\" for now-inline".to_string())
+        );
+        assert_eq!(
+            error("colour = red\n"),
+            (1, "unknown key colour".to_string())
+        );
+        assert_eq!(
+            error("now-inline\n"),
+            (1, "expected key = value".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_file() {
+        let dir = TempDir::new();
+        let config = Config::load(&dir.path().join("config")).unwrap();
+        assert_eq!(config, Config::default());
+
+        let path = dir.write("config", "now-label = jetzt\n");
+        assert_eq!(Config::load(&path).unwrap().now_label, "jetzt");
+
+        // Other errors than a missing file are reported
+        assert!(matches!(
+            Config::load(dir.path()),
+            Err(Error::ReadConfig { .. })
+        ));
+    }
+}
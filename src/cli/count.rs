@@ -0,0 +1,47 @@
+use std::fmt::Write;
+
+use chrono::NaiveDateTime;
+
+use crate::eval::{DateRange, Entry};
+
+use super::layout;
+
+/// Format one line per day of `range` with the number of entries on that day.
+pub fn fmt_counts(entries: &[Entry], range: DateRange, now: NaiveDateTime) -> String {
+    let mut result = String::new();
+    for (day, count) in layout::day_counts(entries, range, now) {
+        writeln!(result, "{} ({}): {count}", day, day.format("%a")).unwrap();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::eval::DateRange;
+    use crate::testing;
+
+    use super::fmt_counts;
+
+    #[test]
+    fn counts_per_day() {
+        let from = NaiveDate::from_ymd_opt(2024, 1, 6).unwrap();
+        let until = NaiveDate::from_ymd_opt(2024, 1, 8).unwrap();
+        let range = DateRange::new(from, until);
+        let (_, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\n\
+             TASK Laundry\nDATE 2024-01-06\n\n\
+             TASK Taxes\nDATE 2024-01-06 10:00\n\n\
+             NOTE Trip\nDATE 2024-01-06 -- 2024-01-07\n\n\
+             NOTE Party\nDATE 2024-01-08\n",
+            range,
+        );
+        let now = from.and_hms_opt(12, 0, 0).unwrap();
+
+        assert_eq!(
+            fmt_counts(&entries, range, now),
+            "2024-01-06 (Sat): 3\n2024-01-07 (Sun): 1\n2024-01-08 (Mon): 1\n"
+        );
+    }
+}
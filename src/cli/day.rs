@@ -0,0 +1,126 @@
+use chrono::NaiveDate;
+use colored::Colorize;
+
+use crate::eval::Entry;
+use crate::files::Files;
+
+use super::show;
+
+/// Why an entry is shown on a day.
+enum OnDay {
+    /// The entry takes place on the day.
+    Occurs,
+    /// The entry starts this many days later, but the user wants to be
+    /// reminded of it.
+    Reminder(i64),
+}
+
+/// Why `entry` is shown on `date`, or `None` if it shouldn't be shown.
+fn on_day(entry: &Entry, date: NaiveDate) -> Option<OnDay> {
+    let (start, end) = entry.dates?.sorted().dates();
+    if start <= date && date <= end {
+        Some(OnDay::Occurs)
+    } else {
+        match entry.remind {
+            Some(remind) if remind <= date && date < start => {
+                Some(OnDay::Reminder((start - date).num_days()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Format the entries taking place on `date` and the entries explicitly
+/// reminded of on `date` in detail.
+///
+/// Entries are ordered by their start. On the same day, entries without time
+/// come before timed entries. Since reminders start after `date`, they come
+/// last and are marked as such.
+fn fmt_day(files: &Files, entries: &[Entry], date: NaiveDate, markdown: bool) -> String {
+    let mut entries = entries
+        .iter()
+        .filter_map(|entry| Some((entry, on_day(entry, date)?)))
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|(e, _)| e.dates.map(|d| d.sorted().root_with_time()));
+
+    let mut result = format!("{}\n", format!("{} {}", date.format("%a"), date).bold());
+    if entries.is_empty() {
+        result.push_str("\nNothing to do\n");
+    }
+    for (entry, on_day) in entries {
+        result.push('\n');
+        if let OnDay::Reminder(days) = on_day {
+            let when = if days == 1 {
+                "tomorrow".to_string()
+            } else {
+                format!("in {days} days")
+            };
+            result.push_str(&format!("{} {when}\n", "Reminder:".bright_black()));
+        }
+        result.push_str(&show::fmt_entry(files, entry, 0, markdown));
+    }
+    result
+}
+
+pub fn day(files: &Files, entries: &[Entry], date: NaiveDate, markdown: bool) {
+    print!("{}", fmt_day(files, entries, date, markdown));
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::eval::DateRange;
+    use crate::testing;
+
+    use super::fmt_day;
+
+    #[test]
+    fn timed_and_untimed() {
+        let _colors = testing::colors(false);
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let (files, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\n\
+             TASK Meeting\nDATE 2024-01-15 10:00 -- 11:00\n# Bring slides\n\n\
+             NOTE Holiday\nDATE 2024-01-15\n# No work today\n",
+            DateRange::new(date, date),
+        );
+        let text = fmt_day(&files, &entries, date, true);
+
+        assert!(text.starts_with("Mon 2024-01-15\n"));
+        assert!(text.contains("When:  2024-01-15 10:00 -- 11:00\n"));
+        assert!(text.contains("When:  2024-01-15\n"));
+        assert!(text.contains("\nBring slides\n"));
+        assert!(text.contains("\nNo work today\n"));
+
+        // Untimed entries come before timed ones
+        let holiday = text.find("Holiday").unwrap();
+        let meeting = text.find("Meeting").unwrap();
+        assert!(holiday < meeting);
+    }
+
+    #[test]
+    fn only_day_and_reminders() {
+        let _colors = testing::colors(false);
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let (files, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\n\
+             TASK Taxes\nREMIND -1w\nDATE 2024-01-18\n\n\
+             TASK Laundry\nDATE 2024-01-12\n\n\
+             TASK Someday\n\n\
+             NOTE Holiday\nDATE 2024-01-15\n",
+            DateRange::new(date, date),
+        );
+        let text = fmt_day(&files, &entries, date, true);
+
+        assert!(text.contains("Holiday"));
+        assert!(text.contains("\nReminder: in 3 days\nTitle: T Taxes\n"));
+        assert!(!text.contains("Laundry"));
+        assert!(!text.contains("Someday"));
+
+        // Reminders come after the day's entries
+        assert!(text.find("Holiday").unwrap() < text.find("Taxes").unwrap());
+    }
+}
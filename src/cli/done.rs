@@ -1,8 +1,8 @@
 use std::vec;
 
-use chrono::NaiveDateTime;
+use chrono::NaiveDate;
 
-use crate::eval::Entry;
+use crate::eval::{self, Entry};
 use crate::files::commands::{Done, DoneKind};
 use crate::files::Files;
 
@@ -14,17 +14,27 @@ pub fn done(
     entries: &[Entry],
     layout: &LineLayout,
     numbers: &[usize],
-    now: NaiveDateTime,
+    done_at: NaiveDate,
 ) -> Result<()> {
     let mut not_tasks = vec![];
     for &number in numbers {
         let entry = &entries[layout.look_up_number(number)?];
+        // Progress applies to the whole task, so for repeating tasks it starts
+        // over with the next occurrence.
+        let next = match entry.root() {
+            Some(root) => eval::next_occurrence(files, entry.source, root)?,
+            None => None,
+        };
+        let progress = if next.is_some() { 0 } else { 100 };
         let done = Done {
             kind: DoneKind::Done,
             date: entry.dates.map(|dates| dates.into()),
-            done_at: now.date(),
+            done_at,
+            spent: None,
         };
-        if !files.add_done(entry.source, done) {
+        if files.add_done(entry.source, done) {
+            files.set_progress(entry.source, progress);
+        } else {
             not_tasks.push(number);
         }
     }
@@ -35,3 +45,89 @@ pub fn done(
         Err(Error::NotATask(not_tasks))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io};
+
+    use chrono::NaiveDate;
+
+    use crate::cli::layout::{self, LayoutOptions};
+    use crate::eval::{DateRange, EntryMode};
+    use crate::files::Files;
+    use crate::testing::TempDir;
+
+    use super::super::parse_eval_date;
+    use super::done;
+
+    #[test]
+    fn done_at() {
+        let dir = TempDir::new();
+        let path = dir.write(
+            "main.today",
+            "TIMEZONE UTC\n\nTASK Laundry\nDATE 2024-01-14\n",
+        );
+
+        let mut files = Files::new();
+        files.load(&path).unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let range = DateRange::new(today, today);
+        let entries = files.eval(EntryMode::Relevant, range).unwrap();
+        let now = today.and_hms_opt(12, 0, 0).unwrap();
+        let layout = layout::layout(&files, &entries, range, now, LayoutOptions::default());
+
+        let at = parse_eval_date("at", "t-1d", today).unwrap();
+        done(&mut files, &entries, &layout, &[1], at).unwrap();
+        let changed = files.save(false, &mut io::sink()).unwrap();
+        assert_eq!(changed, [path.clone()]);
+
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.contains("\nDONE [2024-01-14] 2024-01-14\n"));
+    }
+
+    fn done_with_progress(text: &str) -> String {
+        let dir = TempDir::new();
+        let path = dir.write("main.today", text);
+
+        let mut files = Files::new();
+        files.load(&path).unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let range = DateRange::new(today, today);
+        let entries = files.eval(EntryMode::Relevant, range).unwrap();
+        assert_eq!(entries[0].progress, Some(60));
+        let now = today.and_hms_opt(12, 0, 0).unwrap();
+        let layout = layout::layout(&files, &entries, range, now, LayoutOptions::default());
+
+        done(&mut files, &entries, &layout, &[1], today).unwrap();
+        files.save(false, &mut io::sink()).unwrap();
+        fs::read_to_string(&path).unwrap()
+    }
+
+    #[test]
+    fn done_sets_progress() {
+        let text =
+            done_with_progress("TIMEZONE UTC\n\nTASK Thesis\nDATE 2024-01-15\nPROGRESS 60%\n");
+        assert!(text.contains("\nPROGRESS 100%\n"));
+        assert!(!text.contains("60%"));
+    }
+
+    #[test]
+    fn done_resets_progress_of_repeating_task() {
+        let text =
+            done_with_progress("TIMEZONE UTC\n\nTASK Report\nDATE 2024-01-15; +w\nPROGRESS 60%\n");
+        assert!(text.contains("\nDONE [2024-01-15] 2024-01-15\n"));
+        assert!(text.contains("\nPROGRESS 0%\n"));
+
+        // The next occurrence doesn't inherit the completed one's progress
+        let mut files = Files::new();
+        let dir = TempDir::new();
+        files.load(&dir.write("main.today", &text)).unwrap();
+        let next = NaiveDate::from_ymd_opt(2024, 1, 22).unwrap();
+        let entries = files
+            .eval(EntryMode::Relevant, DateRange::new(next, next))
+            .unwrap();
+        assert_eq!(entries[0].progress, Some(0));
+    }
+}
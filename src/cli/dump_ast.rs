@@ -0,0 +1,82 @@
+use serde::Serialize;
+
+use crate::files::commands::Command;
+use crate::files::primitives::Spanned;
+use crate::files::Files;
+
+#[derive(Serialize)]
+struct DumpedFile<'a> {
+    name: String,
+    commands: &'a [Spanned<Command>],
+}
+
+#[derive(Serialize)]
+struct Dump<'a> {
+    files: Vec<DumpedFile<'a>>,
+}
+
+/// The syntax trees of all loaded files, including source spans.
+fn fmt_ast(files: &Files) -> String {
+    let files = files
+        .parsed()
+        .into_iter()
+        .map(|(path, file)| DumpedFile {
+            name: path.to_string_lossy().to_string(),
+            commands: &file.commands,
+        })
+        .collect();
+    serde_json::to_string(&Dump { files }).expect("syntax tree can always be serialized")
+}
+
+pub fn dump_ast(files: &Files) {
+    println!("{}", fmt_ast(files));
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::testing;
+
+    use super::fmt_ast;
+
+    #[test]
+    fn task_with_date() {
+        let files = testing::load("TIMEZONE UTC\nTASK Laundry\nDATE 2024-01-05 10:00\n");
+        let json: Value = serde_json::from_str(&fmt_ast(&files)).unwrap();
+
+        let command = &json["files"][0]["commands"][1];
+        assert_eq!(command["span"]["start"], 13);
+        assert_eq!(command["span"]["end"], 48);
+        let task = &command["value"]["Task"];
+        assert_eq!(task["title"], "Laundry");
+
+        // The DATE's span covers the whole statement
+        let date = &task["statements"][0]["Date"];
+        assert_eq!(date["span"]["start"], 26);
+        assert_eq!(date["span"]["end"], 48);
+        let spec = &date["value"]["Date"];
+        assert_eq!(spec["start"], "2024-01-05");
+        assert_eq!(spec["start_time"]["hour"], 10);
+        assert_eq!(spec["start_time"]["min"], 0);
+        assert_eq!(spec["repeat"], Value::Null);
+    }
+
+    #[test]
+    fn multiple_files() {
+        let files = testing::load_files(&[
+            ("main.today", "TIMEZONE UTC\nINCLUDE other.today\n"),
+            ("other.today", "NOTE Party\nDATE 2024-01-06\n"),
+        ]);
+        let json: Value = serde_json::from_str(&fmt_ast(&files)).unwrap();
+
+        let files = json["files"].as_array().unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files[0]["name"].as_str().unwrap().ends_with("main.today"));
+        let commands = &files[0]["commands"];
+        assert_eq!(commands[0]["value"]["Timezone"]["value"], "UTC");
+        assert_eq!(commands[1]["value"]["Include"]["value"], "other.today");
+        assert!(files[1]["name"].as_str().unwrap().ends_with("other.today"));
+        assert_eq!(files[1]["commands"][0]["value"]["Note"]["title"], "Party");
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+use std::process::ExitStatus;
 use std::{io, result};
 
 use chrono::NaiveDate;
@@ -30,8 +32,38 @@ pub enum Error {
     NotATask(Vec<usize>),
     #[error("No capture file found")]
     NoCaptureFile,
+    #[error("No template named {0}")]
+    NoSuchTemplate(String),
+    #[error("{0}")]
+    TemplateParse(files::ParseError<FileSource>),
+    #[error("Template {0} takes no arguments")]
+    TemplateArguments(String),
+    #[error("No loaded file at {0}")]
+    NoSuchSource(PathBuf),
     #[error("Error editing: {0}")]
     EditingIo(io::Error),
+    #[error("Could not resolve time zone {tz}: {error}")]
+    ResolveTz { tz: String, error: io::Error },
+    #[error("Could not read {file}: {error}")]
+    ReadImport { file: PathBuf, error: io::Error },
+    #[error("Could not read {file}: {error}")]
+    ReadState { file: PathBuf, error: io::Error },
+    #[error("Could not write {file}: {error}")]
+    WriteState { file: PathBuf, error: io::Error },
+    #[error("Could not read {file}: {error}")]
+    ReadConfig { file: PathBuf, error: io::Error },
+    #[error("Invalid config in {file} on line {line}: {message}")]
+    InvalidConfig {
+        file: String,
+        line: usize,
+        message: String,
+    },
+    #[error("Could not run {command}: {error}")]
+    RunHook { command: String, error: io::Error },
+    #[error("{command} failed with {status}")]
+    HookFailed { command: String, status: ExitStatus },
+    #[error("Found {0} problem(s)")]
+    ProblemsFound(usize),
 }
 
 pub type Result<T> = result::Result<T, Error>;
@@ -59,10 +91,54 @@ where
                 }
             }
             Self::NoCaptureFile => eprintln!("No capture file found"),
+            Self::NoSuchTemplate(name) => eprintln!("No template named {name}"),
+            Self::TemplateParse(error) => error.eprint(files, config),
+            Self::TemplateArguments(name) => eprintln!("Template {name} takes no arguments"),
+            Self::NoSuchSource(path) => eprintln!("No loaded file at {}", path.display()),
             Self::EditingIo(error) => {
                 eprintln!("Error while editing:");
                 eprintln!("  {error}");
             }
+            Self::ResolveTz { tz, error } => {
+                eprintln!("Could not resolve time zone {tz}:");
+                eprintln!("  {error}");
+            }
+            Self::ReadImport { file, error }
+            | Self::ReadState { file, error }
+            | Self::ReadConfig { file, error } => {
+                eprintln!("Could not read {}:", file.display());
+                eprintln!("  {error}");
+            }
+            Self::WriteState { file, error } => {
+                eprintln!("Could not write {}:", file.display());
+                eprintln!("  {error}");
+            }
+            Self::InvalidConfig {
+                file,
+                line,
+                message,
+            } => {
+                eprintln!("Invalid config in {file} on line {line}:");
+                eprintln!("  {message}");
+            }
+            Self::RunHook { command, error } => {
+                eprintln!("Could not run {command}:");
+                eprintln!("  {error}");
+            }
+            Self::HookFailed { command, status } => eprintln!("{command} failed with {status}"),
+            Self::ProblemsFound(1) => eprintln!("Found 1 problem"),
+            Self::ProblemsFound(n) => eprintln!("Found {n} problems"),
+        }
+    }
+
+    #[allow(single_use_lifetimes)]
+    fn json<'f: 'a>(&self, files: &'f F) -> String {
+        match self {
+            Self::Eval(e) => e.json(files),
+            Self::ArgumentParse { file, error } => error.json(file),
+            Self::ArgumentEval { file, error } => error.json(file),
+            Self::TemplateParse(error) => error.json(files),
+            _ => crate::error::json_message(&self.to_string()),
         }
     }
 }
@@ -0,0 +1,94 @@
+use std::fmt::Write;
+
+use chrono::NaiveDate;
+use colored::Colorize;
+
+use crate::eval::{self, DateRange, Entry};
+use crate::files::Files;
+
+use super::error::Result;
+use super::layout::line::LineLayout;
+
+/// Format the steps taken to evaluate an entry's command within `range`.
+pub fn fmt_explain(
+    files: &Files,
+    entry: &Entry,
+    range: DateRange,
+    future_from: Option<NaiveDate>,
+) -> Result<String> {
+    let mut result = String::new();
+    writeln!(
+        result,
+        "{} {} within {} -- {}",
+        "Explaining".bright_black(),
+        entry.title,
+        range.from(),
+        range.until()
+    )
+    .unwrap();
+    for step in eval::explain(files, entry.source, range, future_from)? {
+        writeln!(result, "  {step}").unwrap();
+    }
+    Ok(result)
+}
+
+pub fn explain(
+    files: &Files,
+    entries: &[Entry],
+    layout: &LineLayout,
+    number: usize,
+    range: DateRange,
+    future_from: Option<NaiveDate>,
+) -> Result<()> {
+    let entry = &entries[layout.look_up_number(number)?];
+    print!("{}", fmt_explain(files, entry, range, future_from)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::eval::DateRange;
+    use crate::testing;
+
+    use super::fmt_explain;
+
+    #[test]
+    fn recurring_task() {
+        let _colors = testing::colors(false);
+
+        let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let until = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let range = DateRange::new(from, until);
+        let (files, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\nTASK Laundry\nDATE 2024-01-01; +w\nEXCEPT 2024-01-08\n",
+            range,
+        );
+        let text = fmt_explain(&files, &entries[0], range, None).unwrap();
+        let lines = text.lines().collect::<Vec<_>>();
+
+        assert_eq!(
+            lines,
+            [
+                "Explaining Laundry within 2024-01-01 -- 2024-01-15",
+                "  DATE 2024-01-01; +w",
+                "  Range 2024-01-01 -- 2024-01-15",
+                "  Occurrence 2024-01-01",
+                "  Occurrence 2024-01-08",
+                "  Occurrence 2024-01-15",
+                "  EXCEPT 2024-01-08",
+                "  Removed occurrence on 2024-01-08",
+                "  Result 2024-01-01",
+                "  Result 2024-01-15",
+            ]
+        );
+
+        // Past occurrences are dropped when only showing future ones
+        let today = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+        let text = fmt_explain(&files, &entries[0], range, Some(today)).unwrap();
+        assert!(text.contains("  Occurrence 2024-01-01 is in the past\n"));
+        assert!(text.ends_with("  Result 2024-01-15\n"));
+        assert!(!text.contains("  Result 2024-01-01\n"));
+    }
+}
@@ -0,0 +1,179 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use crate::eval::Entry;
+use crate::files::primitives::Time;
+
+use super::util::stable_hash;
+
+/// Maximum length of a content line in octets, excluding the line break.
+const MAX_LINE_LEN: usize = 75;
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+}
+
+fn fmt_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+fn fmt_date_time(date: NaiveDate, time: Time) -> String {
+    format!("{}T{:02}{:02}00Z", fmt_date(date), time.hour, time.min)
+}
+
+/// Append a content line, folding it into multiple lines if it is too long.
+/// Lines are only split between characters, never inside one.
+fn push_line(result: &mut String, line: &str) {
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > MAX_LINE_LEN {
+            // Continuation lines start with a space, which counts towards
+            // their length
+            result.push_str("\r\n ");
+            len = 1;
+        }
+        result.push(c);
+        len += c.len_utf8();
+    }
+    result.push_str("\r\n");
+}
+
+/// Format all dated entries as events of an iCalendar (.ics) file.
+///
+/// Times are exported as UTC times, so the entries' dates must already be
+/// converted to UTC. `stamp` is the time the file is created at. UIDs only depend on an entry's title and dates so that calendar
+/// applications recognize the same events when importing them again.
+pub fn fmt_ical(entries: &[Entry], stamp: DateTime<Utc>) -> String {
+    let stamp = stamp.format("%Y%m%dT%H%M%SZ");
+
+    let mut result = String::new();
+    push_line(&mut result, "BEGIN:VCALENDAR");
+    push_line(&mut result, "VERSION:2.0");
+    push_line(&mut result, "PRODID:-//today//EN");
+    for entry in entries {
+        let dates = match entry.dates {
+            Some(dates) => dates.sorted(),
+            None => continue,
+        };
+        let (start, end) = dates.dates();
+        let uid = stable_hash(&format!("{dates} {}", entry.title));
+
+        push_line(&mut result, "BEGIN:VEVENT");
+        push_line(
+            &mut result,
+            &format!("UID:{}-{uid:016x}@today", fmt_date(start)),
+        );
+        push_line(&mut result, &format!("DTSTAMP:{stamp}"));
+        push_line(&mut result, &format!("SUMMARY:{}", escape(&entry.title)));
+        match dates.times() {
+            Some((start_time, end_time)) => {
+                let start = fmt_date_time(start, start_time);
+                let end = fmt_date_time(end, end_time);
+                push_line(&mut result, &format!("DTSTART:{start}"));
+                push_line(&mut result, &format!("DTEND:{end}"));
+            }
+            None => {
+                // The end date of all-day events is exclusive
+                let end = end + Duration::days(1);
+                push_line(
+                    &mut result,
+                    &format!("DTSTART;VALUE=DATE:{}", fmt_date(start)),
+                );
+                push_line(&mut result, &format!("DTEND;VALUE=DATE:{}", fmt_date(end)));
+            }
+        }
+        push_line(&mut result, "END:VEVENT");
+    }
+    push_line(&mut result, "END:VCALENDAR");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use crate::cli::convert_entries;
+    use crate::eval::DateRange;
+    use crate::testing;
+
+    use super::{fmt_ical, push_line};
+
+    #[test]
+    fn whole_calendar() {
+        let from = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        let until = NaiveDate::from_ymd_opt(2024, 1, 6).unwrap();
+        let (files, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\n\
+             TASK Laundry, then ironing\nDATE 2024-01-05\n\n\
+             NOTE Call\nDATE 2024-01-06 10:00 -- 10:30\n\n\
+             TASK Someday\n",
+            DateRange::new(from, until),
+        );
+        let mut entries = convert_entries(&files, &entries, &Utc).unwrap();
+        entries.sort_by_key(|entry| entry.root());
+        let stamp = Utc.with_ymd_and_hms(2024, 1, 1, 8, 30, 0).unwrap();
+
+        let ical = fmt_ical(&entries, stamp);
+        let uid = |line: &str| line.strip_prefix("UID:").map(|uid| uid.to_string());
+        let uids = ical.lines().filter_map(uid).collect::<Vec<_>>();
+        assert_eq!(uids.len(), 2);
+        assert_eq!(
+            ical,
+            format!(
+                "BEGIN:VCALENDAR\r\n\
+                 VERSION:2.0\r\n\
+                 PRODID:-//today//EN\r\n\
+                 BEGIN:VEVENT\r\n\
+                 UID:{}\r\n\
+                 DTSTAMP:20240101T083000Z\r\n\
+                 SUMMARY:Laundry\\, then ironing\r\n\
+                 DTSTART;VALUE=DATE:20240105\r\n\
+                 DTEND;VALUE=DATE:20240106\r\n\
+                 END:VEVENT\r\n\
+                 BEGIN:VEVENT\r\n\
+                 UID:{}\r\n\
+                 DTSTAMP:20240101T083000Z\r\n\
+                 SUMMARY:Call\r\n\
+                 DTSTART:20240106T100000Z\r\n\
+                 DTEND:20240106T103000Z\r\n\
+                 END:VEVENT\r\n\
+                 END:VCALENDAR\r\n",
+                uids[0], uids[1]
+            )
+        );
+
+        // UIDs don't depend on the position of the entries
+        assert!(uids[0].starts_with("20240105-"));
+        entries.reverse();
+        let reversed = fmt_ical(&entries, stamp);
+        let reversed_uids = reversed.lines().filter_map(uid).collect::<Vec<_>>();
+        assert_eq!(reversed_uids, [uids[1].clone(), uids[0].clone()]);
+    }
+
+    #[test]
+    fn times_in_utc() {
+        let date = NaiveDate::from_ymd_opt(2024, 7, 1).unwrap();
+        let (files, entries) = testing::load_eval(
+            "TIMEZONE Europe/Berlin\n\nNOTE Call\nDATE 2024-07-01 23:30 -- 2024-07-02 00:30\n",
+            DateRange::new(date, date),
+        );
+        let entries = convert_entries(&files, &entries, &Utc).unwrap();
+        let stamp = Utc.with_ymd_and_hms(2024, 1, 1, 8, 30, 0).unwrap();
+
+        let ical = fmt_ical(&entries, stamp);
+        assert!(ical.contains("DTSTART:20240701T213000Z\r\nDTEND:20240701T223000Z\r\n"));
+    }
+
+    #[test]
+    fn fold_long_lines() {
+        let mut result = String::new();
+        push_line(&mut result, &format!("SUMMARY:{}", "ä".repeat(40)));
+        let lines = result.split("\r\n").collect::<Vec<_>>();
+        // 8 octets of "SUMMARY:" and 33 two-octet characters fit into 75 octets
+        assert_eq!(lines[0], format!("SUMMARY:{}", "ä".repeat(33)));
+        assert_eq!(lines[1], format!(" {}", "ä".repeat(7)));
+        assert_eq!(lines[2], "");
+        assert!(lines.iter().all(|line| line.len() <= 75));
+    }
+}
@@ -0,0 +1,112 @@
+use std::fmt::Write;
+
+use colored::Colorize;
+
+use crate::eval::Entry;
+
+use super::layout::line::LineLayout;
+use super::util;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortKey {
+    /// By start date and time, undated entries last
+    Date,
+    /// Alphabetically by title, ignoring case
+    Title,
+}
+
+/// Format entries as a flat list sorted by `key`, one line per entry.
+///
+/// Entries keep the display numbers of the regular layout so they can still be
+/// referred to by commands like `done`.
+pub fn fmt_flat(entries: &[Entry], layout: &LineLayout, key: SortKey) -> String {
+    let mut indices = (0..entries.len()).collect::<Vec<_>>();
+    let by_date = |index: &usize| {
+        let dates = entries[*index].dates;
+        (
+            dates.is_none(),
+            dates.map(|dates| dates.sorted().root_with_time()),
+        )
+    };
+    match key {
+        SortKey::Date => indices.sort_by_key(by_date),
+        SortKey::Title => {
+            indices.sort_by_key(|index| (entries[*index].title.to_lowercase(), by_date(index)))
+        }
+    }
+
+    let num_width = layout.num_width();
+    let mut result = String::new();
+    for index in indices {
+        let entry = &entries[index];
+        let num = match layout.number(index) {
+            Some(n) => format!("{n}"),
+            None => "".to_string(),
+        };
+        let when = match entry.dates {
+            Some(dates) => format!(" {}", dates.sorted()).bright_black(),
+            None => "".into(),
+        };
+        writeln!(
+            result,
+            "{:>nw$} {}{} {}",
+            num.bright_black(),
+            util::display_kind(LineLayout::entry_kind(entry)),
+            when,
+            entry.title,
+            nw = num_width,
+        )
+        .unwrap();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::cli::layout::{self, LayoutOptions};
+    use crate::eval::DateRange;
+    use crate::testing;
+
+    use super::{fmt_flat, SortKey};
+
+    fn titles(key: SortKey) -> Vec<String> {
+        let _colors = testing::colors(false);
+
+        let from = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let until = NaiveDate::from_ymd_opt(2024, 1, 16).unwrap();
+        let range = DateRange::new(from, until);
+        let (files, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\n\
+             TASK laundry\nDATE 2024-01-16\n\n\
+             TASK Meeting\nDATE 2024-01-15 10:00\n\n\
+             NOTE Backlog\n\n\
+             TASK Call\nDATE 2024-01-15 09:00\n",
+            range,
+        );
+        let now = from.and_hms_opt(12, 0, 0).unwrap();
+        let layout = layout::layout(&files, &entries, range, now, LayoutOptions::default());
+
+        fmt_flat(&entries, &layout, key)
+            .lines()
+            .map(|line| line.rsplit(' ').next().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn sort_by_date() {
+        assert_eq!(
+            titles(SortKey::Date),
+            ["Call", "Meeting", "laundry", "Backlog"]
+        );
+    }
+
+    #[test]
+    fn sort_by_title() {
+        assert_eq!(
+            titles(SortKey::Title),
+            ["Backlog", "Call", "laundry", "Meeting"]
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use colored::Colorize;
+
+use crate::eval::Entry;
+use crate::files::Files;
+
+use super::layout::line::LineLayout;
+use super::util;
+
+/// Format entries grouped by the file they come from, one line per entry.
+///
+/// Entries keep the display numbers of the regular layout so they can still be
+/// referred to by commands like `done`.
+pub fn fmt_grouped(files: &Files, entries: &[Entry], layout: &LineLayout) -> String {
+    let mut groups = BTreeMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        groups
+            .entry(entry.source.file())
+            .or_insert_with(Vec::new)
+            .push(index);
+    }
+
+    let num_width = layout.num_width();
+    let mut result = String::new();
+    for (i, (file, mut indices)) in groups.into_iter().enumerate() {
+        if i > 0 {
+            result.push('\n');
+        }
+        let name = files.path(file).to_string_lossy();
+        writeln!(result, "{}", name.bold()).unwrap();
+
+        indices.sort_by_key(|&index| {
+            let dates = entries[index].dates;
+            (
+                dates.is_some(),
+                dates.map(|dates| dates.sorted().root_with_time()),
+            )
+        });
+        for index in indices {
+            let entry = &entries[index];
+            let num = match layout.number(index) {
+                Some(n) => format!("{n}"),
+                None => "".to_string(),
+            };
+            let when = match entry.dates {
+                Some(dates) => format!(" {}", dates.sorted()).bright_black(),
+                None => "".into(),
+            };
+            writeln!(
+                result,
+                "{:>nw$} {}{} {}",
+                num.bright_black(),
+                util::display_kind(LineLayout::entry_kind(entry)),
+                when,
+                entry.title,
+                nw = num_width,
+            )
+            .unwrap();
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::cli::layout::{self, LayoutOptions};
+    use crate::eval::{DateRange, EntryMode};
+    use crate::testing;
+
+    use super::fmt_grouped;
+
+    #[test]
+    fn two_files() {
+        let _colors = testing::colors(false);
+
+        let files = testing::load_files(&[
+            (
+                "main.today",
+                "TIMEZONE UTC\nINCLUDE work.today\n\nTASK Laundry\nDATE 2024-01-15\n",
+            ),
+            (
+                "work.today",
+                "TASK Meeting\nDATE 2024-01-15 10:00\n\nNOTE Review\n",
+            ),
+        ]);
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let range = DateRange::new(date, date);
+        let entries = files.eval(EntryMode::Relevant, range).unwrap();
+        let now = date.and_hms_opt(12, 0, 0).unwrap();
+        let layout = layout::layout(
+            &files,
+            &entries,
+            range,
+            now,
+            LayoutOptions {
+                show_now: false,
+                ..Default::default()
+            },
+        );
+        let text = fmt_grouped(&files, &entries, &layout);
+
+        let main = text.find("main.today\n").unwrap();
+        let work = text.find("work.today\n").unwrap();
+        let laundry = text.find("Laundry").unwrap();
+        let meeting = text.find("Meeting").unwrap();
+        let review = text.find("Review").unwrap();
+        assert!(main < laundry && laundry < work);
+        assert!(work < review && review < meeting);
+        assert!(text.contains(" T 2024-01-15 10:00"));
+    }
+}
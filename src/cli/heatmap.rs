@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDate};
+use colored::{ColoredString, Colorize};
+
+use crate::eval::{DateRange, Entry};
+use crate::files::primitives::Weekday;
+
+use super::locale::Locale;
+
+/// Glyphs for the buckets returned by [`bucket`], from no entries to many.
+const GLYPHS: [&str; 5] = ["·", "░", "▒", "▓", "█"];
+
+/// The range of days of the month `date` lies in.
+pub fn month_range(date: NaiveDate) -> DateRange {
+    let first = date.with_day(1).unwrap();
+    let last = match first.month() {
+        12 => NaiveDate::from_ymd_opt(first.year() + 1, 1, 1),
+        m => NaiveDate::from_ymd_opt(first.year(), m + 1, 1),
+    }
+    .unwrap()
+    .pred_opt()
+    .unwrap();
+    DateRange::new(first, last)
+}
+
+/// Count how many entries touch each day of the range.
+fn count_entries(entries: &[Entry], range: DateRange) -> HashMap<NaiveDate, usize> {
+    let mut counts = HashMap::new();
+    for entry in entries {
+        if let Some(dates) = entry.dates {
+            let (start, end) = dates.sorted().dates();
+            for day in range.days().filter(|d| start <= *d && *d <= end) {
+                *counts.entry(day).or_insert(0) += 1;
+            }
+        }
+    }
+    counts
+}
+
+fn bucket(count: usize) -> usize {
+    match count {
+        0 => 0,
+        1..=2 => 1,
+        3..=5 => 2,
+        6..=9 => 3,
+        _ => 4,
+    }
+}
+
+fn glyph(count: usize) -> ColoredString {
+    let bucket = bucket(count);
+    let glyph = GLYPHS[bucket];
+    match bucket {
+        0 => glyph.bright_black(),
+        1 | 2 => glyph.green(),
+        _ => glyph.bright_green(),
+    }
+}
+
+pub fn heatmap(entries: &[Entry], range: DateRange, locale: Locale) {
+    let counts = count_entries(entries, range);
+    let first = range.from();
+
+    let month = format!("{} {}", locale.month(first.month()), first.year());
+    println!("{}", month.bold());
+    let weekdays = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+    let weekdays = weekdays.map(|wd| locale.weekday_short(wd));
+    println!("{}", weekdays.join(" "));
+
+    let offset = first.weekday().num_days_from_monday() as usize;
+    let mut line = "   ".repeat(offset);
+    for day in range.days() {
+        let count = counts.get(&day).copied().unwrap_or(0);
+        line.push_str(&format!("{}  ", glyph(count)));
+        if day.weekday().num_days_from_monday() == 6 {
+            println!("{}", line.trim_end());
+            line.clear();
+        }
+    }
+    if !line.is_empty() {
+        println!("{}", line.trim_end());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::eval::{Dates, Entry, EntryKind};
+    use crate::files::Source;
+
+    use super::{bucket, count_entries, month_range, GLYPHS};
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn buckets() {
+        let glyph = |count| GLYPHS[bucket(count)];
+        assert_eq!(glyph(0), "·");
+        assert_eq!(glyph(1), "░");
+        assert_eq!(glyph(2), "░");
+        assert_eq!(glyph(3), "▒");
+        assert_eq!(glyph(5), "▒");
+        assert_eq!(glyph(6), "▓");
+        assert_eq!(glyph(9), "▓");
+        assert_eq!(glyph(10), "█");
+        assert_eq!(glyph(100), "█");
+    }
+
+    #[test]
+    fn counts() {
+        let entry = |from, until| {
+            Entry::new(
+                Source::new(0, 0),
+                EntryKind::Note,
+                "Note".to_string(),
+                false,
+                Some(Dates::new(from, until)),
+                None,
+            )
+        };
+        let entries = [
+            entry(ymd(2024, 2, 1), ymd(2024, 2, 1)),
+            entry(ymd(2024, 2, 1), ymd(2024, 2, 2)),
+            entry(ymd(2024, 1, 31), ymd(2024, 2, 1)),
+            entry(ymd(2024, 3, 1), ymd(2024, 3, 1)),
+        ];
+
+        let range = month_range(ymd(2024, 2, 14));
+        assert_eq!(range.from(), ymd(2024, 2, 1));
+        assert_eq!(range.until(), ymd(2024, 2, 29));
+
+        let counts = count_entries(&entries, range);
+        assert_eq!(counts.get(&ymd(2024, 2, 1)), Some(&3));
+        assert_eq!(counts.get(&ymd(2024, 2, 2)), Some(&1));
+        assert_eq!(counts.get(&ymd(2024, 2, 3)), None);
+        assert_eq!(counts.len(), 2);
+    }
+}
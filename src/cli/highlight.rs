@@ -0,0 +1,155 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use codespan_reporting::files::SimpleFile;
+
+use crate::eval::Entry;
+use crate::files::cli::CliExpr;
+use crate::files::Files;
+
+use super::error::{Error, Result};
+
+/// Which entries to emphasize, given using `--highlight` or in the highlight
+/// config file.
+pub enum Rule {
+    /// Entries with this tag.
+    Tag(String),
+    /// Entries whose root date satisfies this formula, like `(wd = fri)`.
+    Formula {
+        /// Where the rule comes from, for error messages.
+        name: String,
+        text: String,
+        expr: CliExpr,
+    },
+}
+
+impl Rule {
+    /// Parse a formula if `text` starts with a parenthesis, otherwise use it
+    /// as tag. The `name` describes where the rule comes from.
+    pub fn parse(name: &str, text: &str) -> Result<Self> {
+        if !text.starts_with('(') {
+            return Ok(Self::Tag(text.to_string()));
+        }
+        let expr = text.parse().map_err(|error| Error::ArgumentParse {
+            file: SimpleFile::new(name.to_string(), text.to_string()),
+            error,
+        })?;
+        Ok(Self::Formula {
+            name: name.to_string(),
+            text: text.to_string(),
+            expr,
+        })
+    }
+
+    fn matches(&self, files: &Files, entry: &Entry) -> Result<bool> {
+        match self {
+            Self::Tag(tag) => {
+                let tags = files.command(entry.source).value.value.tags();
+                Ok(tags.contains(&tag.as_str()))
+            }
+            Self::Formula { name, text, expr } => match entry.root() {
+                Some(date) => {
+                    expr.eval((), date, files.fiscal_year())
+                        .map_err(|error| Error::ArgumentEval {
+                            file: SimpleFile::new(name.clone(), text.clone()),
+                            error,
+                        })
+                }
+                None => Ok(false),
+            },
+        }
+    }
+}
+
+/// The rules in the file at `path`, one per line. Empty lines are ignored.
+///
+/// Returns no rules if the file doesn't exist.
+pub fn load(path: &Path) -> Result<Vec<Rule>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => {
+            return Err(Error::ReadConfig {
+                file: path.to_path_buf(),
+                error,
+            })
+        }
+    };
+    let name = path.display().to_string();
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| Rule::parse(&name, line))
+        .collect()
+}
+
+/// Indices of all entries that match at least one of the rules.
+pub fn find_highlighted(
+    files: &Files,
+    entries: &[Entry],
+    rules: &[Rule],
+) -> Result<HashSet<usize>> {
+    let mut result = HashSet::new();
+    for (index, entry) in entries.iter().enumerate() {
+        for rule in rules {
+            if rule.matches(files, entry)? {
+                result.insert(index);
+                break;
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::eval::DateRange;
+    use crate::testing::{self, TempDir};
+
+    use super::super::error::Error;
+    use super::{find_highlighted, load, Rule};
+
+    #[test]
+    fn tags_and_formulas() {
+        let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let until = NaiveDate::from_ymd_opt(2024, 1, 7).unwrap();
+        let (files, mut entries) = testing::load_eval(
+            "TIMEZONE UTC\n\n\
+             TASK Taxes\nDATE 2024-01-02\nTAG urgent\n\n\
+             TASK Laundry\nDATE 2024-01-03\n\n\
+             NOTE Party\nDATE 2024-01-05\n",
+            DateRange::new(from, until),
+        );
+        entries.sort_by_key(|entry| entry.root());
+
+        let rules = [
+            Rule::parse("--highlight", "urgent").unwrap(),
+            Rule::parse("--highlight", "(wd = fri)").unwrap(),
+        ];
+        let mut highlighted = find_highlighted(&files, &entries, &rules)
+            .unwrap()
+            .into_iter()
+            .collect::<Vec<_>>();
+        highlighted.sort();
+        assert_eq!(highlighted, [0, 2]);
+
+        assert!(Rule::parse("--highlight", "(wd =)").is_err());
+    }
+
+    #[test]
+    fn load_rules() {
+        let dir = TempDir::new();
+        let path = dir.write("highlight", "urgent\n\n(d = 1)\n");
+        let rules = load(&path).unwrap();
+        assert!(matches!(&rules[..], [Rule::Tag(tag), Rule::Formula { .. }] if tag == "urgent"));
+
+        assert!(load(&dir.path().join("missing")).unwrap().is_empty());
+
+        // Other errors than a missing file are reported
+        assert!(matches!(load(dir.path()), Err(Error::ReadConfig { .. })));
+    }
+}
@@ -0,0 +1,104 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use super::error::{Error, Result};
+
+/// The command to run after saving, as configured in the file at `path`.
+///
+/// Returns `None` if the file doesn't exist or is empty.
+pub fn load(path: &Path) -> Result<Option<String>> {
+    let command = match fs::read_to_string(path) {
+        Ok(command) => command,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => {
+            return Err(Error::ReadConfig {
+                file: path.to_path_buf(),
+                error,
+            })
+        }
+    };
+    let command = command.trim();
+    if command.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(command.to_string()))
+    }
+}
+
+/// Run `command` in a shell if any files were `changed`, passing their paths
+/// as arguments.
+pub fn on_save(command: Option<&str>, changed: &[PathBuf]) -> Result<()> {
+    let command = match command {
+        Some(command) if !changed.is_empty() => command,
+        _ => return Ok(()),
+    };
+
+    let status = process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .arg("today") // $0
+        .args(changed)
+        .status()
+        .map_err(|error| Error::RunHook {
+            command: command.to_string(),
+            error,
+        })?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::HookFailed {
+            command: command.to_string(),
+            status,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::testing::TempDir;
+
+    use super::super::error::Error;
+    use super::{load, on_save};
+
+    #[test]
+    fn load_command() {
+        let dir = TempDir::new();
+        assert_eq!(load(&dir.path().join("on-save")).unwrap(), None);
+
+        let path = dir.write("on-save", "  git commit -am sync\n");
+        assert_eq!(load(&path).unwrap().as_deref(), Some("git commit -am sync"));
+
+        let path = dir.write("on-save", "\n");
+        assert_eq!(load(&path).unwrap(), None);
+
+        // Other errors than a missing file are reported
+        assert!(matches!(load(dir.path()), Err(Error::ReadConfig { .. })));
+    }
+
+    #[test]
+    fn runs_only_on_changes() {
+        let dir = TempDir::new();
+        let out = dir.path().join("out");
+        let command = format!("echo \"$@\" > '{}'", out.display());
+        let changed = [dir.path().join("main.today")];
+
+        on_save(Some(&command), &[]).unwrap();
+        assert!(!out.exists());
+
+        on_save(None, &changed).unwrap();
+        assert!(!out.exists());
+
+        on_save(Some(&command), &changed).unwrap();
+        let paths = fs::read_to_string(&out).unwrap();
+        assert_eq!(paths, format!("{}\n", changed[0].display()));
+
+        assert!(matches!(
+            on_save(Some("exit 3"), &changed),
+            Err(Error::HookFailed { .. })
+        ));
+    }
+}
@@ -0,0 +1,178 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+use crate::files::commands::{BirthdaySpec, CaptureKind, Command, Note, Statement};
+use crate::files::Files;
+
+use super::error::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportFormat {
+    /// Birthdays of contacts from a vCard (.vcf) file
+    #[value(name = "vcard")]
+    VCard,
+}
+
+/// Join folded vCard lines, which continue with a space or tab.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Decode the escape sequences of a vCard text value in a single pass, so
+/// that an escaped backslash can't start another escape sequence. Line breaks
+/// become spaces since titles must fit on one line.
+fn unescape(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => result.push(' '),
+            Some(c) => result.push(c),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Parse a vCard `BDAY` value like `19870514`, `1987-05-14` or, if the year
+/// is unknown, `--0921` or `--09-21`.
+fn parse_bday(value: &str) -> Option<BirthdaySpec> {
+    // Some clients include a time, which doesn't matter for birthdays
+    let date = value.split('T').next()?;
+    if let Some(month_day) = date.strip_prefix("--") {
+        let month_day = month_day.replace('-', "");
+        if month_day.len() != 4 {
+            return None;
+        }
+        let month = month_day[..2].parse().ok()?;
+        let day = month_day[2..].parse().ok()?;
+        // Year 0 is a leap year, so birthdays on February 29 work too
+        let date = NaiveDate::from_ymd_opt(0, month, day)?;
+        Some(BirthdaySpec {
+            date,
+            year_known: false,
+        })
+    } else {
+        let date = NaiveDate::parse_from_str(&date.replace('-', ""), "%Y%m%d").ok()?;
+        Some(BirthdaySpec {
+            date,
+            year_known: true,
+        })
+    }
+}
+
+/// A `NOTE` with a `BDATE` for each contact with a name and a valid birthday.
+fn vcard_birthdays(text: &str) -> Vec<Command> {
+    let mut commands = vec![];
+    let mut name = None;
+    let mut bday = None;
+    for line in unfold(text) {
+        let (property, value) = match line.split_once(':') {
+            Some(split) => split,
+            None => continue,
+        };
+        // Properties may have parameters (`BDAY;VALUE=date`) and a group
+        // prefix (`item1.BDAY`)
+        let property = property.split(';').next().unwrap_or_default();
+        let property = property.rsplit('.').next().unwrap_or_default();
+        match property.to_ascii_uppercase().as_str() {
+            "BEGIN" => {
+                name = None;
+                bday = None;
+            }
+            "FN" => name = Some(unescape(value).trim().to_string()),
+            "BDAY" => bday = parse_bday(value.trim()),
+            "END" => {
+                if let (Some(title), Some(spec)) = (name.take(), bday.take()) {
+                    if !title.is_empty() {
+                        commands.push(Command::Note(Note {
+                            title,
+                            statements: vec![Statement::BDate(spec)],
+                            desc: vec![],
+                        }));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    commands
+}
+
+pub fn import(files: &mut Files, format: ImportFormat, path: &Path) -> Result<()> {
+    let text = fs::read_to_string(path).map_err(|error| Error::ReadImport {
+        file: path.to_path_buf(),
+        error,
+    })?;
+    let commands = match format {
+        ImportFormat::VCard => vcard_birthdays(&text),
+    };
+
+    let capture = files
+        .capture(CaptureKind::Notes)
+        .ok_or(Error::NoCaptureFile)?;
+    println!("Imported {} entries", commands.len());
+    for command in commands {
+        files.insert(capture, command);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{unescape, vcard_birthdays};
+
+    #[test]
+    fn unescape_values() {
+        assert_eq!(unescape(r"Smith\, John\; Jr."), "Smith, John; Jr.");
+        assert_eq!(unescape(r"Line\nbreak"), "Line break");
+        // An escaped backslash doesn't start another escape sequence
+        assert_eq!(unescape(r"C:\\new"), r"C:\new");
+        assert_eq!(unescape(r"Trailing\"), r"Trailing\");
+    }
+
+    #[test]
+    fn vcard() {
+        let text = "BEGIN:VCARD\r\n\
+                    VERSION:4.0\r\n\
+                    FN:Max Mustermann\r\n\
+                    BDAY:19870514\r\n\
+                    END:VCARD\r\n\
+                    BEGIN:VCARD\r\n\
+                    VERSION:3.0\r\n\
+                    FN:Martha Sm\r\n\
+                    \x20ith\r\n\
+                    item1.BDAY;VALUE=date:--09-21\r\n\
+                    END:VCARD\r\n\
+                    BEGIN:VCARD\r\n\
+                    FN:Nobody\r\n\
+                    END:VCARD\r\n";
+
+        let commands = vcard_birthdays(text)
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            commands,
+            [
+                "NOTE Max Mustermann\nBDATE 1987-05-14\n",
+                "NOTE Martha Smith\nBDATE ?-09-21\n",
+            ]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 
 use crate::eval::{DateRange, Entry};
 use crate::files::Files;
@@ -9,17 +9,242 @@ use self::line::LineLayout;
 mod day;
 pub mod line;
 
+/// Options controlling how entries are laid out.
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutOptions {
+    /// Show a line marking the current time.
+    pub show_now: bool,
+    /// Mark the last occurrence of repeating entries before their `UNTIL`.
+    pub mark_ends: bool,
+    /// The maximum number of span columns. Entries that don't fit are
+    /// summarized in their start and end lines instead.
+    pub max_spans: Option<usize>,
+    /// The maximum number of entries per day. The remaining entries are
+    /// summarized in a single line.
+    pub per_day_limit: Option<usize>,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            show_now: true,
+            mark_ends: false,
+            max_spans: None,
+            per_day_limit: None,
+        }
+    }
+}
+
 pub fn layout(
     files: &Files,
     entries: &[Entry],
     range: DateRange,
     now: NaiveDateTime,
+    options: LayoutOptions,
 ) -> LineLayout {
-    let mut day_layout = DayLayout::new(range, now);
+    let mut day_layout = DayLayout::new(range, now, options.show_now);
     day_layout.layout(entries);
 
-    let mut line_layout = LineLayout::new();
+    let mut line_layout =
+        LineLayout::new(options.mark_ends, options.max_spans, options.per_day_limit);
     line_layout.render(files, entries, &day_layout);
 
     line_layout
 }
+
+/// Count the entries shown on each day of `range`.
+pub fn day_counts(
+    entries: &[Entry],
+    range: DateRange,
+    now: NaiveDateTime,
+) -> Vec<(NaiveDate, usize)> {
+    let mut day_layout = DayLayout::new(range, now, false);
+    day_layout.layout(entries);
+    day_layout.counts()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::eval::{DateRange, EntryMode};
+    use crate::testing;
+
+    use super::line::{LineEntry, LineLayout};
+    use super::{layout, LayoutOptions};
+
+    fn extras(layout: &LineLayout) -> Vec<(&str, Option<&str>)> {
+        layout
+            .lines()
+            .iter()
+            .filter_map(|line| match line {
+                LineEntry::Entry { text, extra, .. } => Some((text.as_str(), extra.as_deref())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn max_spans() {
+        let from = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let until = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let range = DateRange::new(from, until);
+        let (files, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\n\
+             NOTE A\nDATE 2024-03-01 -- 2024-03-10\n\n\
+             NOTE B\nDATE 2024-03-02 -- 2024-03-09\n\n\
+             NOTE C\nDATE 2024-03-03 -- 2024-03-08\n",
+            range,
+        );
+        let now = from.and_hms_opt(12, 0, 0).unwrap();
+        let options = LayoutOptions {
+            show_now: false,
+            ..Default::default()
+        };
+
+        let unlimited = layout(&files, &entries, range, now, options);
+        assert_eq!(unlimited.span_width(), 3);
+        assert!(extras(&unlimited).iter().all(|(_, extra)| extra.is_none()));
+
+        let options = LayoutOptions {
+            max_spans: Some(2),
+            ..options
+        };
+        let limited = layout(&files, &entries, range, now, options);
+        assert_eq!(limited.span_width(), 2);
+        assert_eq!(
+            extras(&limited),
+            [
+                ("A", None),
+                ("B", None),
+                ("C", Some("until 2024-03-08")),
+                ("C", Some("since 2024-03-03")),
+                ("B", None),
+                ("A", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn per_day_limit() {
+        let mut text = "TIMEZONE UTC\n".to_string();
+        for i in 1..=6 {
+            text.push_str(&format!("\nNOTE N{i}\nDATE 2024-03-01\n"));
+        }
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let range = DateRange::new(date, date);
+        let (files, entries) = testing::load_eval(&text, range);
+        let now = date.and_hms_opt(12, 0, 0).unwrap();
+        let options = LayoutOptions {
+            show_now: false,
+            per_day_limit: Some(3),
+            ..Default::default()
+        };
+
+        let limited = layout(&files, &entries, range, now, options);
+        assert_eq!(extras(&limited).len(), 3);
+        assert!(matches!(
+            limited.lines().last(),
+            Some(LineEntry::Overflow { count: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn progress() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let range = DateRange::new(date, date);
+        let (files, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\n\
+             TASK Thesis\nDATE 2024-03-01\nPROGRESS 60%\n\n\
+             TASK Slides\nDATE 2024-03-01\nPROGRESS 20%\nDONE [2024-03-01] 2024-03-01\n",
+            range,
+        );
+        let now = date.and_hms_opt(12, 0, 0).unwrap();
+        let options = LayoutOptions {
+            show_now: false,
+            ..Default::default()
+        };
+
+        let layout = layout(&files, &entries, range, now, options);
+        let mut extras = extras(&layout);
+        extras.sort();
+        assert_eq!(extras, [("Slides", None), ("Thesis", Some("60%"))]);
+    }
+
+    #[test]
+    fn day_of_span() {
+        let from = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let until = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let range = DateRange::new(from, until);
+        let (files, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\n\
+             NOTE Conference\nDATE 2024-03-04 -- 2024-03-07\n\n\
+             NOTE Hackathon\nDATE 2024-03-04 10:00 -- 2024-03-07 18:00\n\n\
+             NOTE Party\nDATE 2024-03-05\n",
+            range,
+        );
+        let now = NaiveDate::from_ymd_opt(2024, 3, 5)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let options = LayoutOptions {
+            show_now: false,
+            ..Default::default()
+        };
+
+        let layout = layout(&files, &entries, range, now, options);
+        let extras = extras(&layout);
+        assert!(extras.contains(&("Conference", Some("day 2 of 4, 2 days left"))));
+        assert!(extras.contains(&("Party", None)));
+        assert_eq!(
+            extras.iter().filter(|(_, extra)| extra.is_some()).count(),
+            1
+        );
+
+        // Timed entries are only shown at their visible start and end
+        let count = |title| extras.iter().filter(|(t, _)| *t == title).count();
+        assert_eq!(count("Conference"), 3);
+        assert_eq!(count("Hackathon"), 2);
+    }
+
+    #[test]
+    fn missed_occurrences() {
+        let files = testing::load(
+            "TIMEZONE UTC\n\n\
+             TASK Water\nDATE 2024-01-01; done +w\nDONE [2024-01-01] 2024-01-01\n",
+        );
+        let options = LayoutOptions {
+            show_now: false,
+            ..Default::default()
+        };
+        let extras_on = |day| {
+            let date = NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+            let range = DateRange::new(date, date);
+            let entries = files.eval(EntryMode::Relevant, range).unwrap();
+            let now = date.and_hms_opt(12, 0, 0).unwrap();
+            let layout = layout(&files, &entries, range, now, options);
+            let mut extras = extras(&layout)
+                .into_iter()
+                .filter_map(|(_, extra)| extra.map(str::to_string))
+                .collect::<Vec<_>>();
+            extras.sort();
+            extras
+        };
+
+        // Only the current occurrence shows how many were missed before it
+        assert_eq!(
+            extras_on(30),
+            [
+                "15 days ago",
+                "22 days ago",
+                "8 days ago",
+                "yesterday, 3 missed"
+            ]
+        );
+        assert_eq!(
+            extras_on(29),
+            ["14 days ago", "21 days ago", "3 missed", "7 days ago"]
+        );
+    }
+}
@@ -30,24 +30,29 @@ pub struct DayLayout {
     pub range: DateRange,
     pub today: NaiveDate,
     pub time: Time,
+    /// Whether to insert a [`DayEntry::Now`] marking the current time.
+    pub show_now: bool,
     /// Entries that are required to draw brackets correctly.
     pub earlier: Vec<DayEntry>,
     pub days: HashMap<NaiveDate, Vec<DayEntry>>,
 }
 
 impl DayLayout {
-    pub fn new(range: DateRange, now: NaiveDateTime) -> Self {
+    pub fn new(range: DateRange, now: NaiveDateTime, show_now: bool) -> Self {
         Self {
             range,
             today: now.date(),
             time: now.time().into(),
+            show_now,
             earlier: vec![],
             days: range.days().map(|d| (d, vec![])).collect(),
         }
     }
 
     pub fn layout(&mut self, entries: &[Entry]) {
-        self.insert(self.today, DayEntry::Now(self.time));
+        if self.show_now {
+            self.insert(self.today, DayEntry::Now(self.time));
+        }
 
         let mut entries = entries.iter().enumerate().collect::<Vec<_>>();
         Self::sort_entries(&mut entries);
@@ -63,13 +68,45 @@ impl DayLayout {
         self.combine_times();
     }
 
+    /// The number of entries on each day of the range, in order.
+    ///
+    /// Entries spanning multiple days are counted on the days they start and
+    /// end on.
+    pub fn counts(&self) -> Vec<(NaiveDate, usize)> {
+        self.range
+            .days()
+            .map(|day| {
+                let mut indices = self.days[&day]
+                    .iter()
+                    .filter_map(|entry| match entry {
+                        DayEntry::Now(_) => None,
+                        DayEntry::End(i)
+                        | DayEntry::TimedEnd(i, _)
+                        | DayEntry::TimedAt(i, _, _)
+                        | DayEntry::TimedStart(i, _)
+                        | DayEntry::ReminderSince(i, _)
+                        | DayEntry::At(i)
+                        | DayEntry::ReminderWhile(i, _)
+                        | DayEntry::Undated(i)
+                        | DayEntry::Start(i)
+                        | DayEntry::ReminderUntil(i, _) => Some(*i),
+                    })
+                    .collect::<Vec<_>>();
+                indices.sort_unstable();
+                indices.dedup();
+                (day, indices.len())
+            })
+            .collect()
+    }
+
     fn layout_entry(&mut self, index: usize, entry: &Entry) {
         match entry.kind {
-            EntryKind::Task => self.layout_task(index, entry),
+            EntryKind::Task | EntryKind::TaskWaiting => self.layout_task(index, entry),
             EntryKind::TaskDone(at) | EntryKind::TaskCanceled(at) => {
                 self.layout_task_done(index, entry, at)
             }
             EntryKind::Note | EntryKind::Birthday(_) => self.layout_note(index, entry),
+            EntryKind::ActiveNote => self.layout_active_note(index, entry),
         }
     }
 
@@ -116,6 +153,7 @@ impl DayLayout {
 
     fn layout_note(&mut self, index: usize, entry: &Entry) {
         if let Some(dates) = entry.dates {
+            let all_day = dates.times().is_none();
             let (start, end) = dates.sorted().dates();
             if self.today < self.range.from() || self.range.until() < self.today {
                 // if `self.today` is not in range, reminders won't be displayed
@@ -128,12 +166,18 @@ impl DayLayout {
                         self.insert(self.today, DayEntry::ReminderUntil(index, days));
                     }
                 }
-            } else if start < self.range.from() && self.range.until() < end {
-                // This note applies to the current day, but it won't appear if
-                // we just layout it as a dated entry, so instead we add it as a
-                // reminder. Since we are usually more interested in when
-                // something ends than when it starts, we count the days until
-                // the end.
+            } else if (start < self.range.from() && self.range.until() < end)
+                || (all_day && start < self.today && self.today < end)
+            {
+                // This note applies to the current day, but it won't appear on
+                // the current day if we just layout it as a dated entry, so we
+                // also add it as a reminder. Since we are usually more
+                // interested in when something ends than when it starts, we
+                // count the days until the end.
+                //
+                // If its start or end are visible, the reminder would only
+                // repeat them, except for all-day entries, which show the
+                // current day's position within them.
                 let days = (end - self.today).num_days();
                 self.insert(self.today, DayEntry::ReminderWhile(index, days));
             }
@@ -143,6 +187,24 @@ impl DayLayout {
         }
     }
 
+    fn layout_active_note(&mut self, index: usize, entry: &Entry) {
+        let dates = entry.dates.expect("active notes are always dated");
+        let (start, end) = dates.sorted().dates();
+        if self.today < self.range.from() || self.range.until() < self.today {
+            // Active notes are only ever displayed on `self.today`
+        } else if self.today < start {
+            if let Some(remind) = entry.remind {
+                if remind <= self.today {
+                    let days = (start - self.today).num_days();
+                    self.insert(self.today, DayEntry::ReminderUntil(index, days));
+                }
+            }
+        } else if self.today <= end {
+            let days = (end - self.today).num_days();
+            self.insert(self.today, DayEntry::ReminderWhile(index, days));
+        }
+    }
+
     fn layout_dated_entry(&mut self, index: usize, dates: Dates) {
         let sorted_dates = dates.sorted();
         let (start, end) = sorted_dates.dates();
@@ -196,16 +258,20 @@ impl DayLayout {
         // 2. Their end date in reverse, if any
         // 3. Their kind
         // 4. Their title
+        // 5. Their source, so entries from the same command stay together
+
+        // 5.
+        entries.sort_by_key(|(_, e)| e.source);
 
         // 4.
         entries.sort_by_key(|(_, e)| &e.title);
 
         // 3.
         entries.sort_by_key(|(_, e)| match e.kind {
-            EntryKind::Task => 0,
+            EntryKind::Task | EntryKind::TaskWaiting => 0,
             EntryKind::TaskDone(_) | EntryKind::TaskCanceled(_) => 1,
             EntryKind::Birthday(_) => 2,
-            EntryKind::Note => 3,
+            EntryKind::Note | EntryKind::ActiveNote => 3,
         });
 
         // 2.
@@ -288,3 +354,116 @@ impl DayLayout {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::eval::{DateRange, Dates, Entry, EntryKind};
+    use crate::files::Source;
+
+    use super::{DayEntry, DayLayout};
+
+    #[test]
+    fn birthday_reminder() {
+        let birthday = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 6, 8).unwrap();
+        let entry = Entry::new(
+            Source::new(0, 0),
+            EntryKind::Birthday(Some(34)),
+            "Bob".to_string(),
+            false,
+            Some(Dates::new(birthday, birthday)),
+            Some(today),
+        );
+
+        let range = DateRange::new(today, today);
+        let mut layout = DayLayout::new(range, today.and_hms_opt(12, 0, 0).unwrap(), true);
+        layout.layout(&[entry]);
+
+        let day = &layout.days[&today];
+        assert!(day
+            .iter()
+            .any(|e| matches!(e, DayEntry::ReminderUntil(0, 7))));
+    }
+
+    fn active_note_layout(today: NaiveDate) -> DayLayout {
+        let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let until = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+        let entry = Entry::new(
+            Source::new(0, 0),
+            EntryKind::ActiveNote,
+            "Vacation".to_string(),
+            false,
+            Some(Dates::new(from, until)),
+            None,
+        );
+
+        let range = DateRange::new(today, today);
+        let mut layout = DayLayout::new(range, today.and_hms_opt(12, 0, 0).unwrap(), true);
+        layout.layout(&[entry]);
+        layout
+    }
+
+    #[test]
+    fn active_note() {
+        let today = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        let layout = active_note_layout(today);
+        let day = &layout.days[&today];
+        assert!(day
+            .iter()
+            .any(|e| matches!(e, DayEntry::ReminderWhile(0, 5))));
+
+        let today = NaiveDate::from_ymd_opt(2024, 1, 11).unwrap();
+        let layout = active_note_layout(today);
+        let day = &layout.days[&today];
+        assert!(day.iter().all(|e| matches!(e, DayEntry::Now(_))));
+    }
+
+    #[test]
+    fn sort_by_source() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let entry = |command| {
+            Entry::new(
+                Source::new(0, command),
+                EntryKind::Note,
+                "Same".to_string(),
+                false,
+                Some(Dates::new(date, date)),
+                None,
+            )
+        };
+        let entries = [entry(1), entry(0), entry(1), entry(0)];
+
+        let mut sorted = entries.iter().enumerate().collect::<Vec<_>>();
+        DayLayout::sort_entries(&mut sorted);
+
+        let sources = sorted.iter().map(|(_, e)| e.source).collect::<Vec<_>>();
+        assert_eq!(
+            sources,
+            vec![
+                Source::new(0, 0),
+                Source::new(0, 0),
+                Source::new(0, 1),
+                Source::new(0, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn no_now() {
+        let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let range = DateRange::new(today, today);
+        let now = today.and_hms_opt(12, 0, 0).unwrap();
+
+        let mut layout = DayLayout::new(range, now, true);
+        layout.layout(&[]);
+        let day = &layout.days[&today];
+        assert!(day.iter().any(|e| matches!(e, DayEntry::Now(_))));
+
+        let mut layout = DayLayout::new(range, now, false);
+        layout.layout(&[]);
+        let day = &layout.days[&today];
+        assert!(day.is_empty());
+    }
+}
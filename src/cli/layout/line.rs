@@ -3,9 +3,9 @@
 //! Additional information, such as the mapping from numbers to entries, are
 //! collected along the way. The lines are not yet rendered into strings.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use chrono::NaiveDate;
+use chrono::{Duration, NaiveDate};
 
 use crate::eval::{Entry, EntryKind};
 use crate::files::primitives::Time;
@@ -41,7 +41,7 @@ pub enum SpanSegment {
 }
 
 impl SpanSegment {
-    fn style(&self) -> SpanStyle {
+    pub fn style(&self) -> SpanStyle {
         match self {
             Self::Start(s) => *s,
             Self::Middle(s) => *s,
@@ -61,6 +61,7 @@ pub enum Times {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LineKind {
     Task,
+    Waiting,
     Done,
     Canceled,
     Note,
@@ -88,6 +89,11 @@ pub enum LineEntry {
         has_desc: bool,
         extra: Option<String>,
     },
+    /// Entries of a day that were left out because of the per-day limit.
+    Overflow {
+        spans: Vec<Option<SpanSegment>>,
+        count: usize,
+    },
 }
 
 pub struct LineLayout {
@@ -101,15 +107,28 @@ pub struct LineLayout {
     last_number: usize,
     spans: Vec<Option<(usize, SpanSegment)>>,
     lines: Vec<LineEntry>,
+    /// Whether to mark the last occurrence of a repeating command before its
+    /// `UNTIL`.
+    mark_ends: bool,
+    /// The maximum number of span columns.
+    max_spans: Option<usize>,
+    /// Entries whose spans didn't fit into `max_spans` columns.
+    hidden_spans: HashSet<usize>,
+    /// The maximum number of entries displayed per day.
+    per_day_limit: Option<usize>,
 }
 
 impl LineLayout {
-    pub fn new() -> Self {
+    pub fn new(mark_ends: bool, max_spans: Option<usize>, per_day_limit: Option<usize>) -> Self {
         Self {
             numbers: HashMap::new(),
             last_number: 0,
             spans: vec![],
             lines: vec![],
+            mark_ends,
+            max_spans,
+            hidden_spans: HashSet::new(),
+            per_day_limit,
         }
     }
 
@@ -134,8 +153,59 @@ impl LineLayout {
             });
 
             let layout_entries = layout.days.get(&day).expect("got nonexisting day");
-            for layout_entry in layout_entries {
-                self.render_layout_entry(entries, layout_entry, today);
+            let overflowing = self.overflowing(layout_entries);
+            for (i, layout_entry) in layout_entries.iter().enumerate() {
+                if overflowing.contains(&i) {
+                    self.skip_layout_entry(layout_entry);
+                } else {
+                    self.render_layout_entry(entries, layout_entry, today);
+                }
+            }
+            if !overflowing.is_empty() {
+                self.line(LineEntry::Overflow {
+                    spans: self.spans_for_line(),
+                    count: overflowing.len(),
+                });
+            }
+        }
+    }
+
+    /// The positions of the day's entries that exceed the per-day limit.
+    ///
+    /// Reminders are kept in favour of other entries. The line marking the
+    /// current time is always kept and doesn't count towards the limit.
+    fn overflowing(&self, layout_entries: &[DayEntry]) -> HashSet<usize> {
+        let limit = match self.per_day_limit {
+            Some(limit) => limit,
+            None => return HashSet::new(),
+        };
+
+        let mut candidates = layout_entries
+            .iter()
+            .enumerate()
+            .filter(|(_, l_entry)| !matches!(l_entry, DayEntry::Now(_)))
+            .collect::<Vec<_>>();
+        // The sort is stable, so entries otherwise keep their order
+        candidates.sort_by_key(|(_, l_entry)| {
+            !matches!(
+                l_entry,
+                DayEntry::ReminderSince(..)
+                    | DayEntry::ReminderWhile(..)
+                    | DayEntry::ReminderUntil(..)
+            )
+        });
+        candidates.into_iter().skip(limit).map(|(i, _)| i).collect()
+    }
+
+    /// Keep the spans consistent for an entry that isn't displayed.
+    fn skip_layout_entry(&mut self, l_entry: &DayEntry) {
+        // Spans of skipped starts are never opened. Spans ending at a skipped
+        // end are removed without drawing their end.
+        if let DayEntry::End(i) | DayEntry::TimedEnd(i, _) = l_entry {
+            for span in self.spans.iter_mut() {
+                if matches!(span, Some((index, _)) if *index == *i) {
+                    *span = None;
+                }
             }
         }
     }
@@ -152,6 +222,11 @@ impl LineLayout {
         &self.lines
     }
 
+    /// The display number of the entry at `index`, if it is displayed.
+    pub fn number(&self, index: usize) -> Option<usize> {
+        self.numbers.get(&index).copied()
+    }
+
     pub fn look_up_number(&self, number: usize) -> Result<usize, Error> {
         self.numbers
             .iter()
@@ -165,7 +240,8 @@ impl LineLayout {
         match l_entry {
             DayEntry::End(i) => {
                 self.stop_span(*i);
-                self.line_entry(entries, *i, today, Times::Untimed, None);
+                let extra = self.hidden_span_extra(entries, *i, false);
+                self.line_entry(entries, *i, today, Times::Untimed, extra);
             }
             DayEntry::Now(t) => self.line(LineEntry::Now {
                 spans: self.spans_for_line(),
@@ -173,7 +249,8 @@ impl LineLayout {
             }),
             DayEntry::TimedEnd(i, t) => {
                 self.stop_span(*i);
-                self.line_entry(entries, *i, today, Times::At(*t), None);
+                let extra = self.hidden_span_extra(entries, *i, false);
+                self.line_entry(entries, *i, today, Times::At(*t), extra);
             }
             DayEntry::TimedAt(i, t, t2) => {
                 let time = t2.map(|t2| Times::FromTo(*t, t2)).unwrap_or(Times::At(*t));
@@ -181,7 +258,8 @@ impl LineLayout {
             }
             DayEntry::TimedStart(i, t) => {
                 self.start_span(*i);
-                self.line_entry(entries, *i, today, Times::At(*t), None);
+                let extra = self.hidden_span_extra(entries, *i, true);
+                self.line_entry(entries, *i, today, Times::At(*t), extra);
             }
             DayEntry::ReminderSince(i, d) => {
                 let extra = if *d == 1 {
@@ -189,14 +267,30 @@ impl LineLayout {
                 } else {
                     format!("{d} days ago")
                 };
+                let current_day = entries[*i]
+                    .dates
+                    .map(|dates| dates.sorted().dates().1 + Duration::days(*d));
+                let extra = match current_day.and_then(|day| Self::missed(entries, *i, day)) {
+                    Some(missed) => format!("{extra}, {missed} missed"),
+                    None => extra,
+                };
                 self.line_entry(entries, *i, today, Times::Untimed, Some(extra));
             }
             DayEntry::At(i) => {
-                self.line_entry(entries, *i, today, Times::Untimed, None);
+                let extra = entries[*i]
+                    .root()
+                    .filter(|_| today)
+                    .and_then(|day| Self::missed(entries, *i, day))
+                    .map(|missed| format!("{missed} missed"));
+                self.line_entry(entries, *i, today, Times::Untimed, extra);
             }
             DayEntry::ReminderWhile(i, d) => {
                 let plural = if *d == 1 { "" } else { "s" };
                 let extra = format!("{d} day{plural} left");
+                let extra = match Self::day_of_span(&entries[*i], *d) {
+                    Some(day_of) => format!("{day_of}, {extra}"),
+                    None => extra,
+                };
                 self.mark_span(*i);
                 self.line_entry(entries, *i, today, Times::Untimed, Some(extra));
             }
@@ -205,7 +299,8 @@ impl LineLayout {
             }
             DayEntry::Start(i) => {
                 self.start_span(*i);
-                self.line_entry(entries, *i, today, Times::Untimed, None);
+                let extra = self.hidden_span_extra(entries, *i, true);
+                self.line_entry(entries, *i, today, Times::Untimed, extra);
             }
             DayEntry::ReminderUntil(i, d) => {
                 let extra = if *d == 1 {
@@ -218,12 +313,51 @@ impl LineLayout {
         }
     }
 
+    /// The number of occurrences missed before the entry at `index`, but only
+    /// if it is the current occurrence on `today`, meaning that no other
+    /// occurrence of the same command lies between it and `today`.
+    fn missed(entries: &[Entry], index: usize, today: NaiveDate) -> Option<usize> {
+        let entry = &entries[index];
+        let root = entry.root()?;
+        if entry.missed == 0 || root > today {
+            return None;
+        }
+        let later = entries.iter().any(|other| {
+            other.source == entry.source
+                && other
+                    .root()
+                    .map(|other| root < other && other <= today)
+                    .unwrap_or(false)
+        });
+        if later {
+            None
+        } else {
+            Some(entry.missed)
+        }
+    }
+
+    /// The position of the current day within an all-day entry spanning
+    /// multiple days, like `day 2 of 4`, given the days left until its end.
+    fn day_of_span(entry: &Entry, days_left: i64) -> Option<String> {
+        let dates = entry.dates?.sorted();
+        if dates.times().is_some() {
+            return None;
+        }
+        let (start, end) = dates.dates();
+        let total = (end - start).num_days() + 1;
+        if total < 2 {
+            return None;
+        }
+        Some(format!("day {} of {total}", total - days_left))
+    }
+
     pub fn entry_kind(entry: &Entry) -> LineKind {
         match entry.kind {
             EntryKind::Task => LineKind::Task,
+            EntryKind::TaskWaiting => LineKind::Waiting,
             EntryKind::TaskDone(_) => LineKind::Done,
             EntryKind::TaskCanceled(_) => LineKind::Canceled,
-            EntryKind::Note => LineKind::Note,
+            EntryKind::Note | EntryKind::ActiveNote => LineKind::Note,
             EntryKind::Birthday(_) => LineKind::Birthday,
         }
     }
@@ -244,11 +378,29 @@ impl LineLayout {
             }
         }
 
-        // Not enough space, we need another column
+        // Not enough space, we need another column if we're allowed to
+        if self.max_spans.is_some_and(|max| self.spans.len() >= max) {
+            self.hidden_spans.insert(index);
+            return;
+        }
         let style = SpanStyle::from_indentation(self.spans.len());
         self.spans.push(Some((index, SpanSegment::Start(style))));
     }
 
+    /// Summarizes the extent of an entry whose span is hidden, on the line of
+    /// its start or end.
+    fn hidden_span_extra(&self, entries: &[Entry], index: usize, start: bool) -> Option<String> {
+        if !self.hidden_spans.contains(&index) {
+            return None;
+        }
+        let (from, until) = entries[index].dates?.sorted().dates();
+        Some(if start {
+            format!("until {until}")
+        } else {
+            format!("since {from}")
+        })
+    }
+
     fn mark_span(&mut self, index: usize) {
         for span in self.spans.iter_mut() {
             match span {
@@ -301,6 +453,26 @@ impl LineLayout {
     ) {
         let entry = &entries[index];
 
+        // Finished tasks are implicitly done completely
+        let progress = match entry.kind {
+            EntryKind::Task | EntryKind::TaskWaiting => entry.progress,
+            _ => None,
+        };
+        let extra = match (progress, extra) {
+            (Some(percent), Some(extra)) => Some(format!("{percent}%, {extra}")),
+            (Some(percent), None) => Some(format!("{percent}%")),
+            (None, extra) => extra,
+        };
+
+        let extra = if self.mark_ends && entry.ends {
+            match extra {
+                Some(extra) => Some(format!("{extra}, ends")),
+                None => Some("ends".to_string()),
+            }
+        } else {
+            extra
+        };
+
         let number = match self.numbers.get(&index) {
             Some(number) => *number,
             None => {
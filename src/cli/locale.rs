@@ -0,0 +1,165 @@
+use crate::files::primitives::Weekday;
+
+/// Language of weekday and month names in the output.
+///
+/// This doesn't affect the file syntax, which always uses English names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Locale {
+    /// English
+    #[default]
+    En,
+    /// German
+    De,
+    /// French
+    Fr,
+    /// Spanish
+    Es,
+}
+
+const WEEKDAYS_EN: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+const WEEKDAYS_DE: [&str; 7] = [
+    "Montag",
+    "Dienstag",
+    "Mittwoch",
+    "Donnerstag",
+    "Freitag",
+    "Samstag",
+    "Sonntag",
+];
+const WEEKDAYS_FR: [&str; 7] = [
+    "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+];
+const WEEKDAYS_ES: [&str; 7] = [
+    "lunes",
+    "martes",
+    "miércoles",
+    "jueves",
+    "viernes",
+    "sábado",
+    "domingo",
+];
+
+const MONTHS_EN: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const MONTHS_DE: [&str; 12] = [
+    "Januar",
+    "Februar",
+    "März",
+    "April",
+    "Mai",
+    "Juni",
+    "Juli",
+    "August",
+    "September",
+    "Oktober",
+    "November",
+    "Dezember",
+];
+const MONTHS_FR: [&str; 12] = [
+    "janvier",
+    "février",
+    "mars",
+    "avril",
+    "mai",
+    "juin",
+    "juillet",
+    "août",
+    "septembre",
+    "octobre",
+    "novembre",
+    "décembre",
+];
+const MONTHS_ES: [&str; 12] = [
+    "enero",
+    "febrero",
+    "marzo",
+    "abril",
+    "mayo",
+    "junio",
+    "julio",
+    "agosto",
+    "septiembre",
+    "octubre",
+    "noviembre",
+    "diciembre",
+];
+
+impl Locale {
+    fn weekdays(self) -> &'static [&'static str; 7] {
+        match self {
+            Self::En => &WEEKDAYS_EN,
+            Self::De => &WEEKDAYS_DE,
+            Self::Fr => &WEEKDAYS_FR,
+            Self::Es => &WEEKDAYS_ES,
+        }
+    }
+
+    fn months(self) -> &'static [&'static str; 12] {
+        match self {
+            Self::En => &MONTHS_EN,
+            Self::De => &MONTHS_DE,
+            Self::Fr => &MONTHS_FR,
+            Self::Es => &MONTHS_ES,
+        }
+    }
+
+    pub fn weekday(self, wd: Weekday) -> &'static str {
+        self.weekdays()[wd.num() as usize - 1]
+    }
+
+    /// The first two letters of the weekday's name.
+    pub fn weekday_short(self, wd: Weekday) -> String {
+        self.weekday(wd).chars().take(2).collect()
+    }
+
+    /// The length of the longest weekday name, in characters.
+    pub fn weekday_width(self) -> usize {
+        self.weekdays()
+            .iter()
+            .map(|name| name.chars().count())
+            .max()
+            .unwrap()
+    }
+
+    /// The name of a month (1-12).
+    pub fn month(self, month: u32) -> &'static str {
+        self.months()[month as usize - 1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::files::primitives::Weekday;
+
+    use super::Locale;
+
+    #[test]
+    fn names() {
+        assert_eq!(Locale::En.weekday(Weekday::Monday), "Monday");
+        assert_eq!(Locale::De.weekday(Weekday::Thursday), "Donnerstag");
+        assert_eq!(Locale::Es.weekday_short(Weekday::Wednesday), "mi");
+        assert_eq!(Locale::Fr.month(8), "août");
+        assert_eq!(Locale::En.weekday_width(), 9);
+        assert_eq!(Locale::De.weekday_width(), 10);
+    }
+}
@@ -1,5 +1,6 @@
 use chrono::NaiveDate;
 
+use crate::eval::DateRange;
 use crate::files::Files;
 
 use super::error::Error;
@@ -22,3 +23,82 @@ pub fn log(files: &mut Files, date: NaiveDate) -> Result<(), Error> {
 
     Ok(())
 }
+
+/// Group consecutive days with identical log descriptions into runs of the
+/// form `(first, last, desc)`. Days without a log break runs.
+fn summarize<'a>(
+    logs: impl IntoIterator<Item = (NaiveDate, Option<&'a [String]>)>,
+) -> Vec<(NaiveDate, NaiveDate, &'a [String])> {
+    let mut runs: Vec<(NaiveDate, NaiveDate, &[String])> = vec![];
+    let mut prev_day = None;
+    for (date, desc) in logs {
+        if let Some(desc) = desc {
+            match runs.last_mut() {
+                Some((_, last, run_desc)) if prev_day == Some(*last) && *run_desc == desc => {
+                    *last = date
+                }
+                _ => runs.push((date, date, desc)),
+            }
+        }
+        prev_day = Some(date);
+    }
+    runs
+}
+
+pub fn summary(files: &Files, range: DateRange) {
+    let logs = range
+        .days()
+        .map(|date| (date, files.log(date).map(|log| &log.value.desc[..])));
+    for (first, last, desc) in summarize(logs) {
+        let text = desc.join(" ");
+        if first == last {
+            println!("{first}: {text}");
+        } else {
+            println!("{first} -- {last}: {text}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::summarize;
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn identical_logs_collapse() {
+        let gym = vec!["Went to the gym".to_string()];
+        let logs = (1..=3).map(|d| (ymd(2024, 1, d), Some(&gym[..])));
+        assert_eq!(
+            summarize(logs),
+            vec![(ymd(2024, 1, 1), ymd(2024, 1, 3), &gym[..])]
+        );
+    }
+
+    #[test]
+    fn differing_day_breaks_run() {
+        let gym = vec!["Went to the gym".to_string()];
+        let rest = vec!["Rested".to_string()];
+        let logs = vec![
+            (ymd(2024, 1, 1), Some(&gym[..])),
+            (ymd(2024, 1, 2), Some(&gym[..])),
+            (ymd(2024, 1, 3), Some(&rest[..])),
+            (ymd(2024, 1, 4), Some(&gym[..])),
+            (ymd(2024, 1, 5), None),
+            (ymd(2024, 1, 6), Some(&gym[..])),
+        ];
+        assert_eq!(
+            summarize(logs),
+            vec![
+                (ymd(2024, 1, 1), ymd(2024, 1, 2), &gym[..]),
+                (ymd(2024, 1, 3), ymd(2024, 1, 3), &rest[..]),
+                (ymd(2024, 1, 4), ymd(2024, 1, 4), &gym[..]),
+                (ymd(2024, 1, 6), ymd(2024, 1, 6), &gym[..]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use colored::Colorize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Plain,
+    /// `*bold*`
+    Bold,
+    /// `_italic_`
+    Italic,
+    /// `` `code` ``
+    Code,
+}
+
+fn is_word_char(c: Option<char>) -> bool {
+    c.is_some_and(char::is_alphanumeric)
+}
+
+/// Find the marker closing the segment opened by the marker at `start`.
+///
+/// Underscores only open and close segments at word boundaries so that names
+/// like `snake_case` aren't styled.
+fn find_end(line: &str, start: usize, marker: char) -> Option<usize> {
+    if marker == '_' && is_word_char(line[..start].chars().next_back()) {
+        return None;
+    }
+    let mut search_from = start + 1;
+    while let Some(offset) = line[search_from..].find(marker) {
+        let end = search_from + offset;
+        if end == start + 1 {
+            // Empty segment
+            return None;
+        }
+        if marker != '_' || !is_word_char(line[end + 1..].chars().next()) {
+            return Some(end);
+        }
+        search_from = end + 1;
+    }
+    None
+}
+
+/// Split a line into differently styled segments.
+///
+/// Markers without a matching closing marker are kept as they are. Segments
+/// can't be nested.
+fn segments(line: &str) -> Vec<(Style, &str)> {
+    let mut result = vec![];
+    let mut plain_from = 0;
+    let mut search_from = 0;
+    while let Some(offset) = line[search_from..].find(['*', '_', '`']) {
+        let start = search_from + offset;
+        let marker = &line[start..start + 1];
+        let end = match find_end(line, start, marker.chars().next().unwrap()) {
+            Some(end) => end,
+            None => {
+                search_from = start + 1;
+                continue;
+            }
+        };
+
+        if plain_from < start {
+            result.push((Style::Plain, &line[plain_from..start]));
+        }
+        let style = match marker {
+            "*" => Style::Bold,
+            "_" => Style::Italic,
+            _ => Style::Code,
+        };
+        result.push((style, &line[start + 1..end]));
+        plain_from = end + 1;
+        search_from = end + 1;
+    }
+    if plain_from < line.len() {
+        result.push((Style::Plain, &line[plain_from..]));
+    }
+    result
+}
+
+/// Style `*bold*`, `_italic_` and `` `code` `` segments of a description line
+/// if `markdown` is set, otherwise leave the line as it is.
+pub fn render(line: &str, markdown: bool) -> String {
+    if !markdown {
+        return line.to_string();
+    }
+
+    segments(line)
+        .into_iter()
+        .map(|(style, text)| match style {
+            Style::Plain => text.to_string(),
+            Style::Bold => text.bold().to_string(),
+            Style::Italic => text.italic().to_string(),
+            Style::Code => text.cyan().to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing;
+
+    use super::{render, segments, Style};
+
+    #[test]
+    fn styled_segments() {
+        assert_eq!(
+            segments("a *b* _c_ `d`"),
+            [
+                (Style::Plain, "a "),
+                (Style::Bold, "b"),
+                (Style::Plain, " "),
+                (Style::Italic, "c"),
+                (Style::Plain, " "),
+                (Style::Code, "d"),
+            ]
+        );
+        assert_eq!(segments("`a*b`"), [(Style::Code, "a*b")]);
+        assert_eq!(segments("2 * 3 = 6"), [(Style::Plain, "2 * 3 = 6")]);
+        assert_eq!(segments("**"), [(Style::Plain, "**")]);
+    }
+
+    #[test]
+    fn italic_at_word_boundaries() {
+        assert_eq!(
+            segments("call snake_case_name"),
+            [(Style::Plain, "call snake_case_name")]
+        );
+        assert_eq!(
+            segments("_snake_case_ name"),
+            [(Style::Italic, "snake_case"), (Style::Plain, " name")]
+        );
+        assert_eq!(
+            segments("a_b _c_."),
+            [
+                (Style::Plain, "a_b "),
+                (Style::Italic, "c"),
+                (Style::Plain, "."),
+            ]
+        );
+    }
+
+    #[test]
+    fn styled_output() {
+        let _colors = testing::colors(true);
+        assert_eq!(
+            render("a *b* _c_ `d`", true),
+            "a \x1b[1mb\x1b[0m \x1b[3mc\x1b[0m \x1b[36md\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn no_markdown() {
+        assert_eq!(render("a *b* _c_ `d`", false), "a *b* _c_ `d`");
+    }
+}
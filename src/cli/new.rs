@@ -1,24 +1,30 @@
 use std::result;
-use std::str::FromStr;
 
 use chrono::NaiveDate;
 use codespan_reporting::files::SimpleFile;
 
 use crate::files::cli::CliCommand;
-use crate::files::commands::{Command, DateSpec, Done, DoneKind, Note, Spec, Statement, Task};
-use crate::files::{Files, ParseError};
+use crate::files::commands::{
+    CaptureKind, Command, DateSpec, Done, DoneKind, Note, Spec, Statement, Task,
+};
+use crate::files::primitives::Spanned;
+use crate::files::{Files, Source, TimesOfDay};
 
 use super::error::{Error, Result};
 use super::util;
 
-fn edit<R, F>(name: &str, mut text: String, validate: F) -> Result<Option<R>>
+fn edit<F>(
+    name: &str,
+    mut text: String,
+    times: &TimesOfDay,
+    validate: F,
+) -> Result<Option<CliCommand>>
 where
-    R: FromStr<Err = ParseError<()>>,
-    F: Fn(&R) -> result::Result<(), &str>,
+    F: Fn(&CliCommand) -> result::Result<(), &str>,
 {
     Ok(loop {
         text = util::edit(&text)?;
-        match text.parse() {
+        match CliCommand::parse(&text, times) {
             Ok(command) => match validate(&command) {
                 Ok(()) => break Some(command),
                 Err(msg) => eprintln!("{msg}"),
@@ -42,10 +48,15 @@ fn is_task_or_note(command: &CliCommand) -> result::Result<(), &str> {
     }
 }
 
-fn new_command(files: &mut Files, command: Command) -> Result<()> {
-    let capture = files.capture().ok_or(Error::NoCaptureFile)?;
+fn new_command(files: &mut Files, command: Command, kind: CaptureKind) -> Result<()> {
+    let capture = files.capture(kind).ok_or(Error::NoCaptureFile)?;
 
-    let command = edit("new command", format!("{command}"), is_task_or_note)?;
+    let command = edit(
+        "new command",
+        format!("{command}"),
+        files.times_of_day(),
+        is_task_or_note,
+    )?;
     if let Some(command) = command {
         files.insert(capture, command.0)
     }
@@ -53,62 +64,171 @@ fn new_command(files: &mut Files, command: Command) -> Result<()> {
     Ok(())
 }
 
-pub fn task(files: &mut Files, date: Option<NaiveDate>) -> Result<()> {
-    let statements = match date {
-        Some(date) => vec![Statement::Date(Spec::Date(DateSpec {
+/// The statements every new task or note starts out with.
+fn statements(date: Option<NaiveDate>, today: NaiveDate) -> Vec<Statement> {
+    let mut statements = vec![Statement::Created(today)];
+    if let Some(date) = date {
+        statements.push(Statement::Date(Spanned::dummy(Spec::Date(DateSpec {
             start: date,
             start_delta: None,
             start_time: None,
+            start_zone: None,
             end: None,
             end_delta: None,
             end_time: None,
+            end_exclusive: None,
             repeat: None,
-        }))],
-        None => vec![],
-    };
-    let command = Command::Task(Task {
+        }))));
+    }
+    statements
+}
+
+fn task_command(date: Option<NaiveDate>, today: NaiveDate) -> Command {
+    Command::Task(Task {
         title: String::new(),
-        statements,
+        statements: statements(date, today),
         done: vec![],
         desc: vec![],
-    });
-
-    new_command(files, command)
+    })
 }
 
-pub fn note(files: &mut Files, date: Option<NaiveDate>) -> Result<()> {
-    let statements = match date {
-        Some(date) => vec![Statement::Date(Spec::Date(DateSpec {
-            start: date,
-            start_delta: None,
-            start_time: None,
-            end: None,
-            end_delta: None,
-            end_time: None,
-            repeat: None,
-        }))],
-        None => vec![],
-    };
-    let command = Command::Note(Note {
+fn note_command(date: Option<NaiveDate>, today: NaiveDate) -> Command {
+    Command::Note(Note {
         title: String::new(),
-        statements,
+        statements: statements(date, today),
         desc: vec![],
-    });
-
-    new_command(files, command)
+    })
 }
 
-pub fn done(files: &mut Files, date: NaiveDate) -> Result<()> {
-    let command = Command::Task(Task {
+fn done_command(today: NaiveDate) -> Command {
+    Command::Task(Task {
         title: String::new(),
-        statements: vec![],
+        statements: vec![Statement::Created(today)],
         done: vec![Done {
             kind: DoneKind::Done,
             date: None,
-            done_at: date,
+            done_at: today,
+            spent: None,
         }],
         desc: vec![],
-    });
+    })
+}
+
+pub fn task(files: &mut Files, date: Option<NaiveDate>, today: NaiveDate) -> Result<()> {
+    new_command(files, task_command(date, today), CaptureKind::Tasks)
+}
+
+pub fn note(files: &mut Files, date: Option<NaiveDate>, today: NaiveDate) -> Result<()> {
+    new_command(files, note_command(date, today), CaptureKind::Notes)
+}
 
-    new_command(files, command)
+pub fn done(files: &mut Files, today: NaiveDate) -> Result<()> {
+    new_command(files, done_command(today), CaptureKind::Tasks)
+}
+
+fn template_text(files: &Files, name: &str) -> Result<(Source, String)> {
+    let template = files
+        .template(name)
+        .ok_or_else(|| Error::NoSuchTemplate(name.to_string()))?;
+    let mut text = template.value.body.join("\n");
+    text.push('\n');
+    Ok((template.source, text))
+}
+
+/// The command described by the template `name`, created `today`.
+fn template_command(files: &Files, name: &str, today: NaiveDate) -> Result<Command> {
+    let (source, text) = template_text(files, name)?;
+    let CliCommand(mut command) =
+        CliCommand::parse(&text, files.times_of_day()).map_err(|error| {
+            let error = error.relocate(source.file(), |at| files.template_offset(source, at));
+            Error::TemplateParse(error)
+        })?;
+    let statements = match &mut command {
+        Command::Task(task) => &mut task.statements,
+        Command::Note(note) => &mut note.statements,
+        // Rejected when editing the command
+        _ => return Ok(command),
+    };
+    statements.retain(|statement| !matches!(statement, Statement::Created(_)));
+    statements.insert(0, Statement::Created(today));
+    Ok(command)
+}
+
+pub fn template(files: &mut Files, name: &str, today: NaiveDate) -> Result<()> {
+    let command = template_command(files, name, today)?;
+    let kind = match command {
+        Command::Task(_) => CaptureKind::Tasks,
+        _ => CaptureKind::Notes,
+    };
+    new_command(files, command, kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::testing;
+
+    use super::super::error::Error;
+    use super::{done_command, note_command, task_command, template_command, template_text};
+
+    #[test]
+    fn created_inserted() {
+        let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+
+        assert_eq!(
+            format!("{}", task_command(None, today)),
+            "TASK \nCREATED 2024-01-01\n"
+        );
+        assert_eq!(
+            format!("{}", task_command(Some(date), today)),
+            "TASK \nCREATED 2024-01-01\nDATE 2024-01-05\n"
+        );
+        assert_eq!(
+            format!("{}", note_command(Some(date), today)),
+            "NOTE \nCREATED 2024-01-01\nDATE 2024-01-05\n"
+        );
+        assert_eq!(
+            format!("{}", done_command(today)),
+            "TASK \nCREATED 2024-01-01\nDONE [2024-01-01]\n"
+        );
+    }
+
+    #[test]
+    fn template_lookup() {
+        let files = testing::load(
+            "TIMEZONE UTC\n\nTEMPLATE review\n# TASK Weekly review\n# # Empty the inbox\n",
+        );
+
+        assert_eq!(
+            template_text(&files, "review").unwrap().1,
+            "TASK Weekly review\n# Empty the inbox\n"
+        );
+        assert!(matches!(
+            template_text(&files, "standup"),
+            Err(Error::NoSuchTemplate(name)) if name == "standup"
+        ));
+    }
+
+    #[test]
+    fn template_created() {
+        let files = testing::load(
+            "TIMEZONE UTC\n\n\
+             TEMPLATE review\n# TASK Weekly review\n# CREATED 2023-01-01\n# # Empty the inbox\n\n\
+             TEMPLATE broken\n# DATE 2024-01-01\n",
+        );
+        let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+        let command = template_command(&files, "review", today).unwrap();
+        let text = format!("{command}");
+        assert!(text.starts_with("TASK Weekly review\nCREATED 2024-01-01\n"));
+        assert!(!text.contains("2023-01-01"));
+        assert!(text.contains("Empty the inbox"));
+
+        assert!(matches!(
+            template_command(&files, "broken", today),
+            Err(Error::TemplateParse(_))
+        ));
+    }
 }
@@ -1,4 +1,5 @@
 use std::cmp;
+use std::collections::{HashMap, HashSet};
 
 use chrono::{Datelike, NaiveDate};
 use colored::{ColoredString, Colorize};
@@ -6,24 +7,149 @@ use colored::{ColoredString, Colorize};
 use crate::files::primitives::{Time, Weekday};
 
 use super::layout::line::{LineEntry, LineKind, LineLayout, SpanSegment, SpanStyle, Times};
+use super::locale::Locale;
+use super::markdown;
 use super::util;
 
+/// Options controlling how a layout is printed.
+#[derive(Debug, Clone)]
+pub struct PrintOptions {
+    /// Show ISO week numbers in day headers.
+    pub week_numbers: bool,
+    /// Label of the line marking the current time.
+    pub now_label: String,
+    /// Mark the current time at the end of the preceding entry instead of on
+    /// a line of its own.
+    pub now_inline: bool,
+    /// How many columns descriptions are indented by, relative to the entry's
+    /// kind letter.
+    pub desc_indent: usize,
+    /// Text at the start of each description line, after the indent.
+    pub desc_marker: String,
+    /// Language of weekday names in day headers.
+    pub locale: Locale,
+    /// Style descriptions' basic Markdown.
+    pub markdown: bool,
+    /// How many spaces separate the number, span and entry columns.
+    pub column_gap: usize,
+    /// Shorten the text of entry lines wider than this many columns.
+    pub width: Option<usize>,
+}
+
+impl Default for PrintOptions {
+    fn default() -> Self {
+        Self {
+            week_numbers: false,
+            now_label: "now".to_string(),
+            now_inline: false,
+            desc_indent: 2,
+            desc_marker: String::new(),
+            locale: Locale::En,
+            markdown: true,
+            column_gap: 1,
+            width: None,
+        }
+    }
+}
+
+/// The contents of an entry line, separate from their styling.
+struct EntryParts {
+    num: String,
+    /// One symbol per span column, `None` for empty columns.
+    spans: Vec<Option<&'static str>>,
+    kind: LineKind,
+    time: String,
+    text: String,
+    has_desc: bool,
+    extra: String,
+}
+
+impl EntryParts {
+    /// The line without any styling, as it appears on the terminal.
+    fn plain(&self, num_width: usize, gap: usize) -> String {
+        let spans = self
+            .spans
+            .iter()
+            .map(|symbol| symbol.unwrap_or(" "))
+            .collect::<String>();
+        let marker = if self.has_desc { "*" } else { "" };
+        let gap = " ".repeat(gap);
+        format!(
+            "{:>nw$}{gap}{}{gap}{}{} {}{}{}",
+            self.num,
+            spans,
+            util::kind_letter(self.kind),
+            self.time,
+            self.text,
+            marker,
+            self.extra,
+            nw = num_width,
+        )
+    }
+
+    /// The number of columns the line takes up on the terminal.
+    fn width(&self, num_width: usize, gap: usize) -> usize {
+        self.plain(num_width, gap).chars().count()
+    }
+
+    /// Shorten the line so that it is at most `width` columns wide. The extra
+    /// information is dropped first, then the text is shortened. The text is
+    /// never shortened to less than an ellipsis.
+    fn shorten(&mut self, num_width: usize, gap: usize, width: usize) {
+        if self.width(num_width, gap) <= width {
+            return;
+        }
+        self.extra.clear();
+        let excess = self.width(num_width, gap).saturating_sub(width);
+        if excess == 0 {
+            return;
+        }
+        let keep = self.text.chars().count().saturating_sub(excess + 1);
+        self.text = self.text.chars().take(keep).chain(Some('…')).collect();
+    }
+}
+
 struct ShowLines {
     num_width: usize,
     span_width: usize,
+    options: PrintOptions,
+    /// Display numbers of the entries to emphasize.
+    highlighted: HashSet<usize>,
+    /// Descriptions to show below entries, by display number.
+    descriptions: HashMap<usize, Vec<String>>,
+    /// ISO week of the previous day header, used to separate weeks.
+    last_week: Option<u32>,
+    /// Whether the previous line displayed an entry.
+    last_was_entry: bool,
     result: String,
 }
 
 impl ShowLines {
-    fn new(num_width: usize, span_width: usize) -> Self {
+    fn new(
+        num_width: usize,
+        span_width: usize,
+        options: PrintOptions,
+        highlighted: HashSet<usize>,
+        descriptions: HashMap<usize, Vec<String>>,
+    ) -> Self {
         Self {
             num_width,
             span_width,
+            options,
+            highlighted,
+            descriptions,
+            last_week: None,
+            last_was_entry: false,
             result: String::new(),
         }
     }
 
+    fn gap(&self) -> String {
+        " ".repeat(self.options.column_gap)
+    }
+
     fn display_line(&mut self, line: &LineEntry) {
+        let is_entry = matches!(line, LineEntry::Entry { .. });
         match line {
             LineEntry::Day {
                 spans,
@@ -43,7 +169,9 @@ impl ShowLines {
                 extra,
             } => self
                 .display_line_entry(*number, spans, *today, *time, *kind, text, *has_desc, extra),
+            LineEntry::Overflow { spans, count } => self.display_line_overflow(spans, *count),
         }
+        self.last_was_entry = is_entry;
     }
 
     fn display_line_date(
@@ -54,7 +182,9 @@ impl ShowLines {
         has_log: bool,
     ) {
         let weekday: Weekday = date.weekday().into();
-        let weekday = weekday.full_name();
+        let locale = self.options.locale;
+        let weekday = locale.weekday(weekday);
+        let ww = locale.weekday_width();
 
         let styled = |s: &str| {
             if today {
@@ -65,13 +195,23 @@ impl ShowLines {
         };
 
         // '=' symbols before the spans start
-        let p1 = styled(&format!("{:=<w$}=", "", w = self.num_width));
+        let w = self.num_width + self.options.column_gap;
+        let p1 = styled(&format!("{:=<w$}", ""));
 
         // Spans and filler '=' symbols
         let p2 = self.display_spans(spans, styled("="));
 
-        // The rest of the line until after the date
-        let p3 = styled(&format!("===  {weekday:9}  {date}"));
+        // The rest of the line until after the date (and week number)
+        let p3 = if self.options.week_numbers {
+            let week = date.iso_week().week();
+            if self.last_week.is_some_and(|last| last != week) {
+                self.push("\n");
+            }
+            self.last_week = Some(week);
+            styled(&format!("===  {weekday:ww$}  {date}  W{week:02}"))
+        } else {
+            styled(&format!("===  {weekday:ww$}  {date}"))
+        };
 
         // The "has log" marker (if any)
         let p4 = Self::display_marker(has_log, " ");
@@ -86,16 +226,61 @@ impl ShowLines {
         self.push(&format!("{p1}{p2}{p3}{p4}{p5}\n"));
     }
 
+    /// Whether `marker` fits behind the previous line without exceeding the
+    /// width. The previous line is already styled, so its escape sequences
+    /// must not be counted.
+    fn fits_inline(&self, marker: &str) -> bool {
+        let width = match self.options.width {
+            Some(width) => width,
+            None => return true,
+        };
+        let last_line = self.result.trim_end_matches('\n').rsplit('\n').next();
+        let last_width = last_line.map(util::visible_width).unwrap_or(0);
+        last_width + 1 + marker.chars().count() <= width
+    }
+
     fn display_line_now(&mut self, spans: &[Option<SpanSegment>], time: Time) {
+        let marker = format!("◀ {}", self.options.now_label);
+        if self.options.now_inline && self.last_was_entry && self.fits_inline(&marker) {
+            self.result.pop(); // Trailing newline of the entry
+            self.push(&format!(" {}\n", marker.bright_cyan().bold()));
+            return;
+        }
+
         self.push(&format!(
-            "{:>nw$} {}  {}\n",
-            "now".bright_cyan().bold(),
+            "{:>nw$}{gap}{}{gap} {}\n",
+            self.options.now_label.bright_cyan().bold(),
             self.display_spans(spans, " ".into()),
             Self::display_time(Times::At(time)),
             nw = self.num_width,
+            gap = self.gap(),
         ));
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn entry_parts(
+        &self,
+        number: Option<usize>,
+        spans: &[Option<SpanSegment>],
+        time: Times,
+        kind: LineKind,
+        text: &str,
+        has_desc: bool,
+        extra: &Option<String>,
+    ) -> EntryParts {
+        EntryParts {
+            num: number.map(|n| n.to_string()).unwrap_or_default(),
+            spans: (0..self.span_width)
+                .map(|i| spans.get(i).copied().flatten().map(Self::span_symbol))
+                .collect(),
+            kind,
+            time: Self::plain_time(time),
+            text: text.to_string(),
+            has_desc,
+            extra: Self::plain_extra(extra),
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn display_line_entry(
         &mut self,
@@ -108,42 +293,105 @@ impl ShowLines {
         has_desc: bool,
         extra: &Option<String>,
     ) {
-        let num = match number {
-            Some(n) => format!("{n}"),
-            None => "".to_string(),
-        };
+        let mut parts = self.entry_parts(number, spans, time, kind, text, has_desc, extra);
+        if let Some(width) = self.options.width {
+            parts.shorten(self.num_width, self.options.column_gap, width);
+        }
+        let highlight = number.is_some_and(|n| self.highlighted.contains(&n));
 
-        let text = if kind == LineKind::Birthday && today {
-            util::display_current_birthday_text(text)
-        } else {
-            text.into()
-        };
+        let spans = parts
+            .spans
+            .iter()
+            .map(|symbol| match symbol {
+                Some(symbol) => symbol.bright_black().to_string(),
+                None => " ".to_string(),
+            })
+            .collect::<String>();
+
+        self.push(&format!(
+            "{:>nw$}{gap}{}{gap}{}{} {}{}{}\n",
+            parts.num.bright_black(),
+            spans,
+            util::display_kind(parts.kind),
+            Self::display_dimmed(&parts.time),
+            Self::display_text(&parts.text, parts.kind, today, highlight),
+            Self::display_marker(parts.has_desc, ""),
+            Self::display_dimmed(&parts.extra),
+            nw = self.num_width,
+            gap = self.gap(),
+        ));
+
+        if let Some(desc) = number.and_then(|n| self.descriptions.get(&n)) {
+            self.display_desc(spans, desc);
+        }
+    }
 
+    fn display_line_overflow(&mut self, spans: &[Option<SpanSegment>], count: usize) {
         self.push(&format!(
-            "{:>nw$} {} {}{} {}{}{}\n",
-            num.bright_black(),
+            "{:nw$}{gap}{}{gap}{}\n",
+            "",
             self.display_spans(spans, " ".into()),
-            util::display_kind(kind),
-            Self::display_time(time),
-            text,
-            Self::display_marker(has_desc, ""),
-            Self::display_extra(extra),
+            format!("… and {count} more").bright_black(),
             nw = self.num_width,
-        ))
+            gap = self.gap(),
+        ));
+    }
+
+    /// Description lines below an entry, continuing its spans.
+    fn display_desc(&mut self, spans: &[Option<SpanSegment>], desc: &[String]) {
+        let spans = (0..self.span_width)
+            .map(|i| match spans.get(i).copied().flatten() {
+                Some(SpanSegment::End(_)) | None => " ".to_string(),
+                Some(segment) => Self::span_symbol(SpanSegment::Middle(segment.style()))
+                    .bright_black()
+                    .to_string(),
+            })
+            .collect::<String>();
+
+        // Description lines start below the entry's kind letter
+        let gap = self.gap();
+        for line in desc {
+            let line = format!(
+                "{:nw$}{gap}{}{gap}{:iw$}{}{}",
+                "",
+                spans,
+                "",
+                self.options.desc_marker,
+                markdown::render(line, self.options.markdown),
+                nw = self.num_width,
+                iw = self.options.desc_indent,
+            );
+            self.push(line.trim_end());
+            self.push("\n");
+        }
+    }
+
+    fn display_text(text: &str, kind: LineKind, today: bool, highlight: bool) -> ColoredString {
+        if highlight {
+            text.bright_red().bold()
+        } else if kind == LineKind::Birthday && today {
+            util::display_current_birthday_text(text)
+        } else {
+            text.into()
+        }
+    }
+
+    fn span_symbol(segment: SpanSegment) -> &'static str {
+        match segment {
+            SpanSegment::Start(_) => "┌",
+            SpanSegment::Middle(SpanStyle::Solid) => "│",
+            SpanSegment::Middle(SpanStyle::Dashed) => "╎",
+            SpanSegment::Middle(SpanStyle::Dotted) => "┊",
+            SpanSegment::Mark(_) => "┝",
+            SpanSegment::End(_) => "└",
+        }
     }
 
     fn display_spans(&self, spans: &[Option<SpanSegment>], empty: ColoredString) -> String {
         let mut result = String::new();
         for i in 0..self.span_width {
             if let Some(Some(segment)) = spans.get(i) {
-                let colored_str = match segment {
-                    SpanSegment::Start(_) => "┌".bright_black(),
-                    SpanSegment::Middle(SpanStyle::Solid) => "│".bright_black(),
-                    SpanSegment::Middle(SpanStyle::Dashed) => "╎".bright_black(),
-                    SpanSegment::Middle(SpanStyle::Dotted) => "┊".bright_black(),
-                    SpanSegment::Mark(_) => "┝".bright_black(),
-                    SpanSegment::End(_) => "└".bright_black(),
-                };
+                let colored_str = Self::span_symbol(*segment).bright_black();
                 result.push_str(&format!("{colored_str}"));
             } else {
                 result.push_str(&format!("{empty}"));
@@ -152,11 +400,23 @@ impl ShowLines {
         result
     }
 
-    fn display_time(time: Times) -> ColoredString {
+    fn plain_time(time: Times) -> String {
         match time {
-            Times::Untimed => "".into(),
-            Times::At(t) => format!(" {t}").bright_black(),
-            Times::FromTo(t1, t2) => format!(" {t1}--{t2}").bright_black(),
+            Times::Untimed => "".to_string(),
+            Times::At(t) => format!(" {t}"),
+            Times::FromTo(t1, t2) => format!(" {t1}--{t2}"),
+        }
+    }
+
+    fn display_time(time: Times) -> ColoredString {
+        Self::display_dimmed(&Self::plain_time(time))
+    }
+
+    fn display_dimmed(text: &str) -> ColoredString {
+        if text.is_empty() {
+            "".into()
+        } else {
+            text.bright_black()
         }
     }
 
@@ -168,10 +428,10 @@ impl ShowLines {
         }
     }
 
-    fn display_extra(extra: &Option<String>) -> ColoredString {
+    fn plain_extra(extra: &Option<String>) -> String {
         match extra {
-            None => "".into(),
-            Some(extra) => format!(" ({extra})").bright_black(),
+            None => "".to_string(),
+            Some(extra) => format!(" ({extra})"),
         }
     }
 
@@ -184,11 +444,318 @@ impl ShowLines {
     }
 }
 
-pub fn print(layout: &LineLayout) {
-    let num_width = cmp::max(layout.num_width(), 3); // `now` is 3 chars wide
-    let mut show_lines = ShowLines::new(num_width, layout.span_width());
+/// Print the layout, emphasizing the entries whose indices are in `highlighted`
+/// and showing the `descriptions` of entries by their indices.
+pub fn print(
+    layout: &LineLayout,
+    options: &PrintOptions,
+    highlighted: &HashSet<usize>,
+    descriptions: &HashMap<usize, Vec<String>>,
+) {
+    let highlighted = highlighted
+        .iter()
+        .filter_map(|&index| layout.number(index))
+        .collect();
+    let descriptions = descriptions
+        .iter()
+        .filter_map(|(&index, desc)| Some((layout.number(index)?, desc.clone())))
+        .collect();
+
+    // The label of the line marking the current time goes where the numbers go
+    let num_width = cmp::max(layout.num_width(), options.now_label.chars().count());
+    let mut show_lines = ShowLines::new(
+        num_width,
+        layout.span_width(),
+        options.clone(),
+        highlighted,
+        descriptions,
+    );
     for line in layout.lines() {
         show_lines.display_line(line);
     }
     print!("{}", show_lines.result());
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use chrono::NaiveDate;
+    use colored::Color;
+
+    use crate::files::primitives::Time;
+    use crate::testing;
+
+    use super::super::layout::line::{LineEntry, LineKind, SpanSegment, SpanStyle, Times};
+    use super::super::locale::Locale;
+    use super::super::util;
+    use super::{PrintOptions, ShowLines};
+
+    #[test]
+    fn week_numbers() {
+        let _colors = testing::colors(false);
+
+        let options = PrintOptions {
+            week_numbers: true,
+            ..Default::default()
+        };
+        let mut show_lines = ShowLines::new(3, 0, options, HashSet::new(), HashMap::new());
+        for day in 6..=8 {
+            let date = NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+            show_lines.display_line_date(&[], date, false, false);
+        }
+        let lines = show_lines.result();
+        let lines = lines.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].contains("2024-01-06  W01"));
+        assert!(lines[1].contains("2024-01-07  W01"));
+        assert_eq!(lines[2], "");
+        assert!(lines[3].contains("2024-01-08  W02"));
+    }
+
+    #[test]
+    fn localized_weekdays() {
+        let _colors = testing::colors(false);
+
+        let options = PrintOptions {
+            locale: Locale::De,
+            ..Default::default()
+        };
+        let mut show_lines = ShowLines::new(3, 0, options, HashSet::new(), HashMap::new());
+        for day in [8, 11] {
+            let date = NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+            show_lines.display_line_date(&[], date, false, false);
+        }
+        let lines = show_lines.result();
+        let lines = lines.lines().collect::<Vec<_>>();
+
+        // Names are padded to the longest weekday name of the locale
+        assert!(lines[0].contains("===  Montag      2024-01-08"));
+        assert!(lines[1].contains("===  Donnerstag  2024-01-11"));
+    }
+
+    #[test]
+    fn highlight() {
+        let text = ShowLines::display_text("Taxes", LineKind::Task, false, true);
+        assert_eq!(text.fgcolor(), Some(Color::BrightRed));
+
+        let text = ShowLines::display_text("Laundry", LineKind::Task, false, false);
+        assert_eq!(text.fgcolor(), None);
+    }
+
+    fn now_lines(options: PrintOptions) -> Vec<String> {
+        let _colors = testing::colors(false);
+
+        let mut show_lines = ShowLines::new(3, 0, options, HashSet::new(), HashMap::new());
+        show_lines.display_line(&LineEntry::Entry {
+            number: Some(1),
+            spans: vec![],
+            today: true,
+            time: Times::At(Time::new(9, 0)),
+            kind: LineKind::Task,
+            text: "Laundry".to_string(),
+            has_desc: false,
+            extra: None,
+        });
+        show_lines.display_line(&LineEntry::Now {
+            spans: vec![],
+            time: Time::new(9, 30),
+        });
+        show_lines.result().lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn now_label() {
+        let lines = now_lines(PrintOptions::default());
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1], "now    09:30");
+
+        let lines = now_lines(PrintOptions {
+            now_label: "jetzt".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1], "jetzt    09:30");
+    }
+
+    #[test]
+    fn now_inline() {
+        let lines = now_lines(PrintOptions {
+            now_inline: true,
+            ..Default::default()
+        });
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with("Laundry ◀ now"));
+
+        // The marker gets its own line if it would make the entry too wide
+        for (width, len) in [(26, 1), (25, 2)] {
+            let lines = now_lines(PrintOptions {
+                now_inline: true,
+                width: Some(width),
+                ..Default::default()
+            });
+            assert_eq!(lines.len(), len);
+        }
+
+        // Styling doesn't count towards the width
+        let options = PrintOptions {
+            width: Some(15),
+            ..Default::default()
+        };
+        let mut show_lines = ShowLines::new(3, 0, options, HashSet::new(), HashMap::new());
+        show_lines.push("\x1b[1;35mT\x1b[0m Laundry\n");
+        assert!(show_lines.fits_inline("◀ now"));
+        assert!(!show_lines.fits_inline("◀ jetzt"));
+    }
+
+    #[test]
+    fn entry_width() {
+        let _colors = testing::colors(false);
+
+        let mut show_lines = ShowLines::new(
+            3,
+            2,
+            PrintOptions::default(),
+            HashSet::new(),
+            HashMap::new(),
+        );
+        let spans = [Some(SpanSegment::Start(SpanStyle::Solid)), None];
+        let extra = Some("2 days".to_string());
+        let parts = show_lines.entry_parts(
+            Some(12),
+            &spans,
+            Times::FromTo(Time::new(9, 0), Time::new(10, 30)),
+            LineKind::Task,
+            "Laundry",
+            true,
+            &extra,
+        );
+        let plain = " 12 ┌  T 09:00--10:30 Laundry* (2 days)";
+        assert_eq!(parts.plain(3, 1), plain);
+        assert_eq!(parts.width(3, 1), plain.chars().count());
+
+        show_lines.display_line(&LineEntry::Entry {
+            number: Some(12),
+            spans: spans.to_vec(),
+            today: false,
+            time: Times::FromTo(Time::new(9, 0), Time::new(10, 30)),
+            kind: LineKind::Task,
+            text: "Laundry".to_string(),
+            has_desc: true,
+            extra,
+        });
+        let line = show_lines.result();
+        assert_eq!(util::visible_width(line.trim_end()), parts.width(3, 1));
+    }
+
+    #[test]
+    fn shorten_to_width() {
+        let _colors = testing::colors(false);
+
+        let options = PrintOptions {
+            width: Some(20),
+            ..Default::default()
+        };
+        let mut show_lines = ShowLines::new(3, 0, options, HashSet::new(), HashMap::new());
+        for text in ["Laundry", "Laundry and ironing"] {
+            show_lines.display_line(&LineEntry::Entry {
+                number: Some(1),
+                spans: vec![],
+                today: false,
+                time: Times::At(Time::new(9, 0)),
+                kind: LineKind::Task,
+                text: text.to_string(),
+                has_desc: false,
+                extra: None,
+            });
+        }
+        let lines = show_lines.result();
+        let lines = lines.lines().collect::<Vec<_>>();
+        assert_eq!(lines, ["  1  T 09:00 Laundry", "  1  T 09:00 Laundr…"]);
+    }
+
+    #[test]
+    fn shorten_extra() {
+        let _colors = testing::colors(false);
+
+        let options = PrintOptions {
+            width: Some(25),
+            ..Default::default()
+        };
+        let mut show_lines = ShowLines::new(3, 0, options, HashSet::new(), HashMap::new());
+        for text in ["Run", "Laundry", "Laundry and ironing"] {
+            show_lines.display_line(&LineEntry::Entry {
+                number: Some(1),
+                spans: vec![],
+                today: false,
+                time: Times::At(Time::new(9, 0)),
+                kind: LineKind::Task,
+                text: text.to_string(),
+                has_desc: false,
+                extra: Some("2 days".to_string()),
+            });
+        }
+        let lines = show_lines.result();
+        let lines = lines.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "  1  T 09:00 Run (2 days)",
+                "  1  T 09:00 Laundry",
+                "  1  T 09:00 Laundry and…",
+            ]
+        );
+    }
+
+    fn desc_lines(options: PrintOptions) -> Vec<String> {
+        let _colors = testing::colors(false);
+
+        let desc = vec!["Sort colors".to_string(), "".to_string()];
+        let descriptions = HashMap::from([(1, desc)]);
+        let mut show_lines = ShowLines::new(3, 0, options, HashSet::new(), descriptions);
+        show_lines.display_line(&LineEntry::Entry {
+            number: Some(1),
+            spans: vec![],
+            today: true,
+            time: Times::Untimed,
+            kind: LineKind::Task,
+            text: "Laundry".to_string(),
+            has_desc: true,
+            extra: None,
+        });
+        show_lines.result().lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn desc_default() {
+        let lines = desc_lines(PrintOptions::default());
+        assert_eq!(lines, ["  1  T Laundry*", "       Sort colors", ""]);
+    }
+
+    #[test]
+    fn desc_indent_and_marker() {
+        let lines = desc_lines(PrintOptions {
+            desc_indent: 4,
+            desc_marker: "> ".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(
+            lines,
+            ["  1  T Laundry*", "         > Sort colors", "         >"]
+        );
+    }
+
+    #[test]
+    fn column_gap() {
+        let options = PrintOptions {
+            column_gap: 3,
+            ..Default::default()
+        };
+        let lines = desc_lines(options.clone());
+        assert_eq!(lines, ["  1      T Laundry*", "           Sort colors", ""]);
+
+        let lines = now_lines(options);
+        assert_eq!(lines[1], "now        09:30");
+    }
+}
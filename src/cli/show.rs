@@ -1,14 +1,17 @@
-use chrono::NaiveDate;
+use std::fmt::Write;
+
+use chrono::{Datelike, NaiveDate};
 use codespan_reporting::files::Files as CsFiles;
 use colored::Colorize;
 
-use crate::eval::{Entry, EntryKind};
-use crate::files::commands::{Command, Log};
+use crate::eval::{self, Dates, Entry, EntryKind};
+use crate::files::commands::{Command, Delta, DoneDate, DoneKind, Log};
 use crate::files::primitives::Spanned;
 use crate::files::{Files, Sourced};
 
 use super::error::Error;
 use super::layout::line::LineLayout;
+use super::markdown;
 use super::util;
 
 fn fmt_where(files: &Files, command: &Sourced<'_, Spanned<Command>>) -> String {
@@ -20,49 +23,211 @@ fn fmt_where(files: &Files, command: &Sourced<'_, Spanned<Command>>) -> String {
     format!("Line {line} in {name}")
 }
 
-fn print_desc(command: &Sourced<'_, Spanned<Command>>) {
-    let desc: &[String] = match &command.value.value {
-        Command::Task(task) => &task.desc,
-        Command::Note(note) => &note.desc,
-        Command::Log(log) => &log.desc,
-        _ => &[],
-    };
+fn fmt_desc(command: &Sourced<'_, Spanned<Command>>, markdown: bool) -> String {
+    let desc = command.value.value.desc();
+    let mut result = String::new();
     if !desc.is_empty() {
-        println!();
+        result.push('\n');
         for line in desc {
-            println!("{}", line);
+            writeln!(result, "{}", markdown::render(line, markdown)).unwrap();
         }
     }
+    result
+}
+
+/// Find up to `count` occurrences of an entry following the one it represents.
+fn find_upcoming(files: &Files, entry: &Entry, count: usize) -> Vec<Dates> {
+    let mut result = vec![];
+    let mut after = match entry.dates {
+        Some(dates) => dates.root(),
+        None => return result,
+    };
+    while result.len() < count {
+        // The entry itself evaluated successfully, so errors are unlikely
+        // here. If they do occur, just show fewer occurrences.
+        match eval::next_occurrence(files, entry.source, after) {
+            Ok(Some(dates)) => {
+                after = dates.root();
+                result.push(dates);
+            }
+            _ => break,
+        }
+    }
+    result
+}
+
+/// Find the log entries of the days a task was completed on, along with the
+/// first line of each as an excerpt.
+fn done_logs(files: &Files, command: &Command) -> Vec<(NaiveDate, String)> {
+    let task = match command {
+        Command::Task(task) => task,
+        _ => return vec![],
+    };
+
+    let mut dates = task
+        .done
+        .iter()
+        .filter(|done| matches!(done.kind, DoneKind::Done))
+        .map(|done| done.done_at)
+        .collect::<Vec<_>>();
+    dates.sort_unstable();
+    dates.dedup();
+
+    dates
+        .into_iter()
+        .filter_map(|date| {
+            let log = files.log(date)?;
+            let excerpt = log
+                .value
+                .desc
+                .iter()
+                .find(|line| !line.trim().is_empty())
+                .cloned()
+                .unwrap_or_default();
+            Some((date, excerpt))
+        })
+        .collect()
+}
+
+/// Format a duration given in minutes, like `1h30min`.
+fn fmt_minutes(minutes: i64) -> String {
+    let minutes = minutes.abs();
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{m}min"),
+        (h, 0) => format!("{h}h"),
+        (h, m) => format!("{h}h{m}min"),
+    }
+}
+
+/// Describe how much the time spent on a task differs from its estimated
+/// effort, like `+1h, +50%`.
+fn fmt_variance(effort: i64, spent: i64) -> String {
+    let diff = spent - effort;
+    let sign = if diff < 0 { "-" } else { "+" };
+    let mut result = format!("{sign}{}", fmt_minutes(diff));
+    if effort > 0 {
+        let percent = (diff * 100 / effort).abs();
+        write!(result, ", {sign}{percent}%").unwrap();
+    }
+    result
+}
+
+/// Format a task's estimated effort and, for completed occurrences, the time
+/// actually spent on it.
+fn fmt_effort(command: &Command, entry: &Entry) -> Option<String> {
+    let task = match command {
+        Command::Task(task) => task,
+        _ => return None,
+    };
+
+    let effort = task.effort().and_then(Delta::minutes);
+    let spent = match entry.kind {
+        EntryKind::TaskDone(at) => task
+            .done
+            .iter()
+            .filter(|done| done.done_at == at)
+            .filter(|done| done.date.map(DoneDate::root) == entry.dates.map(|d| d.root()))
+            .find_map(|done| done.spent.as_ref())
+            .and_then(Delta::minutes),
+        _ => None,
+    };
+
+    match (effort, spent) {
+        (None, None) => None,
+        (Some(effort), None) => Some(format!("{} estimated", fmt_minutes(effort))),
+        (None, Some(spent)) => Some(format!("{} spent", fmt_minutes(spent))),
+        (Some(effort), Some(spent)) => Some(format!(
+            "{} estimated, {} spent ({})",
+            fmt_minutes(effort),
+            fmt_minutes(spent),
+            fmt_variance(effort, spent)
+        )),
+    }
 }
 
-fn show_entry(files: &Files, entry: &Entry) {
+/// Format an entry's details, one property per line.
+///
+/// If the entry recurs, up to `upcoming` of its following occurrences are
+/// listed as well.
+pub fn fmt_entry(files: &Files, entry: &Entry, upcoming: usize, markdown: bool) -> String {
     let command = files.command(entry.source);
+    let mut result = String::new();
 
     let kind = util::display_kind(LineLayout::entry_kind(entry));
-    println!("{} {} {}", "Title:".bright_black(), kind, entry.title);
+    writeln!(
+        result,
+        "{} {} {}",
+        "Title:".bright_black(),
+        kind,
+        entry.title
+    )
+    .unwrap();
 
     let what = match entry.kind {
         EntryKind::Task => "Task".to_string(),
+        EntryKind::TaskWaiting => match &command.value.value {
+            Command::Task(task) => format!("Task, waiting on {}", task.waiting_on().unwrap()),
+            _ => unreachable!(),
+        },
         EntryKind::TaskDone(date) => format!("Task, done {date}"),
         EntryKind::TaskCanceled(date) => format!("Task, canceled {date}"),
         EntryKind::Note => "Note".to_string(),
+        EntryKind::ActiveNote => "Note, active".to_string(),
         EntryKind::Birthday(None) => "Birthday, age unknown".to_string(),
         EntryKind::Birthday(Some(age)) => format!("Birthday, age {age}"),
     };
-    println!("{}  {}", "What:".bright_black(), what);
+    let what = match entry.progress {
+        Some(percent) => format!("{what} ({percent}%)"),
+        None => what,
+    };
+    writeln!(result, "{}  {}", "What:".bright_black(), what).unwrap();
 
     let when = match entry.dates {
         None => "no date".to_string(),
         Some(date) => format!("{}", date.sorted()),
     };
-    println!("{}  {}", "When:".bright_black(), when);
+    writeln!(result, "{}  {}", "When:".bright_black(), when).unwrap();
 
-    println!("{} {}", "Where:".bright_black(), fmt_where(files, &command));
+    if let Some(dates) = entry.dates {
+        let week = dates.sorted().root().iso_week();
+        let week = format!("{}-W{:02}", week.year(), week.week());
+        writeln!(result, "{}  {}", "Week:".bright_black(), week).unwrap();
+    }
+
+    if let Some(rule) = command.value.value.recurrence() {
+        writeln!(result, "{}  {}", "Rule:".bright_black(), rule).unwrap();
+    }
+
+    if let Some(effort) = fmt_effort(&command.value.value, entry) {
+        writeln!(result, "{}  {}", "Time:".bright_black(), effort).unwrap();
+    }
 
-    print_desc(&command);
+    if let Some(created) = entry.created {
+        writeln!(result, "{} {}", "Created:".bright_black(), created).unwrap();
+    }
+
+    let next = find_upcoming(files, entry, upcoming);
+    if !next.is_empty() {
+        let next = next
+            .iter()
+            .map(|dates| dates.sorted().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(result, "{}  {}", "Next:".bright_black(), next).unwrap();
+    }
+
+    for (date, excerpt) in done_logs(files, &command.value.value) {
+        writeln!(result, "{}   {} {}", "Log:".bright_black(), date, excerpt).unwrap();
+    }
+
+    let where_ = fmt_where(files, &command);
+    writeln!(result, "{} {}", "Where:".bright_black(), where_).unwrap();
+
+    result.push_str(&fmt_desc(&command, markdown));
+    result
 }
 
-fn show_log(files: &Files, log: Sourced<'_, Log>) {
+fn show_log(files: &Files, log: Sourced<'_, Log>, markdown: bool) {
     let command = files.command(log.source);
 
     println!("{}  Log entry", "What:".bright_black());
@@ -70,17 +235,24 @@ fn show_log(files: &Files, log: Sourced<'_, Log>) {
 
     println!("{} {}", "Where:".bright_black(), fmt_where(files, &command));
 
-    print_desc(&command);
+    print!("{}", fmt_desc(&command, markdown));
 }
 
-fn show_ident(files: &Files, entries: &[Entry], layout: &LineLayout, ident: Ident) {
+fn show_ident(
+    files: &Files,
+    entries: &[Entry],
+    layout: &LineLayout,
+    ident: Ident,
+    upcoming: usize,
+    markdown: bool,
+) {
     match ident {
         Ident::Number(n) => match layout.look_up_number(n) {
-            Ok(index) => show_entry(files, &entries[index]),
+            Ok(index) => print!("{}", fmt_entry(files, &entries[index], upcoming, markdown)),
             Err(e) => println!("{e}"),
         },
         Ident::Date(date) => match files.log(date) {
-            Some(log) => show_log(files, log),
+            Some(log) => show_log(files, log, markdown),
             None => println!("{}", Error::NoSuchLog(date)),
         },
     }
@@ -92,17 +264,134 @@ pub enum Ident {
     Date(NaiveDate),
 }
 
-pub fn show(files: &Files, entries: &[Entry], layout: &LineLayout, idents: &[Ident]) {
+pub fn show(
+    files: &Files,
+    entries: &[Entry],
+    layout: &LineLayout,
+    idents: &[Ident],
+    upcoming: usize,
+    markdown: bool,
+) {
     if idents.is_empty() {
         // Nothing to do
         return;
     }
 
-    show_ident(files, entries, layout, idents[0]);
+    show_ident(files, entries, layout, idents[0], upcoming, markdown);
     for &ident in idents.iter().skip(1) {
         println!();
         println!();
         println!();
-        show_ident(files, entries, layout, ident);
+        show_ident(files, entries, layout, ident, upcoming, markdown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::eval::DateRange;
+    use crate::testing;
+
+    use super::{fmt_entry, fmt_variance};
+
+    #[test]
+    fn created() {
+        let _colors = testing::colors(false);
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        let (files, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\nTASK Laundry\nCREATED 2024-01-01\nDATE 2024-01-05\n",
+            DateRange::new(date, date),
+        );
+        assert_eq!(entries.len(), 1);
+
+        let text = fmt_entry(&files, &entries[0], 0, true);
+        assert!(text.contains("\nCreated: 2024-01-01\n"));
+    }
+
+    #[test]
+    fn iso_week() {
+        let _colors = testing::colors(false);
+
+        let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let until = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
+        let (files, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\nTASK Planning\nDATE 2024-01-16\n\nTASK Review\nDATE 2024-12-30\n",
+            DateRange::new(from, until),
+        );
+        let week = |title: &str| {
+            let entry = entries.iter().find(|e| e.title == title).unwrap();
+            let text = fmt_entry(&files, entry, 0, true);
+            text.lines()
+                .find_map(|line| line.strip_prefix("Week:  "))
+                .map(|week| week.to_string())
+        };
+
+        assert_eq!(week("Planning").as_deref(), Some("2024-W03"));
+        // The ISO year can differ from the calendar year
+        assert_eq!(week("Review").as_deref(), Some("2025-W01"));
+    }
+
+    #[test]
+    fn upcoming_occurrences() {
+        let _colors = testing::colors(false);
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let (files, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\nTASK Laundry\nDATE 2024-01-01; +w\n",
+            DateRange::new(date, date),
+        );
+        assert_eq!(entries.len(), 1);
+
+        let text = fmt_entry(&files, &entries[0], 5, true);
+        assert!(
+            text.contains("\nNext:  2024-01-08, 2024-01-15, 2024-01-22, 2024-01-29, 2024-02-05\n")
+        );
+
+        let text = fmt_entry(&files, &entries[0], 0, true);
+        assert!(!text.contains("Next:"));
+    }
+
+    #[test]
+    fn done_log() {
+        let _colors = testing::colors(false);
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+        let (files, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\n\
+             TASK Laundry\nDATE 2024-01-05\nDONE [2024-01-05] 2024-01-06\n\n\
+             LOG 2024-01-06\n# Finally did the laundry.\n# Took all day.\n",
+            DateRange::new(date, date),
+        );
+        assert_eq!(entries.len(), 1);
+
+        let text = fmt_entry(&files, &entries[0], 0, true);
+        assert!(text.contains("\nLog:   2024-01-06 Finally did the laundry.\n"));
+        assert!(!text.contains("Took all day."));
+    }
+
+    #[test]
+    fn variance() {
+        assert_eq!(fmt_variance(120, 180), "+1h, +50%");
+        assert_eq!(fmt_variance(120, 90), "-30min, -25%");
+        assert_eq!(fmt_variance(60, 60), "+0min, +0%");
+        assert_eq!(fmt_variance(0, 90), "+1h30min");
+    }
+
+    #[test]
+    fn effort() {
+        let _colors = testing::colors(false);
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let (files, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\n\
+             TASK Report\nDATE 2024-01-02\nEFFORT +2h\nDONE [2024-01-02] 2024-01-02 spent +3h\n",
+            DateRange::new(date, date),
+        );
+        assert_eq!(entries.len(), 1);
+
+        let text = fmt_entry(&files, &entries[0], 0, true);
+        assert!(text.contains("\nTime:  2h estimated, 3h spent (+1h, +50%)\n"));
     }
 }
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crate::files::commands::Command;
+use crate::files::{Files, Source};
+
+use super::error::{Error, Result};
+use super::util::stable_hash;
+
+/// Identifies a command independently of its position within its file, so
+/// that inserting or removing other commands doesn't affect it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Key {
+    /// Path of the file containing the command.
+    path: String,
+    /// Hash of the command's title.
+    title: u64,
+    /// Position among the commands with the same title in the same file.
+    nth: usize,
+}
+
+/// What a command looked like when it was last seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Seen {
+    /// Hash of the command's formatted text.
+    hash: u64,
+    /// Number of `DONE`s and `CANCELED`s.
+    done: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Modified,
+    Completed,
+}
+
+/// The state of all tasks and notes at some point in time.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    seen: BTreeMap<Key, Seen>,
+    /// Where each command is located in the loaded files. Empty for snapshots
+    /// that were parsed instead of taken.
+    sources: BTreeMap<Key, Source>,
+}
+
+impl Snapshot {
+    pub fn of(files: &Files) -> Self {
+        let mut result = Self::default();
+        let mut titles = BTreeMap::new();
+        for command in files.commands() {
+            let (title, done) = match &command.value.value {
+                Command::Task(task) => (&task.title, task.done.len()),
+                Command::Note(note) => (&note.title, 0),
+                _ => continue,
+            };
+            let path = files.path(command.source.file()).to_string_lossy();
+            let title = stable_hash(title);
+            let nth = titles.entry((path.clone(), title)).or_insert(0);
+            let key = Key {
+                path: path.into_owned(),
+                title,
+                nth: *nth,
+            };
+            *nth += 1;
+
+            let hash = stable_hash(&command.value.value.to_string());
+            result.seen.insert(key.clone(), Seen { hash, done });
+            result.sources.insert(key, command.source);
+        }
+        result
+    }
+
+    /// Parse a snapshot written by [`Self::format`], ignoring malformed lines.
+    pub fn parse(text: &str) -> Self {
+        let mut result = Self::default();
+        for line in text.lines() {
+            let mut parts = line.splitn(5, ' ');
+            let numbers = parts
+                .by_ref()
+                .take(4)
+                .map(|part| part.parse::<u64>().ok())
+                .collect::<Option<Vec<_>>>();
+            if let (Some(&[done, hash, title, nth]), Some(path)) =
+                (numbers.as_deref(), parts.next())
+            {
+                let key = Key {
+                    path: path.to_string(),
+                    title,
+                    nth: nth as usize,
+                };
+                let done = done as usize;
+                result.seen.insert(key, Seen { hash, done });
+            }
+        }
+        result
+    }
+
+    /// One line per command, consisting of its number of `DONE`s, its hash,
+    /// the hash of its title, its position among commands with the same title
+    /// and finally the path of its file.
+    pub fn format(&self) -> String {
+        let mut result = String::new();
+        for (key, seen) in &self.seen {
+            writeln!(
+                result,
+                "{} {} {} {} {}",
+                seen.done, seen.hash, key.title, key.nth, key.path
+            )
+            .unwrap();
+        }
+        result
+    }
+
+    /// Load a snapshot from `path`. If there is no such file, the snapshot is
+    /// empty.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(Error::ReadState {
+                file: path.to_path_buf(),
+                error,
+            }),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let write = |path: &Path| {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(path, self.format())
+        };
+        write(path).map_err(|error| Error::WriteState {
+            file: path.to_path_buf(),
+            error,
+        })
+    }
+
+    /// How each command changed between this and a later snapshot of the
+    /// loaded files. Commands that didn't change are omitted.
+    pub fn changes(&self, later: &Self) -> BTreeMap<Source, Change> {
+        let mut changes = BTreeMap::new();
+        for (key, source) in &later.sources {
+            let seen = &later.seen[key];
+            let change = match self.seen.get(key) {
+                None => Change::Added,
+                Some(before) if seen.done > before.done => Change::Completed,
+                Some(before) if seen.hash != before.hash => Change::Modified,
+                Some(_) => continue,
+            };
+            changes.insert(*source, change);
+        }
+        changes
+    }
+}
+
+/// Compare the files to the snapshot at `path`, then replace the snapshot with
+/// the files' current state unless `dry_run` is set.
+pub fn changes_since_last(
+    files: &Files,
+    path: &Path,
+    dry_run: bool,
+) -> Result<BTreeMap<Source, Change>> {
+    let before = Snapshot::load(path)?;
+    let now = Snapshot::of(files);
+    let changes = before.changes(&now);
+    if !dry_run {
+        now.save(path)?;
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::files::{Files, Source};
+    use crate::testing::TempDir;
+
+    use super::{Change, Snapshot};
+
+    /// Take a snapshot of `text`, always using the same file in `dir`.
+    fn snapshot(dir: &TempDir, text: &str) -> Snapshot {
+        let path = dir.write("main.today", &format!("TIMEZONE UTC\n\n{text}"));
+        let mut files = Files::new();
+        files.load(&path).unwrap();
+        Snapshot::of(&files)
+    }
+
+    #[test]
+    fn changes() {
+        let dir = TempDir::new();
+        let before = snapshot(
+            &dir,
+            "TASK Laundry\nDATE 2024-01-05\n\n\
+             TASK Taxes\nDATE 2024-01-06\n\n\
+             NOTE Party\nDATE 2024-01-07\n",
+        );
+        let after = snapshot(
+            &dir,
+            "TASK Laundry\nDATE 2024-01-05\nDONE [2024-01-05]\n\n\
+             TASK Taxes\nDATE 2024-01-08\n\n\
+             NOTE Party\nDATE 2024-01-07\n\n\
+             NOTE Call\nDATE 2024-01-09\n",
+        );
+
+        // The TIMEZONE is the first command
+        let changes = before.changes(&after).into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                (Source::new(0, 1), Change::Completed),
+                (Source::new(0, 2), Change::Modified),
+                (Source::new(0, 4), Change::Added),
+            ]
+        );
+
+        // Snapshots survive being written and read back
+        let parsed = Snapshot::parse(&after.format());
+        assert!(parsed.changes(&after).is_empty());
+        assert_eq!(Snapshot::default().changes(&after).len(), 4);
+    }
+
+    #[test]
+    fn insert_in_middle() {
+        let dir = TempDir::new();
+        let before = snapshot(
+            &dir,
+            "TASK Laundry\nDATE 2024-01-05\n\n\
+             TASK Taxes\nDATE 2024-01-06\n",
+        );
+        let after = snapshot(
+            &dir,
+            "TASK Laundry\nDATE 2024-01-05\n\n\
+             NOTE Call\nDATE 2024-01-09\n\n\
+             TASK Taxes\nDATE 2024-01-06\n",
+        );
+
+        // Only the inserted command changed, even though Taxes moved
+        let changes = before.changes(&after).into_iter().collect::<Vec<_>>();
+        assert_eq!(changes, [(Source::new(0, 2), Change::Added)]);
+    }
+}
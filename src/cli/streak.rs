@@ -0,0 +1,107 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+
+use crate::eval::{self, DateRange, Entry};
+use crate::files::commands::{Command, DoneDate, DoneKind};
+use crate::files::Files;
+
+use super::error::{Error, Result};
+use super::layout::line::LineLayout;
+
+/// Count how many of the most recent `occurrences` were done in a row.
+///
+/// An occurrence on `today` that was not done yet does not break the streak
+/// since it may still be done later today.
+fn count_streak(occurrences: &[NaiveDate], done: &HashSet<NaiveDate>, today: NaiveDate) -> usize {
+    let mut streak = 0;
+    for (i, date) in occurrences.iter().rev().enumerate() {
+        if done.contains(date) {
+            streak += 1;
+        } else if i == 0 && *date == today {
+            continue;
+        } else {
+            break;
+        }
+    }
+    streak
+}
+
+pub fn streak(
+    files: &Files,
+    entries: &[Entry],
+    layout: &LineLayout,
+    number: usize,
+    today: NaiveDate,
+) -> Result<()> {
+    let entry = &entries[layout.look_up_number(number)?];
+    let task = match &files.command(entry.source).value.value {
+        Command::Task(task) => task,
+        _ => return Err(Error::NotATask(vec![number])),
+    };
+
+    let done = task
+        .done
+        .iter()
+        .filter(|done| matches!(done.kind, DoneKind::Done))
+        .filter_map(|done| done.date.map(DoneDate::root))
+        .collect::<HashSet<_>>();
+
+    // The streak can't reach further back than the earliest DONE
+    let streak = match done.iter().min() {
+        Some(&first) if first <= today => {
+            let range = DateRange::new(first, today);
+            let occurrences = eval::schedule(files, entry.source, range)?
+                .into_iter()
+                .map(|dates| dates.root())
+                .collect::<Vec<_>>();
+            count_streak(&occurrences, &done, today)
+        }
+        _ => 0,
+    };
+
+    println!("{}: {streak} in a row", task.title);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use chrono::NaiveDate;
+
+    use super::count_streak;
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn unbroken_streak() {
+        let occurrences = (1..=5).map(|d| ymd(2024, 1, d)).collect::<Vec<_>>();
+
+        let done = occurrences.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(count_streak(&occurrences, &done, ymd(2024, 1, 5)), 5);
+
+        // Today's occurrence is still pending
+        let done = occurrences[..4].iter().copied().collect::<HashSet<_>>();
+        assert_eq!(count_streak(&occurrences, &done, ymd(2024, 1, 5)), 4);
+    }
+
+    #[test]
+    fn broken_streak() {
+        let occurrences = (1..=6).map(|d| ymd(2024, 1, d)).collect::<Vec<_>>();
+        let done = [1, 2, 4, 5, 6]
+            .into_iter()
+            .map(|d| ymd(2024, 1, d))
+            .collect::<HashSet<_>>();
+        assert_eq!(count_streak(&occurrences, &done, ymd(2024, 1, 6)), 3);
+
+        // Missing yesterday's occurrence breaks the streak
+        let done = [1, 2, 3, 4]
+            .into_iter()
+            .map(|d| ymd(2024, 1, d))
+            .collect::<HashSet<_>>();
+        assert_eq!(count_streak(&occurrences, &done, ymd(2024, 1, 6)), 0);
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+
+use crate::files::commands::Command;
+use crate::files::Files;
+
+/// Count how many commands use each tag.
+fn count_tags<'a>(commands: impl IntoIterator<Item = &'a Command>) -> BTreeMap<&'a str, usize> {
+    let mut counts = BTreeMap::new();
+    for command in commands {
+        for tag in command.tags() {
+            *counts.entry(tag).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Which entries to show based on their tags.
+///
+/// An entry is shown if it has at least one of the included tags (or if no
+/// tags are included) and none of the excluded tags. Excluding wins over
+/// including.
+#[derive(Debug, Default)]
+pub struct TagFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl TagFilter {
+    /// Create a filter from `--tag` arguments. Arguments prefixed with `!`
+    /// exclude the tag instead of including it.
+    pub fn new(args: &[String]) -> Self {
+        let mut filter = Self::default();
+        for arg in args {
+            match arg.strip_prefix('!') {
+                Some(tag) => filter.exclude.push(tag.to_string()),
+                None => filter.include.push(arg.clone()),
+            }
+        }
+        filter
+    }
+
+    pub fn matches(&self, tags: &[&str]) -> bool {
+        if self.exclude.iter().any(|tag| tags.contains(&tag.as_str())) {
+            return false;
+        }
+        self.include.is_empty() || self.include.iter().any(|tag| tags.contains(&tag.as_str()))
+    }
+}
+
+pub fn tags(files: &Files) {
+    let commands = files.commands();
+    let counts = count_tags(commands.iter().map(|c| &c.value.value));
+    for (tag, count) in counts {
+        println!("{tag} ({count})");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::files::parse_file;
+
+    use super::{count_tags, TagFilter};
+
+    fn filter(args: &[&str]) -> TagFilter {
+        let args = args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        TagFilter::new(&args)
+    }
+
+    #[test]
+    fn count_overlapping_tags() {
+        let file1 = parse_file(
+            Path::new("a.today"),
+            "TASK A\nTAG work urgent\n\nNOTE B\nTAG home work work\n",
+        )
+        .unwrap();
+        let file2 = parse_file(
+            Path::new("b.today"),
+            "TASK C\nTAG urgent\nTAG work\n\nNOTE D\n",
+        )
+        .unwrap();
+
+        let commands = file1.commands.iter().chain(file2.commands.iter());
+        let counts = count_tags(commands.map(|c| &c.value));
+        let counts = counts.into_iter().collect::<Vec<_>>();
+        assert_eq!(counts, vec![("home", 1), ("urgent", 2), ("work", 3)]);
+    }
+
+    #[test]
+    fn filter_exclude_only() {
+        let filter = filter(&["!work"]);
+        assert!(filter.matches(&[]));
+        assert!(filter.matches(&["home"]));
+        assert!(!filter.matches(&["work"]));
+        assert!(!filter.matches(&["home", "work"]));
+    }
+
+    #[test]
+    fn filter_include_only() {
+        let filter = filter(&["work", "urgent"]);
+        assert!(!filter.matches(&[]));
+        assert!(!filter.matches(&["home"]));
+        assert!(filter.matches(&["work"]));
+        assert!(filter.matches(&["home", "urgent"]));
+    }
+
+    #[test]
+    fn filter_mixed() {
+        let filter = filter(&["work", "!urgent"]);
+        assert!(!filter.matches(&[]));
+        assert!(filter.matches(&["work"]));
+        assert!(!filter.matches(&["urgent"]));
+        // Excluding wins over including
+        assert!(!filter.matches(&["work", "urgent"]));
+    }
+}
@@ -1,16 +1,63 @@
 use colored::{ColoredString, Colorize};
+use terminal_size::{Height, Width};
 
 use super::error::{Error, Result};
 use super::layout::line::LineKind;
 
+pub fn kind_letter(kind: LineKind) -> &'static str {
+    match kind {
+        LineKind::Task => "T",
+        LineKind::Waiting => "W",
+        LineKind::Done => "D",
+        LineKind::Canceled => "C",
+        LineKind::Note => "N",
+        LineKind::Birthday => "B",
+    }
+}
+
 pub fn display_kind(kind: LineKind) -> ColoredString {
+    let letter = kind_letter(kind);
     match kind {
-        LineKind::Task => "T".magenta().bold(),
-        LineKind::Done => "D".green().bold(),
-        LineKind::Canceled => "C".red().bold(),
-        LineKind::Note => "N".blue().bold(),
-        LineKind::Birthday => "B".yellow().bold(),
+        LineKind::Task => letter.magenta().bold(),
+        LineKind::Waiting => letter.cyan().bold(),
+        LineKind::Done => letter.green().bold(),
+        LineKind::Canceled => letter.red().bold(),
+        LineKind::Note => letter.blue().bold(),
+        LineKind::Birthday => letter.yellow().bold(),
+    }
+}
+
+/// The number of columns a string takes up on the terminal, ignoring any ANSI
+/// escape sequences used for styling it.
+pub fn visible_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip a CSI sequence like `\x1b[1;35m` up to its final byte
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+        } else {
+            width += 1;
+        }
     }
+    width
+}
+
+/// FNV-1a. Unlike the hashers in std, it is guaranteed to produce the same
+/// hashes across runs and compiler versions.
+pub fn stable_hash(text: &str) -> u64 {
+    let mut hash = 0xcbf29ce484222325_u64;
+    for byte in text.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
 }
 
 pub fn display_current_birthday_text(text: &str) -> ColoredString {
@@ -26,3 +73,54 @@ pub fn edit_with_suffix(input: &str, suffix: &str) -> Result<String> {
     builder.suffix(suffix);
     edit::edit_with_builder(input, &builder).map_err(Error::EditingIo)
 }
+
+/// Width to assume if the terminal width can't be determined, for example
+/// because stdout is not a terminal.
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
+
+fn width_from_size(size: Option<(Width, Height)>) -> usize {
+    match size {
+        Some((Width(w), _)) if w > 0 => w.into(),
+        _ => DEFAULT_TERMINAL_WIDTH,
+    }
+}
+
+/// The width of the terminal in columns, or 80 if it can't be determined.
+pub fn terminal_width() -> usize {
+    width_from_size(terminal_size::terminal_size())
+}
+
+#[cfg(test)]
+mod tests {
+    use terminal_size::{Height, Width};
+
+    use crate::testing;
+
+    use super::super::layout::line::LineKind;
+    use super::{display_kind, visible_width, width_from_size};
+
+    #[test]
+    fn display_waiting() {
+        let _colors = testing::colors(false);
+        assert_eq!(display_kind(LineKind::Waiting).to_string(), "W");
+        assert_eq!(display_kind(LineKind::Task).to_string(), "T");
+    }
+
+    #[test]
+    fn terminal_width_fallback() {
+        assert_eq!(width_from_size(None), 80);
+        assert_eq!(width_from_size(Some((Width(0), Height(0)))), 80);
+        assert_eq!(width_from_size(Some((Width(120), Height(40)))), 120);
+    }
+
+    #[test]
+    fn visible_width_ignores_ansi() {
+        assert_eq!(visible_width("T 09:00 Laundry"), 15);
+        assert_eq!(
+            visible_width("\x1b[1;35mT\x1b[0m \x1b[90m09:00\x1b[0m Laundry"),
+            15
+        );
+        assert_eq!(visible_width("\x1b[90m┌\x1b[0m│"), 2);
+        assert_eq!(visible_width(""), 0);
+    }
+}
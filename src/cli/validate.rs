@@ -0,0 +1,155 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::NaiveDate;
+
+use crate::eval::{Entry, Error};
+use crate::files::{FileSource, Files, Source};
+
+use super::error::{self, Result};
+use super::ErrorFormat;
+
+/// Find pairs of different commands with entries of the same title on the same
+/// day. Each pair is only reported for the first day it occurs on.
+fn duplicate_titles(files: &Files, entries: &[Entry]) -> Vec<Error<FileSource>> {
+    let mut by_day: BTreeMap<(NaiveDate, &str), Vec<Source>> = BTreeMap::new();
+    for entry in entries {
+        if let Some(dates) = entry.dates {
+            let date = dates.sorted().root();
+            let sources = by_day.entry((date, entry.title.as_str())).or_default();
+            if !sources.contains(&entry.source) {
+                sources.push(entry.source);
+            }
+        }
+    }
+
+    let mut reported = BTreeSet::new();
+    let mut duplicates = vec![];
+    for ((date, title), sources) in by_day {
+        let (first, others) = match sources.split_first() {
+            Some(split) => split,
+            None => continue,
+        };
+        for other in others {
+            if !reported.insert((*first, *other)) {
+                continue;
+            }
+            duplicates.push(Error::DuplicateTitle {
+                index1: first.file(),
+                span1: files.command(*first).value.span,
+                index2: other.file(),
+                span2: files.command(*other).value.span,
+                date,
+                title: title.to_string(),
+            });
+        }
+    }
+    duplicates
+}
+
+/// Print all problems found in the loaded files.
+///
+/// Fails if there are any problems so that the exit code can be checked.
+pub fn validate(files: &Files, entries: &[Entry], format: ErrorFormat) -> Result<()> {
+    let mut problems = files.unreachable_moves();
+    problems.extend(files.overlapping_occurrences());
+    problems.extend(duplicate_titles(files, entries));
+    if problems.is_empty() {
+        println!("No problems found");
+        return Ok(());
+    }
+    for problem in &problems {
+        super::eprint_error(format, files, problem);
+    }
+    Err(error::Error::ProblemsFound(problems.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::cli::ErrorFormat;
+    use crate::eval::{DateRange, Error};
+    use crate::testing;
+
+    use super::super::error;
+    use super::{duplicate_titles, validate};
+
+    #[test]
+    fn unreachable_moves() {
+        // The first MOVE is fine, the second one doesn't hit any occurrence
+        let text = "TIMEZONE UTC\n\nNOTE Standup\nDATE 2024-01-01; +w\n\
+                    MOVE 2024-01-08 TO 2024-01-09\nMOVE 2024-01-10 TO 2024-01-11\n";
+        let files = testing::load(text);
+
+        let problems = files.unreachable_moves();
+        assert_eq!(problems.len(), 1);
+        match &problems[0] {
+            Error::MoveWithoutSource { span, .. } => {
+                assert_eq!(span.start, text.find("MOVE 2024-01-10").unwrap());
+            }
+            e => panic!("unexpected error {e:?}"),
+        }
+    }
+
+    #[test]
+    fn other_eval_errors() {
+        // The first MOVE can't move an untimed occurrence to a time, which
+        // doesn't prevent finding the unreachable second one
+        let text = "TIMEZONE UTC\n\nNOTE Standup\nDATE 2024-01-01; +w\n\
+                    MOVE 2024-01-08 TO 10:00\n\n\
+                    NOTE Review\nDATE 2024-01-01; +w\nMOVE 2024-01-10 TO 2024-01-11\n";
+        let files = testing::load(text);
+
+        let problems = files.unreachable_moves();
+        assert_eq!(problems.len(), 2);
+        assert!(matches!(problems[0], Error::TimedMoveWithoutTime { .. }));
+        assert!(matches!(problems[1], Error::MoveWithoutSource { .. }));
+    }
+
+    #[test]
+    fn fails_on_problems() {
+        let text = "TIMEZONE UTC\n\nTASK Laundry\nDATE 2024-01-05\n";
+        let files = testing::load(text);
+        assert!(validate(&files, &[], ErrorFormat::Human).is_ok());
+
+        let text = "TIMEZONE UTC\n\nNOTE Standup\nDATE 2024-01-01; +w\n\
+                    MOVE 2024-01-10 TO 2024-01-11\n";
+        let files = testing::load(text);
+        assert!(matches!(
+            validate(&files, &[], ErrorFormat::Human),
+            Err(error::Error::ProblemsFound(1))
+        ));
+    }
+
+    fn duplicates(text: &str) -> Vec<String> {
+        let from = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let until = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let (files, entries) = testing::load_eval(text, DateRange::new(from, until));
+        duplicate_titles(&files, &entries)
+            .into_iter()
+            .map(|problem| match problem {
+                Error::DuplicateTitle { date, title, .. } => format!("{date} {title}"),
+                e => panic!("unexpected error {e:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn duplicate_title() {
+        let text = "TIMEZONE UTC\n\nTASK Laundry\nDATE 2024-01-05\n\n\
+                    TASK Laundry\nDATE 2024-01-05\n\nTASK Laundry\nDATE 2024-01-06\n";
+        assert_eq!(duplicates(text), ["2024-01-05 Laundry"]);
+
+        // Recurring duplicates are only reported once
+        let text = "TIMEZONE UTC\n\nNOTE Standup\nDATE 2024-01-01; +w\n\n\
+                    NOTE Standup\nDATE 2024-01-01; +w\n";
+        assert_eq!(duplicates(text), ["2024-01-01 Standup"]);
+    }
+
+    #[test]
+    fn distinct_titles() {
+        let text = "TIMEZONE UTC\n\nTASK Laundry\nDATE 2024-01-05\n\n\
+                    TASK Taxes\nDATE 2024-01-05\n";
+        assert!(duplicates(text).is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+use crate::files::commands::Command;
+use crate::files::Files;
+
+/// Find the titles of all tasks that are waiting, along with what they are
+/// waiting on.
+fn find_waiting<'a>(commands: impl IntoIterator<Item = &'a Command>) -> Vec<(&'a str, &'a str)> {
+    commands
+        .into_iter()
+        .filter_map(|command| match command {
+            Command::Task(task) => task.waiting_on().map(|on| (task.title.as_str(), on)),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn waiting(files: &Files) {
+    let commands = files.commands();
+    for (title, on) in find_waiting(commands.iter().map(|c| &c.value.value)) {
+        println!("{title} (waiting on {on})");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::files::parse_file;
+
+    use super::find_waiting;
+
+    #[test]
+    fn list_waiting() {
+        let file = parse_file(
+            Path::new("test.today"),
+            "TASK Report\nWAITING Alice\n\nTASK Laundry\n\nTASK Taxes\nWAITING Bob\nWAITING Carol\n\nNOTE Party\n",
+        )
+        .unwrap();
+
+        let waiting = find_waiting(file.commands.iter().map(|c| &c.value));
+        assert_eq!(waiting, vec![("Report", "Alice"), ("Taxes", "Carol")]);
+    }
+}
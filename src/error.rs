@@ -1,4 +1,6 @@
-use codespan_reporting::diagnostic::Diagnostic;
+use std::fmt::Write;
+
+use codespan_reporting::diagnostic::{Diagnostic, LabelStyle, Severity};
 use codespan_reporting::files::Files;
 use codespan_reporting::term::{self, Config};
 use termcolor::StandardStream;
@@ -18,6 +20,10 @@ pub trait Eprint<'a, F: Files<'a>> {
 
     #[allow(single_use_lifetimes)]
     fn eprint<'f: 'a>(&self, files: &'f F, config: &Config);
+
+    /// The error as a single-line JSON object, see [`json_diagnostic`].
+    #[allow(single_use_lifetimes)]
+    fn json<'f: 'a>(&self, files: &'f F) -> String;
 }
 
 #[allow(single_use_lifetimes)]
@@ -29,3 +35,165 @@ where
     let config = Config::default();
     e.eprint(files, &config);
 }
+
+#[allow(single_use_lifetimes)]
+pub fn eprint_error_json<'a, 'f: 'a, F, E>(files: &'f F, e: &E)
+where
+    F: Files<'a>,
+    E: Eprint<'a, F>,
+{
+    eprintln!("{}", e.json(files));
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut result = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => write!(result, "\\u{:04x}", c as u32).unwrap(),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+fn json_object(
+    severity: Severity,
+    message: &str,
+    location: Option<(String, usize, usize)>,
+    notes: &[String],
+) -> String {
+    let severity = match severity {
+        Severity::Bug => "bug",
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+        Severity::Help => "help",
+    };
+    let (file, start, end) = match location {
+        Some((file, start, end)) => (json_string(&file), start.to_string(), end.to_string()),
+        None => ("null".to_string(), "null".to_string(), "null".to_string()),
+    };
+    let notes = notes
+        .iter()
+        .map(|note| json_string(note))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{{\"severity\":\"{severity}\",\"message\":{},\"file\":{file},\"start\":{start},\"end\":{end},\"notes\":[{notes}]}}",
+        json_string(message),
+    )
+}
+
+/// Serialize a diagnostic as a single-line JSON object.
+///
+/// The object contains the `severity`, `message` and `notes` of the diagnostic
+/// as well as the `file` name and the `start` and `end` byte offsets of its
+/// first primary label. The location fields are `null` if there is no such
+/// label.
+#[allow(single_use_lifetimes)]
+pub fn json_diagnostic<'a, 'f: 'a, F: Files<'a>>(
+    files: &'f F,
+    diagnostic: &Diagnostic<F::FileId>,
+) -> String {
+    let location = diagnostic
+        .labels
+        .iter()
+        .find(|label| label.style == LabelStyle::Primary)
+        .map(|label| {
+            let name = files
+                .name(label.file_id)
+                .map(|name| name.to_string())
+                .unwrap_or_default();
+            (name, label.range.start, label.range.end)
+        });
+    json_object(
+        diagnostic.severity,
+        &diagnostic.message,
+        location,
+        &diagnostic.notes,
+    )
+}
+
+/// Serialize an error without any location as a single-line JSON object, see
+/// [`json_diagnostic`].
+pub fn json_message(message: &str) -> String {
+    json_object(Severity::Error, message, None, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::NaiveDate;
+    use codespan_reporting::files::SimpleFile;
+
+    use crate::eval;
+    use crate::files::primitives::Span;
+    use crate::files::Files;
+    use crate::testing::TempDir;
+
+    use super::{json_message, Eprint};
+
+    #[test]
+    fn parse_error_json() {
+        let text = "TASK Laundry\nDATE 2024-13-01\n";
+        let file = SimpleFile::new("test.today", text);
+        let error = crate::files::parse_file(Path::new("test.today"), text).unwrap_err();
+        assert_eq!(
+            error.json(&file),
+            "{\"severity\":\"error\",\"message\":\"Could not parse test.today\",\
+             \"file\":\"test.today\",\"start\":18,\"end\":28,\
+             \"notes\":[\"invalid date\"]}"
+        );
+    }
+
+    #[test]
+    fn eval_error_json() {
+        let file = SimpleFile::new("test.today", "");
+        let error = eval::Error::DivByZero {
+            index: (),
+            span: Span { start: 5, end: 12 },
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        };
+        assert_eq!(
+            error.json(&file),
+            "{\"severity\":\"error\",\"message\":\"Tried to divide by zero\",\
+             \"file\":\"test.today\",\"start\":5,\"end\":12,\
+             \"notes\":[\"At date: 2024-01-01\"]}"
+        );
+    }
+
+    #[test]
+    fn conflict_error_json() {
+        let dir = TempDir::new();
+        dir.write("other.today", "TIMEZONE Europe/Berlin\n");
+        let path = dir.write("main.today", "TIMEZONE UTC\nINCLUDE other.today\n");
+
+        let mut files = Files::new();
+        let error = files.load(&path).unwrap_err();
+        let json = error.json(&files);
+        assert!(json.starts_with(
+            "{\"severity\":\"error\",\
+             \"message\":\"Time zone conflict between UTC and Europe/Berlin\",\"file\":"
+        ));
+        assert!(json.ends_with(
+            "main.today\",\"start\":9,\"end\":12,\
+             \"notes\":[\"All TIMEZONE commands must set the same time zone.\"]}"
+        ));
+    }
+
+    #[test]
+    fn message_json() {
+        assert_eq!(
+            json_message("No \"capture\" file\n"),
+            "{\"severity\":\"error\",\"message\":\"No \\\"capture\\\" file\\n\",\
+             \"file\":null,\"start\":null,\"end\":null,\"notes\":[]}"
+        );
+    }
+}
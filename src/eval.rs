@@ -1,9 +1,12 @@
-use chrono::NaiveDate;
+use std::collections::HashMap;
 
-use crate::files::cli::{CliDate, CliDatum, CliRange};
-use crate::files::{FileSource, Files};
+use chrono::{Duration, NaiveDate};
 
-use self::command::{CommandState, EvalCommand};
+use crate::files::cli::{CliDate, CliDatum, CliExpr, CliRange};
+use crate::files::commands::{Command, Note, Spec, Statement};
+use crate::files::{FileSource, Files, Source};
+
+use self::command::{CommandState, DateSpec, EvalCommand, Expr};
 pub use self::date::Dates;
 use self::delta::Delta;
 use self::entry::Entries;
@@ -19,13 +22,260 @@ mod error;
 mod range;
 mod util;
 
+/// Evaluate all loaded commands, returning the entries relevant to `range`.
+pub fn evaluate(files: &Files, range: DateRange) -> Result<Vec<Entry>, Error<FileSource>> {
+    files.eval(EntryMode::Relevant, range)
+}
+
+/// Find the first pending occurrence of the command at `source` whose root
+/// date lies strictly after `after`.
+///
+/// Occurrences that are already done or canceled are skipped. Returns `None`
+/// if the command is undated or has no such occurrence within the next 64
+/// years.
+pub fn next_occurrence(
+    files: &Files,
+    source: Source,
+    after: NaiveDate,
+) -> Result<Option<Dates>, Error<FileSource>> {
+    let command = match EvalCommand::new(&files.command(source).value.value) {
+        Some(command) => command,
+        None => return Ok(None),
+    };
+    let anchors = files.find_anchors()?;
+
+    // Search windows of increasing size so frequently repeating commands
+    // don't need to be evaluated over a huge range.
+    let from = after + Duration::days(1);
+    for days in [366, 4 * 366, 16 * 366, 64 * 366] {
+        let range = DateRange::new(from, from + Duration::days(days));
+        let state = CommandState::new(command, source, range, files.fiscal_year(), &anchors)
+            .with_timezones(files.timezone(), files.zones())
+            .max_occurrences(files.max_occurrences());
+        let next = state
+            .eval()?
+            .entries()
+            .into_iter()
+            .filter(|entry| {
+                !matches!(
+                    entry.kind,
+                    EntryKind::TaskDone(_) | EntryKind::TaskCanceled(_)
+                )
+            })
+            .filter_map(|entry| entry.dates)
+            .filter(|dates| dates.root() > after)
+            .min_by_key(|dates| dates.root());
+        if next.is_some() {
+            return Ok(next);
+        }
+    }
+    Ok(None)
+}
+
+/// Find the dates a task is scheduled for within `range`, regardless of
+/// whether the task was done or canceled on those dates.
+///
+/// Returns an empty list if the command at `source` is not a task.
+pub fn schedule(
+    files: &Files,
+    source: Source,
+    range: DateRange,
+) -> Result<Vec<Dates>, Error<FileSource>> {
+    let task = match &files.command(source).value.value {
+        Command::Task(task) => task,
+        _ => return Ok(vec![]),
+    };
+
+    // Evaluating the task's statements as a note ignores its DONEs, which
+    // would otherwise replace or skip some of the scheduled occurrences.
+    let note = Note {
+        title: task.title.clone(),
+        statements: task.statements.clone(),
+        desc: vec![],
+    };
+    let anchors = files.find_anchors()?;
+    let state = CommandState::new(
+        EvalCommand::Note(&note),
+        source,
+        range,
+        files.fiscal_year(),
+        &anchors,
+    )
+    .with_timezones(files.timezone(), files.zones())
+    .max_occurrences(files.max_occurrences());
+
+    let mut dates = state
+        .eval()?
+        .entries()
+        .into_iter()
+        .filter_map(|entry| entry.dates)
+        .filter(|dates| range.contains(dates.root()))
+        .collect::<Vec<_>>();
+    dates.sort_by_key(|dates| dates.root());
+    Ok(dates)
+}
+
+/// Describe step by step how the command at `source` is evaluated within
+/// `range`, ending with the resulting occurrences.
+///
+/// If `future_from` is set, occurrences of repeating tasks before it are
+/// dropped like in [`Files::eval_future_only`].
+pub fn explain(
+    files: &Files,
+    source: Source,
+    range: DateRange,
+    future_from: Option<NaiveDate>,
+) -> Result<Vec<String>, Error<FileSource>> {
+    let command = match EvalCommand::new(&files.command(source).value.value) {
+        Some(command) => command,
+        None => return Ok(vec![]),
+    };
+    let anchors = files.find_anchors()?;
+    let mut state = CommandState::new(command, source, range, files.fiscal_year(), &anchors)
+        .with_timezones(files.timezone(), files.zones())
+        .max_occurrences(files.max_occurrences())
+        .collect_trace();
+    if let Some(today) = future_from {
+        state = state.future_only(today);
+    }
+    let state = state.eval()?;
+
+    let mut trace = state.trace().to_vec();
+    let mut entries = state.entries();
+    entries.sort_by_key(|entry| entry.root());
+    for entry in entries {
+        match entry.dates {
+            Some(dates) => trace.push(format!("Result {dates}")),
+            None => trace.push("Result without date".to_string()),
+        }
+    }
+    Ok(trace)
+}
+
 impl Files {
+    /// Find the dates of all entries that other entries can be anchored to
+    /// using `DATE after`. Fails if multiple entries share an ID.
+    fn find_anchors(&self) -> Result<HashMap<String, NaiveDate>, Error<FileSource>> {
+        let commands = self.commands();
+        let mut ids = HashMap::new();
+        let mut anchors = HashMap::new();
+        for command in &commands {
+            if let Some(id) = command.value.value.id() {
+                let index = command.source.file();
+                let span = command.value.span;
+                if let Some((index1, span1)) = ids.insert(id, (index, span)) {
+                    return Err(Error::DuplicateId {
+                        index1,
+                        span1,
+                        index2: index,
+                        span2: span,
+                        id: id.to_string(),
+                    });
+                }
+                if let Some(eval_command) = EvalCommand::new(&command.value.value) {
+                    if let Some(date) = eval_command.anchor(command.source)? {
+                        anchors.entry(id.to_string()).or_insert(date);
+                    }
+                }
+            }
+        }
+        Ok(anchors)
+    }
+
+    /// Find all `MOVE`s whose source date doesn't match any occurrence of
+    /// their command.
+    ///
+    /// Unlike [`Self::eval`], this doesn't stop at the first such `MOVE`.
+    /// Commands that fail to evaluate for other reasons are reported with
+    /// their error instead.
+    pub fn unreachable_moves(&self) -> Vec<Error<FileSource>> {
+        let anchors = match self.find_anchors() {
+            Ok(anchors) => anchors,
+            Err(error) => return vec![error],
+        };
+
+        let mut unreachable = vec![];
+        for command in self.commands() {
+            let source = command.source;
+            if let Some(command) = EvalCommand::new(&command.value.value) {
+                // CommandState::new expands the range to contain all moves anyway.
+                let first_move = command.statements().iter().find_map(|s| match s {
+                    Statement::Move { from, .. } => Some(*from),
+                    _ => None,
+                });
+                if let Some(from) = first_move {
+                    let range = DateRange::new(from, from);
+                    let state =
+                        CommandState::new(command, source, range, self.fiscal_year(), &anchors)
+                            .with_timezones(self.timezone(), self.zones())
+                            .max_occurrences(self.max_occurrences())
+                            .collect_unreachable_moves();
+                    match state.eval() {
+                        Ok(state) => unreachable.extend(state.unreachable_moves()),
+                        Err(error) => unreachable.push(error),
+                    }
+                }
+            }
+        }
+        unreachable
+    }
+
+    /// Find all repeating `DATE`s whose consecutive occurrences overlap.
+    pub fn overlapping_occurrences(&self) -> Vec<Error<FileSource>> {
+        let mut overlapping = vec![];
+        for command in self.commands() {
+            let index = command.source.file();
+            if let Some(command) = EvalCommand::new(&command.value.value) {
+                for statement in command.statements() {
+                    if let Statement::Date(spec) = statement {
+                        if let Spec::Date(date_spec) = &spec.value {
+                            let date_spec = DateSpec::from(date_spec);
+                            overlapping.extend(date_spec.overlap(index, spec.span));
+                        }
+                    }
+                }
+            }
+        }
+        overlapping
+    }
+
     pub fn eval(&self, mode: EntryMode, range: DateRange) -> Result<Vec<Entry>, Error<FileSource>> {
+        self.eval_entries(mode, range, None)
+    }
+
+    /// Like [`Self::eval`], but drops occurrences of repeating tasks before
+    /// `today` unless they were done.
+    pub fn eval_future_only(
+        &self,
+        mode: EntryMode,
+        range: DateRange,
+        today: NaiveDate,
+    ) -> Result<Vec<Entry>, Error<FileSource>> {
+        self.eval_entries(mode, range, Some(today))
+    }
+
+    fn eval_entries(
+        &self,
+        mode: EntryMode,
+        range: DateRange,
+        future_from: Option<NaiveDate>,
+    ) -> Result<Vec<Entry>, Error<FileSource>> {
+        // Anchors are resolved first so entries depending on them can be
+        // evaluated in any order.
+        let anchors = self.find_anchors()?;
+
         let mut entries = Entries::new(mode, range);
         for command in self.commands() {
             let source = command.source;
             if let Some(command) = EvalCommand::new(&command.value.value) {
-                for entry in CommandState::new(command, source, range).eval()?.entries() {
+                let mut state =
+                    CommandState::new(command, source, range, self.fiscal_year(), &anchors)
+                        .with_timezones(self.timezone(), self.zones())
+                        .max_occurrences(self.max_occurrences());
+                if let Some(today) = future_from {
+                    state = state.future_only(today);
+                }
+                for entry in state.eval()?.entries() {
                     entries.add(entry);
                 }
             }
@@ -50,6 +300,19 @@ impl CliDate {
     }
 }
 
+impl CliExpr {
+    /// Whether the formula holds on `date`. Fiscal year variables are
+    /// evaluated relative to the month `fiscal_year` (1-12).
+    pub fn eval<S: Copy>(
+        &self,
+        index: S,
+        date: NaiveDate,
+        fiscal_year: u32,
+    ) -> Result<bool, Error<S>> {
+        Expr::new(&self.0, fiscal_year).holds(index, date)
+    }
+}
+
 impl CliRange {
     pub fn eval<S: Copy>(&self, index: S, today: NaiveDate) -> Result<DateRange, Error<S>> {
         let mut start = match self.start {
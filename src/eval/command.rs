@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use chrono::{Duration, NaiveDate};
+use tzfile::Tz;
 
 use crate::files::commands::{
     self, BirthdaySpec, Command, Done, DoneDate, DoneKind, Note, Spec, Statement, Task,
@@ -8,6 +9,9 @@ use crate::files::commands::{
 use crate::files::primitives::{Span, Spanned, Time};
 use crate::files::{FileSource, Source};
 
+pub use self::date::DateSpec;
+pub use self::formula::Expr;
+use self::formula::FormulaSpec;
 use super::date::Dates;
 use super::delta::Delta;
 use super::{DateRange, Entry, EntryKind, Error};
@@ -16,7 +20,15 @@ mod birthday;
 mod date;
 mod formula;
 
+/// The maximum amount of occurrences a single command may generate unless
+/// configured otherwise, see [`CommandState::max_occurrences`].
+///
+/// Without this limit, a repeating `DATE` evaluated over a huge range can
+/// generate so many entries that evaluation and layout appear to hang.
+pub const MAX_OCCURRENCES: usize = 100_000;
+
 /// A command that can be evaluated.
+#[derive(Clone, Copy)]
 pub enum EvalCommand<'a> {
     Task(&'a Task),
     Note(&'a Note),
@@ -31,7 +43,7 @@ impl<'a> EvalCommand<'a> {
         }
     }
 
-    fn statements(&self) -> &[Statement] {
+    pub fn statements(&self) -> &[Statement] {
         match self {
             Self::Task(task) => &task.statements,
             Self::Note(note) => &note.statements,
@@ -40,6 +52,7 @@ impl<'a> EvalCommand<'a> {
 
     fn kind(&self) -> EntryKind {
         match self {
+            Self::Task(task) if task.waiting_on().is_some() => EntryKind::TaskWaiting,
             Self::Task(_) => EntryKind::Task,
             Self::Note(_) => EntryKind::Note,
         }
@@ -59,6 +72,37 @@ impl<'a> EvalCommand<'a> {
         }
     }
 
+    /// When the command was created, as set by the last `CREATED`.
+    fn created(&self) -> Option<NaiveDate> {
+        self.statements().iter().rev().find_map(|s| match s {
+            Statement::Created(date) => Some(*date),
+            _ => None,
+        })
+    }
+
+    /// How much of the command is done, as set by the last `PROGRESS`.
+    fn progress(&self) -> Option<u8> {
+        self.statements().iter().rev().find_map(|s| match s {
+            Statement::Progress(percent) => Some(*percent),
+            _ => None,
+        })
+    }
+
+    /// The date other commands can be anchored to using `DATE after`.
+    ///
+    /// This is determined by the command's first `DATE` statement. Only
+    /// non-repeating fixed dates can be used as anchors.
+    pub fn anchor(&self, source: Source) -> Result<Option<NaiveDate>, Error<FileSource>> {
+        let spec = self.statements().iter().find_map(|s| match s {
+            Statement::Date(spec) => Some(&spec.value),
+            _ => None,
+        });
+        match spec {
+            Some(Spec::Date(spec)) => DateSpec::from(spec).anchor(source.file()),
+            _ => Ok(None),
+        }
+    }
+
     /// Last root date mentioned in any `DONE`.
     fn last_done_root(&self) -> Option<NaiveDate> {
         match self {
@@ -80,6 +124,14 @@ impl<'a> EvalCommand<'a> {
     }
 }
 
+/// When to remind the user of an entry.
+enum Remind {
+    /// Relative to the entry's start date
+    Delta(Spanned<Delta>),
+    /// At a fixed date
+    Date(Spanned<NaiveDate>),
+}
+
 pub struct CommandState<'a> {
     command: EvalCommand<'a>,
     source: Source,
@@ -87,14 +139,50 @@ pub struct CommandState<'a> {
 
     from: Option<NaiveDate>,
     until: Option<NaiveDate>,
-    remind: Option<Spanned<Delta>>,
+    remind: Option<Remind>,
+    /// The month the fiscal year starts in.
+    fiscal_year: u32,
+    /// The dates of all entries that can be referred to using `DATE after`.
+    anchors: &'a HashMap<String, NaiveDate>,
+
+    /// If set, occurrences of repeating tasks before this date are dropped
+    /// unless they were done.
+    future_from: Option<NaiveDate>,
+
+    /// How many occurrences have been generated so far.
+    occurrences: usize,
+    /// How many occurrences may be generated before evaluation fails.
+    max_occurrences: usize,
+
+    /// Root dates of the occurrences of each repeating spec that has no further
+    /// occurrences before [`Self::until`]. The latest remaining occurrence of
+    /// each of these specs ends its series.
+    series_ends: Vec<Vec<NaiveDate>>,
+
+    /// If set, `MOVE`s without a source are collected here instead of failing
+    /// the evaluation.
+    unreachable_moves: Option<Vec<Error<FileSource>>>,
+
+    /// If set, a human-readable description of each evaluation step is
+    /// collected here.
+    trace: Option<Vec<String>>,
+
+    /// The files' time zone and all other time zones used by `DATE`s, see
+    /// [`Self::with_timezones`].
+    timezones: Option<(&'a Tz, &'a HashMap<String, Tz>)>,
 
     dated: HashMap<NaiveDate, Entry>,
     undated: Vec<Entry>,
 }
 
 impl<'a> CommandState<'a> {
-    pub fn new(command: EvalCommand<'a>, source: Source, mut range: DateRange) -> Self {
+    pub fn new(
+        command: EvalCommand<'a>,
+        source: Source,
+        mut range: DateRange,
+        fiscal_year: u32,
+        anchors: &'a HashMap<String, NaiveDate>,
+    ) -> Self {
         // If we don't calculate entries for the source of the move command, it
         // fails even though the user did nothing wrong. Also, move commands (or
         // chains thereof) may move an initially out-of-range entry into range.
@@ -115,11 +203,56 @@ impl<'a> CommandState<'a> {
             from: None,
             until: None,
             remind: None,
+            fiscal_year,
+            anchors,
+            future_from: None,
+            occurrences: 0,
+            max_occurrences: MAX_OCCURRENCES,
+            series_ends: vec![],
+            unreachable_moves: None,
+            trace: None,
+            timezones: None,
             dated: HashMap::new(),
             undated: Vec::new(),
         }
     }
 
+    /// Drop occurrences of repeating tasks before `today`, except for those
+    /// that were done.
+    pub fn future_only(mut self, today: NaiveDate) -> Self {
+        self.future_from = Some(today);
+        self
+    }
+
+    /// Allow at most `max` occurrences instead of [`MAX_OCCURRENCES`], if
+    /// given.
+    pub fn max_occurrences(mut self, max: Option<usize>) -> Self {
+        if let Some(max) = max {
+            self.max_occurrences = max;
+        }
+        self
+    }
+
+    /// Collect `MOVE`s without a source instead of failing, see
+    /// [`Self::unreachable_moves`].
+    pub fn collect_unreachable_moves(mut self) -> Self {
+        self.unreachable_moves = Some(vec![]);
+        self
+    }
+
+    /// Convert times given in another time zone to `timezone` using `zones`.
+    /// Without this, such times are used as if they were in `timezone`.
+    pub fn with_timezones(mut self, timezone: &'a Tz, zones: &'a HashMap<String, Tz>) -> Self {
+        self.timezones = Some((timezone, zones));
+        self
+    }
+
+    /// Describe each evaluation step, see [`Self::trace`].
+    pub fn collect_trace(mut self) -> Self {
+        self.trace = Some(vec![]);
+        self
+    }
+
     pub fn eval(mut self) -> Result<Self, Error<FileSource>> {
         match self.command {
             EvalCommand::Task(task) => self.eval_task(task)?,
@@ -135,12 +268,35 @@ impl<'a> CommandState<'a> {
             .collect()
     }
 
+    pub fn unreachable_moves(self) -> Vec<Error<FileSource>> {
+        self.unreachable_moves.unwrap_or_default()
+    }
+
+    pub fn trace(&self) -> &[String] {
+        self.trace.as_deref().unwrap_or_default()
+    }
+
     // Helper functions
 
-    fn range_with_remind(&self) -> DateRange {
+    /// Record an evaluation step if a trace is being collected.
+    fn trace_step(&mut self, step: impl FnOnce() -> String) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(step());
+        }
+    }
+
+    /// The range repeating specs need to be evaluated in so that all entries
+    /// whose reminders are inside [`Self::range`] are found.
+    ///
+    /// Fails for `REMIND on`, which only makes sense for non-repeating specs.
+    fn range_with_remind(&self) -> Result<DateRange, Error<FileSource>> {
         match &self.remind {
-            None => self.range,
-            Some(delta) => self.range.expand_by(&delta.value),
+            Some(Remind::Delta(delta)) => Ok(self.range.expand_by(&delta.value)),
+            Some(Remind::Date(date)) => Err(Error::RemindDateRepeats {
+                index: self.source.file(),
+                span: date.span,
+            }),
+            None => Ok(self.range),
         }
     }
 
@@ -169,31 +325,73 @@ impl<'a> CommandState<'a> {
         kind: EntryKind,
         dates: Option<Dates>,
     ) -> Result<Entry, Error<FileSource>> {
-        let remind = if let (Some(dates), Some(delta)) = (dates, &self.remind) {
-            let index = self.source.file();
-            let start = dates.sorted().root();
-            let remind = delta.value.apply_date(index, dates.sorted().root())?;
-            if remind >= start {
-                return Err(Error::RemindDidNotMoveBackwards {
-                    index,
-                    span: delta.span,
-                    from: start,
-                    to: remind,
-                });
+        let index = self.source.file();
+        let remind = match (dates, &self.remind) {
+            (Some(dates), Some(Remind::Delta(delta))) => {
+                let start = dates.sorted().root();
+                let remind = delta.value.apply_date(index, dates.sorted().root())?;
+                if remind >= start {
+                    return Err(Error::RemindDidNotMoveBackwards {
+                        index,
+                        span: delta.span,
+                        from: start,
+                        to: remind,
+                    });
+                }
+                Some(remind)
             }
-            Some(remind)
-        } else {
-            None
+            (Some(dates), Some(Remind::Date(date))) => {
+                let start = dates.sorted().root();
+                if date.value >= start {
+                    return Err(Error::RemindNotBeforeStart {
+                        index,
+                        span: date.span,
+                        remind: date.value,
+                        start,
+                    });
+                }
+                Some(date.value)
+            }
+            _ => None,
         };
 
-        Ok(Entry::new(
+        let mut entry = Entry::new(
             self.source,
             kind,
             self.command.title(),
             self.command.has_description(),
             dates,
             remind,
-        ))
+        );
+        entry.created = self.command.created();
+        entry.progress = self.command.progress();
+        Ok(entry)
+    }
+
+    /// Count a newly generated occurrence, failing if the command has generated
+    /// more than [`Self::max_occurrences`] occurrences.
+    fn count_occurrence(&mut self, span: Span) -> Result<(), Error<FileSource>> {
+        self.occurrences += 1;
+        if self.occurrences > self.max_occurrences {
+            return Err(Error::TooManyOccurrences {
+                index: self.source.file(),
+                span,
+                max: self.max_occurrences,
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether an occurrence of a repeating spec should be dropped because it
+    /// lies before [`Self::future_from`].
+    ///
+    /// Done occurrences are added separately by [`Self::eval_done`], so they
+    /// are not affected by this.
+    fn is_past_occurrence(&self, dates: Dates) -> bool {
+        match (self.command, self.future_from) {
+            (EvalCommand::Task(_), Some(today)) => dates.root() < today,
+            _ => false,
+        }
     }
 
     /// Add an entry, respecting [`Self::from`] and [`Self::until`]. Does not
@@ -203,14 +401,17 @@ impl<'a> CommandState<'a> {
             let root = dates.root();
             if let Some(from) = self.from {
                 if root < from {
+                    self.trace_step(|| format!("Occurrence {dates} is before FROM"));
                     return;
                 }
             }
             if let Some(until) = self.until {
                 if until < root {
+                    self.trace_step(|| format!("Occurrence {dates} is after UNTIL"));
                     return;
                 }
             }
+            self.trace_step(|| format!("Occurrence {dates}"));
             self.dated.entry(root).or_insert(entry);
         } else {
             self.undated.push(entry);
@@ -221,6 +422,7 @@ impl<'a> CommandState<'a> {
     /// overwrites existing entries if a root date is specified.
     fn add_forced(&mut self, entry: Entry) {
         if let Some(dates) = entry.dates {
+            self.trace_step(|| format!("Occurrence {dates}"));
             self.dated.insert(dates.root(), entry);
         } else {
             self.undated.push(entry);
@@ -230,9 +432,34 @@ impl<'a> CommandState<'a> {
     // Actual evaluation
 
     fn has_date_stmt(statements: &[Statement]) -> bool {
-        statements
+        statements.iter().any(|s| {
+            matches!(
+                s,
+                Statement::Date(_) | Statement::BDate(_) | Statement::Active { .. }
+            )
+        })
+    }
+
+    /// Mark the latest remaining occurrence of each ending series as the last
+    /// one before [`Self::until`]. This happens after all `EXCEPT`s and `MOVE`s
+    /// so that they are taken into account.
+    fn mark_ends(&mut self) {
+        let lasts = self
+            .series_ends
             .iter()
-            .any(|s| matches!(s, Statement::Date(_) | Statement::BDate(_)))
+            .filter_map(|roots| {
+                roots
+                    .iter()
+                    .filter(|root| self.dated.contains_key(root))
+                    .max()
+                    .copied()
+            })
+            .collect::<Vec<_>>();
+        for root in lasts {
+            if let Some(entry) = self.dated.get_mut(&root) {
+                entry.ends = true;
+            }
+        }
     }
 
     fn eval_task(&mut self, task: &Task) -> Result<(), Error<FileSource>> {
@@ -240,6 +467,7 @@ impl<'a> CommandState<'a> {
             for statement in &task.statements {
                 self.eval_statement(statement)?;
             }
+            self.mark_ends();
         } else if task.done.is_empty() {
             self.add(self.entry_with_remind(self.command.kind(), None)?);
         }
@@ -256,6 +484,7 @@ impl<'a> CommandState<'a> {
             for statement in &note.statements {
                 self.eval_statement(statement)?;
             }
+            self.mark_ends();
         } else {
             self.add(self.entry_with_remind(self.command.kind(), None)?);
         }
@@ -264,28 +493,43 @@ impl<'a> CommandState<'a> {
     }
 
     fn eval_statement(&mut self, statement: &Statement) -> Result<(), Error<FileSource>> {
+        self.trace_step(|| statement.to_string().trim_end().to_string());
         match statement {
             Statement::Date(spec) => self.eval_date(spec)?,
             Statement::BDate(spec) => self.eval_bdate(spec)?,
+            Statement::Active { from, until } => self.eval_active(*from, *until)?,
             Statement::From(date) => self.from = *date,
             Statement::Until(date) => self.until = *date,
             Statement::Except(date) => self.eval_except(*date),
+            Statement::ExceptExpr(expr) => self.eval_except_expr(expr)?,
             Statement::Move {
                 span,
                 from,
                 to,
                 to_time,
             } => self.eval_move(*span, *from, *to, *to_time)?,
-            Statement::Remind(delta) => self.eval_remind(delta),
+            Statement::Remind(remind) => self.eval_remind(remind),
+            Statement::Created(_)
+            | Statement::Id(_)
+            | Statement::Tag(_)
+            | Statement::Waiting(_)
+            | Statement::Progress(_)
+            | Statement::Effort(_) => {}
         }
         Ok(())
     }
 
-    fn eval_date(&mut self, spec: &Spec) -> Result<(), Error<FileSource>> {
-        match spec {
-            Spec::Date(spec) => self.eval_date_spec(spec.into()),
-            Spec::Weekday(spec) => self.eval_formula_spec(spec.into()),
-            Spec::Formula(spec) => self.eval_formula_spec(spec.into()),
+    fn eval_date(&mut self, spec: &Spanned<Spec>) -> Result<(), Error<FileSource>> {
+        let span = spec.span;
+        match &spec.value {
+            Spec::Date(spec) => self.eval_date_spec(spec.into(), span),
+            Spec::Weekday(spec) => {
+                self.eval_formula_spec(FormulaSpec::from_weekday(spec, self.fiscal_year), span)
+            }
+            Spec::Formula(spec) => {
+                self.eval_formula_spec(FormulaSpec::new(spec, self.fiscal_year), span)
+            }
+            Spec::After(spec) => self.eval_after_spec(spec, span),
         }
     }
 
@@ -293,9 +537,17 @@ impl<'a> CommandState<'a> {
         self.eval_birthday_spec(spec)
     }
 
+    fn eval_active(&mut self, from: NaiveDate, until: NaiveDate) -> Result<(), Error<FileSource>> {
+        let dates = Dates::new(from, until);
+        self.add(self.entry_with_remind(EntryKind::ActiveNote, Some(dates))?);
+        Ok(())
+    }
+
     fn eval_except(&mut self, date: NaiveDate) {
         // TODO Error if nothing is removed?
-        self.dated.remove(&date);
+        if self.dated.remove(&date).is_some() {
+            self.trace_step(|| format!("Removed occurrence on {date}"));
+        }
     }
 
     fn eval_move(
@@ -325,24 +577,40 @@ impl<'a> CommandState<'a> {
             }
 
             dates = dates.move_by(delta);
+            self.trace_step(|| format!("Moved occurrence on {from} to {dates}"));
             entry.dates = Some(dates);
+            for root in self.series_ends.iter_mut().flatten() {
+                if *root == from {
+                    *root = dates.root();
+                }
+            }
             self.dated.insert(dates.root(), entry);
 
             Ok(())
         } else {
-            Err(Error::MoveWithoutSource {
+            let error = Error::MoveWithoutSource {
                 index: self.source.file(),
                 span,
-            })
+            };
+            match &mut self.unreachable_moves {
+                Some(unreachable) => {
+                    unreachable.push(error);
+                    Ok(())
+                }
+                None => Err(error),
+            }
         }
     }
 
-    fn eval_remind(&mut self, delta: &Option<Spanned<commands::Delta>>) {
-        if let Some(delta) = delta {
-            self.remind = Some(Spanned::new(delta.span, (&delta.value).into()));
-        } else {
-            self.remind = None;
-        }
+    fn eval_remind(&mut self, remind: &Option<commands::Remind>) {
+        self.remind = match remind {
+            Some(commands::Remind::Delta(delta)) => Some(Remind::Delta(Spanned::new(
+                delta.span,
+                (&delta.value).into(),
+            ))),
+            Some(commands::Remind::Date(date)) => Some(Remind::Date(*date)),
+            None => None,
+        };
     }
 
     fn eval_done(&mut self, done: &Done) -> Result<(), Error<FileSource>> {
@@ -350,6 +618,7 @@ impl<'a> CommandState<'a> {
             DoneKind::Done => EntryKind::TaskDone(done.done_at),
             DoneKind::Canceled => EntryKind::TaskCanceled(done.done_at),
         };
+        self.trace_step(|| done.to_string().trim_end().to_string());
         let dates = done.date.map(|date| date.into());
         self.add_forced(self.entry_with_remind(kind, dates)?);
         Ok(())
@@ -10,7 +10,7 @@ use super::super::EntryKind;
 
 impl CommandState<'_> {
     pub fn eval_birthday_spec(&mut self, spec: &BirthdaySpec) -> Result<(), Error<FileSource>> {
-        let range = match self.limit_from_until(self.range_with_remind()) {
+        let range = match self.limit_from_until(self.range_with_remind()?) {
             Some(range) => range,
             None => return Ok(()),
         };
@@ -44,3 +44,116 @@ impl CommandState<'_> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    use chrono::{Datelike, Duration, NaiveDate};
+
+    use crate::files::commands::Command;
+    use crate::files::{self, Source};
+
+    use super::super::super::{DateRange, Entry, EntryKind};
+    use super::super::{CommandState, EvalCommand};
+
+    fn eval(input: &str, range: DateRange) -> Vec<Entry> {
+        let file = files::parse_file(Path::new("test.today"), input).unwrap();
+        let note = match &file.commands[0].value {
+            Command::Note(note) => note,
+            _ => panic!("expected note"),
+        };
+
+        CommandState::new(
+            EvalCommand::Note(note),
+            Source::new(0, 0),
+            range,
+            1,
+            &HashMap::new(),
+        )
+        .eval()
+        .unwrap()
+        .entries()
+    }
+
+    #[test]
+    fn birthday_with_remind() {
+        let from = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let until = NaiveDate::from_ymd_opt(2024, 6, 30).unwrap();
+        let range = DateRange::new(from, until);
+        let entries = eval("NOTE Bob\nREMIND -1w\nBDATE 1990-06-15\n", range);
+
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        let birthday = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        assert!(matches!(entry.kind, EntryKind::Birthday(Some(34))));
+        assert_eq!(entry.dates.unwrap().root(), birthday);
+        assert_eq!(entry.remind, Some(birthday - Duration::days(7)));
+    }
+
+    #[test]
+    fn birthday_from_until() {
+        let from = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap();
+        let until = NaiveDate::from_ymd_opt(2026, 12, 31).unwrap();
+        let range = DateRange::new(from, until);
+        let years = |entries: Vec<Entry>| {
+            let mut years = entries
+                .into_iter()
+                .map(|entry| entry.dates.unwrap().root().year())
+                .collect::<Vec<_>>();
+            years.sort_unstable();
+            years
+        };
+
+        let entries = eval(
+            "NOTE Bob\nFROM 2022-01-01\nUNTIL 2024-12-31\nBDATE 1990-06-15\n",
+            range,
+        );
+        assert_eq!(years(entries), vec![2022, 2023, 2024]);
+
+        // Birthdays on the boundaries are still included
+        let entries = eval(
+            "NOTE Bob\nFROM 2022-06-15\nUNTIL 2023-06-15\nBDATE 1990-06-15\n",
+            range,
+        );
+        assert_eq!(years(entries), vec![2022, 2023]);
+
+        // A window without any birthday in it suppresses all of them
+        let entries = eval(
+            "NOTE Bob\nFROM 2022-07-01\nUNTIL 2023-05-31\nBDATE 1990-06-15\n",
+            range,
+        );
+        assert!(entries.is_empty());
+    }
+
+    /// The birthdays within `range` along with their ages.
+    ///
+    /// Birthdays are evaluated for whole years, so occurrences outside of the
+    /// range are filtered out here.
+    fn ages(bdate: &str, range: DateRange) -> Vec<(NaiveDate, i32)> {
+        let entries = eval(&format!("NOTE Bob\nBDATE {bdate}\n"), range);
+        entries
+            .into_iter()
+            .filter(|entry| range.contains(entry.dates.unwrap().root()))
+            .map(|entry| match entry.kind {
+                EntryKind::Birthday(Some(age)) => (entry.dates.unwrap().root(), age),
+                _ => panic!("expected birthday with age"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn birthday_age_across_year_boundary() {
+        let ymd = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        // Viewed in January, the upcoming december birthday is this year's
+        let range = DateRange::new(ymd(2025, 1, 10), ymd(2025, 12, 31));
+        assert_eq!(ages("1990-12-20", range), vec![(ymd(2025, 12, 20), 35)]);
+
+        // A range spanning new year uses each occurrence's own year
+        let range = DateRange::new(ymd(2024, 12, 28), ymd(2025, 1, 10));
+        assert_eq!(ages("1990-12-30", range), vec![(ymd(2024, 12, 30), 34)]);
+        assert_eq!(ages("1990-01-05", range), vec![(ymd(2025, 1, 5), 35)]);
+    }
+}
@@ -1,7 +1,9 @@
+use std::cmp::Ordering;
+
 use chrono::NaiveDate;
 
 use crate::files::commands;
-use crate::files::primitives::{Spanned, Time};
+use crate::files::primitives::{Span, Spanned, Time};
 use crate::files::FileSource;
 
 use super::super::command::CommandState;
@@ -14,6 +16,8 @@ pub struct DateSpec {
     pub start: NaiveDate,
     pub start_delta: Delta,
     pub start_time: Option<Time>,
+    /// Time zone the times are given in if it differs from the file's.
+    pub zone: Option<String>,
     pub end_delta: Delta,
     pub repeat: Option<Spanned<Delta>>,
     pub start_at_done: bool,
@@ -45,6 +49,16 @@ impl From<&commands::DateSpec> for DateSpec {
                 .steps
                 .push(Spanned::new(time.span, DeltaStep::Time(time.value)));
         }
+        if let Some(span) = spec.end_exclusive {
+            // An exclusive end lies one step before the specified end, which
+            // is a minute for timed specs and a day otherwise.
+            let step = if spec.start_time.is_some() {
+                DeltaStep::Minute(-1)
+            } else {
+                DeltaStep::Day(-1)
+            };
+            end_delta.steps.push(Spanned::new(span, step));
+        }
 
         let repeat: Option<Spanned<Delta>> = spec
             .repeat
@@ -60,6 +74,7 @@ impl From<&commands::DateSpec> for DateSpec {
             start: spec.start,
             start_delta,
             start_time: spec.start_time,
+            zone: spec.start_zone.as_ref().map(|zone| zone.value.clone()),
             end_delta,
             repeat,
             start_at_done,
@@ -68,12 +83,95 @@ impl From<&commands::DateSpec> for DateSpec {
 }
 
 impl DateSpec {
-    /// Find the start date and range for the date spec calculation.
+    /// A non-repeating date spec relative to the date of an anchor entry.
+    pub fn after(spec: &commands::AfterSpec, anchor: NaiveDate) -> Self {
+        let start_delta: Delta = spec
+            .start_delta
+            .as_ref()
+            .map(|delta| delta.into())
+            .unwrap_or_default();
+
+        let mut end_delta: Delta = spec
+            .end_delta
+            .as_ref()
+            .map(|delta| delta.into())
+            .unwrap_or_default();
+        if let Some(time) = spec.end_time {
+            end_delta
+                .steps
+                .push(Spanned::new(time.span, DeltaStep::Time(time.value)));
+        }
+
+        Self {
+            start: anchor,
+            start_delta,
+            start_time: spec.start_time,
+            zone: None,
+            end_delta,
+            repeat: None,
+            start_at_done: false,
+        }
+    }
+
+    /// The date other entries are anchored to, or `None` if the spec repeats.
+    pub fn anchor(&self, index: FileSource) -> Result<Option<NaiveDate>, Error<FileSource>> {
+        if self.repeat.is_some() {
+            return Ok(None);
+        }
+        Ok(Some(self.start_delta.apply_date(index, self.start)?))
+    }
+
+    /// Warn if consecutive occurrences of a repeating spec overlap because an
+    /// occurrence reaches the start of the next one. Ends are inclusive, so an
+    /// occurrence ending exactly one period after its start already overlaps.
+    /// Exclusive ends (`--]`) are already one step shorter in
+    /// [`Self::end_delta`].
+    ///
+    /// If an occurrence ends on the day the next one starts, timed specs only
+    /// overlap if the end isn't before the next start.
+    pub fn overlap(&self, index: FileSource, span: Span) -> Option<Error<FileSource>> {
+        let repeat = self.repeat.as_ref()?;
+        let length = self.end_delta.lower_bound();
+        let period = repeat.value.lower_bound();
+        let overlaps = match length.cmp(&period) {
+            Ordering::Less => false,
+            Ordering::Equal => self.start_time.is_none() || self.reaches_next(index, repeat),
+            Ordering::Greater => true,
+        };
+        if overlaps {
+            Some(Error::OverlappingOccurrences {
+                index,
+                span,
+                length,
+                period,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Whether the first occurrence ends at or after the start of the second
+    /// one. If either can't be evaluated, they are assumed to overlap.
+    fn reaches_next(&self, index: FileSource, repeat: &Spanned<Delta>) -> bool {
+        let first = self.dates(index, self.start);
+        let second = Self::step(index, self.start, repeat).and_then(|next| self.dates(index, next));
+        match (first, second) {
+            (Ok(first), Ok(second)) => first.other_with_time() >= second.root_with_time(),
+            _ => true,
+        }
+    }
+
+    /// Find the start date and range for the date spec calculation, based on
+    /// the command's `range` including reminders.
     ///
     /// Returns a tuple `(start, skip, range)` where `skip` is `true` if the
     /// `start` date itself should be skipped (and thus not result in an entry).
     /// This may be necessary if [`Self::start_at_done`] is set.
-    fn start_and_range(&self, s: &CommandState<'_>) -> Option<(NaiveDate, bool, DateRange)> {
+    fn start_and_range(
+        &self,
+        s: &CommandState<'_>,
+        range: DateRange,
+    ) -> Option<(NaiveDate, bool, DateRange)> {
         let (start, skip, range) = match s.command {
             EvalCommand::Task(_) => {
                 let (start, skip) = s
@@ -87,8 +185,7 @@ impl DateSpec {
                     .last_done_root()
                     .map(|date| date.succ_opt().unwrap())
                     .unwrap_or(self.start);
-                let range = s
-                    .range_with_remind()
+                let range = range
                     .expand_by(&self.end_delta)
                     .move_by(&self.start_delta)
                     .with_from(range_from)?;
@@ -96,10 +193,7 @@ impl DateSpec {
             }
             EvalCommand::Note(_) => {
                 let start = self.start;
-                let range = s
-                    .range_with_remind()
-                    .expand_by(&self.end_delta)
-                    .move_by(&self.start_delta);
+                let range = range.expand_by(&self.end_delta).move_by(&self.start_delta);
                 (start, false, range)
             }
         };
@@ -138,26 +232,576 @@ impl DateSpec {
 }
 
 impl CommandState<'_> {
-    pub fn eval_date_spec(&mut self, spec: DateSpec) -> Result<(), Error<FileSource>> {
+    pub fn eval_date_spec(&mut self, spec: DateSpec, span: Span) -> Result<(), Error<FileSource>> {
         let index = self.source.file();
         if let Some(repeat) = &spec.repeat {
-            if let Some((mut start, skip, range)) = spec.start_and_range(self) {
+            let range = self.range_with_remind()?;
+            if let Some((mut start, skip, range)) = spec.start_and_range(self, range) {
+                self.trace_step(|| format!("Range {} -- {}", range.from(), range.until()));
                 if skip {
+                    self.trace_step(|| format!("Starting after last completion {start}"));
                     start = DateSpec::step(index, start, repeat)?;
                 }
                 while start < range.from() {
                     start = DateSpec::step(index, start, repeat)?;
                 }
+                let mut missed = 0;
+                let mut roots = vec![];
                 while start <= range.until() {
-                    let dates = spec.dates(index, start)?;
-                    self.add(self.entry_with_remind(self.command.kind(), Some(dates))?);
-                    start = DateSpec::step(index, start, repeat)?;
+                    self.count_occurrence(span)?;
+                    let dates = self.in_timezone(&spec, span, spec.dates(index, start)?)?;
+                    let next = DateSpec::step(index, start, repeat)?;
+                    if self.is_past_occurrence(dates) {
+                        self.trace_step(|| format!("Occurrence {dates} is in the past"));
+                    } else {
+                        let mut entry = self.entry_with_remind(self.command.kind(), Some(dates))?;
+                        if spec.start_at_done {
+                            entry.missed = missed;
+                        }
+                        roots.push(dates.root());
+                        self.add(entry);
+                    }
+                    missed += 1;
+                    start = next;
                 }
+                let ends = match self.until {
+                    Some(until) => spec.start_delta.apply_date(index, start)? > until,
+                    None => false,
+                };
+                if ends {
+                    self.series_ends.push(roots);
+                }
+            } else {
+                self.trace_step(|| "Range doesn't contain any occurrences".to_string());
             }
         } else {
-            let dates = spec.dates(index, spec.start)?;
+            let dates = self.in_timezone(&spec, span, spec.dates(index, spec.start)?)?;
             self.add(self.entry_with_remind(self.command.kind(), Some(dates))?);
         }
         Ok(())
     }
+
+    /// Convert dates from the spec's time zone to the files' time zone.
+    fn in_timezone(
+        &self,
+        spec: &DateSpec,
+        span: Span,
+        dates: Dates,
+    ) -> Result<Dates, Error<FileSource>> {
+        match (&spec.zone, self.timezones) {
+            (Some(zone), Some((timezone, zones))) => match zones.get(zone) {
+                Some(zone) => dates
+                    .convert_timezone(&zone, &timezone)
+                    .map_err(|(date, time)| Error::NonexistentTime {
+                        index: self.source.file(),
+                        span,
+                        date,
+                        time,
+                    }),
+                None => Ok(dates),
+            },
+            _ => Ok(dates),
+        }
+    }
+
+    pub fn eval_after_spec(
+        &mut self,
+        spec: &commands::AfterSpec,
+        span: Span,
+    ) -> Result<(), Error<FileSource>> {
+        match self.anchors.get(&spec.id.value) {
+            Some(&anchor) => self.eval_date_spec(DateSpec::after(spec, anchor), span),
+            None => Err(Error::UnknownAnchor {
+                index: self.source.file(),
+                span: spec.id.span,
+                id: spec.id.value.clone(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    use chrono::NaiveDate;
+
+    use crate::files::commands::{self, Delta, DeltaStep, Repeat, Spec, Statement, Task};
+    use crate::files::primitives::Spanned;
+    use crate::files::{self, FileSource, Source};
+    use crate::testing;
+
+    use super::super::super::entry::Entries;
+    use super::super::super::{DateRange, EntryKind, EntryMode, Error};
+    use super::super::{CommandState, EvalCommand};
+    use super::DateSpec;
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn too_many_occurrences() {
+        // DATE 2000-01-01; +d
+        let spec = commands::DateSpec {
+            start: ymd(2000, 1, 1),
+            start_delta: None,
+            start_time: None,
+            start_zone: None,
+            end: None,
+            end_delta: None,
+            end_time: None,
+            end_exclusive: None,
+            repeat: Some(Repeat {
+                start_at_done: false,
+                delta: Spanned::dummy(Delta(vec![Spanned::dummy(DeltaStep::Day(1))])),
+            }),
+        };
+        let task = Task {
+            title: "Daily".to_string(),
+            statements: vec![Statement::Date(Spanned::dummy(Spec::Date(spec)))],
+            done: vec![],
+            desc: vec![],
+        };
+
+        let command = EvalCommand::Task(&task);
+        let range = DateRange::new(ymd(2000, 1, 1), ymd(2999, 12, 31));
+        let result =
+            CommandState::new(command, Source::new(0, 0), range, 1, &HashMap::new()).eval();
+        assert!(matches!(result, Err(Error::TooManyOccurrences { .. })));
+
+        let range = DateRange::new(ymd(2000, 1, 1), ymd(2000, 1, 31));
+        let result = CommandState::new(command, Source::new(0, 0), range, 1, &HashMap::new())
+            .max_occurrences(Some(10))
+            .eval();
+        assert!(matches!(
+            result,
+            Err(Error::TooManyOccurrences { max: 10, .. })
+        ));
+        let result = CommandState::new(command, Source::new(0, 0), range, 1, &HashMap::new())
+            .max_occurrences(Some(31))
+            .eval();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn anchored_date() {
+        let file = files::parse_file(
+            Path::new("test.today"),
+            "NOTE Kickoff\nID kickoff\nDATE 2024-03-01 +1d\n\n\
+             TASK Follow up\nDATE after kickoff +3d 10:00 -- 11:00\n",
+        )
+        .unwrap();
+        let kickoff = EvalCommand::new(&file.commands[0].value).unwrap();
+        let anchor = kickoff.anchor(Source::new(0, 0)).unwrap();
+        assert_eq!(anchor, Some(ymd(2024, 3, 2)));
+        let anchors = HashMap::from([("kickoff".to_string(), anchor.unwrap())]);
+
+        let command = EvalCommand::new(&file.commands[1].value).unwrap();
+        let range = DateRange::new(ymd(2024, 3, 1), ymd(2024, 3, 31));
+        let entries = CommandState::new(command, Source::new(0, 0), range, 1, &anchors)
+            .eval()
+            .unwrap()
+            .entries();
+
+        assert_eq!(entries.len(), 1);
+        let dates = entries[0].dates.unwrap();
+        assert_eq!(dates.root(), ymd(2024, 3, 5));
+        assert_eq!(format!("{dates}"), "2024-03-05 10:00 -- 11:00");
+    }
+
+    #[test]
+    fn repeating_anchor() {
+        let file = files::parse_file(
+            Path::new("test.today"),
+            "NOTE Standup\nID standup\nDATE 2024-03-01; +w\n",
+        )
+        .unwrap();
+        let command = EvalCommand::new(&file.commands[0].value).unwrap();
+        assert_eq!(command.anchor(Source::new(0, 0)).unwrap(), None);
+    }
+
+    #[test]
+    fn unknown_anchor() {
+        let file = files::parse_file(
+            Path::new("test.today"),
+            "TASK Follow up\nDATE after kickoff +3d\n",
+        )
+        .unwrap();
+        let command = EvalCommand::new(&file.commands[0].value).unwrap();
+        let range = DateRange::new(ymd(2024, 3, 1), ymd(2024, 3, 31));
+        let result =
+            CommandState::new(command, Source::new(0, 0), range, 1, &HashMap::new()).eval();
+        assert!(matches!(result, Err(Error::UnknownAnchor { .. })));
+    }
+
+    #[test]
+    fn duplicate_anchor() {
+        let files = testing::load(
+            "TIMEZONE UTC\n\n\
+             NOTE Kickoff\nID kickoff\nDATE 2024-03-01\n\n\
+             NOTE Other kickoff\nID kickoff\nDATE 2024-03-08\n\n\
+             TASK Follow up\nDATE after kickoff +3d\n",
+        );
+        let range = DateRange::new(ymd(2024, 3, 1), ymd(2024, 3, 31));
+        let result = files.eval(EntryMode::Relevant, range);
+        assert!(matches!(
+            result,
+            Err(Error::DuplicateId { span1, span2, id, .. })
+                if id == "kickoff" && span1.start < span2.start
+        ));
+    }
+
+    #[test]
+    fn remind_on_date() {
+        let file = files::parse_file(
+            Path::new("test.today"),
+            "TASK Taxes\nREMIND on 2024-05-01\nDATE 2024-05-31\n",
+        )
+        .unwrap();
+        let command = EvalCommand::new(&file.commands[0].value).unwrap();
+        let range = DateRange::new(ymd(2024, 5, 1), ymd(2024, 5, 31));
+        let entries = CommandState::new(command, Source::new(0, 0), range, 1, &HashMap::new())
+            .eval()
+            .unwrap()
+            .entries();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].remind, Some(ymd(2024, 5, 1)));
+    }
+
+    #[test]
+    fn remind_on_date_not_before_start() {
+        let file = files::parse_file(
+            Path::new("test.today"),
+            "TASK Taxes\nREMIND on 2024-05-31\nDATE 2024-05-31\n",
+        )
+        .unwrap();
+        let command = EvalCommand::new(&file.commands[0].value).unwrap();
+        let range = DateRange::new(ymd(2024, 5, 1), ymd(2024, 5, 31));
+        let result =
+            CommandState::new(command, Source::new(0, 0), range, 1, &HashMap::new()).eval();
+        assert!(matches!(result, Err(Error::RemindNotBeforeStart { .. })));
+    }
+
+    #[test]
+    fn remind_on_date_before_range() {
+        let file = files::parse_file(
+            Path::new("test.today"),
+            "TASK Taxes\nREMIND on 2024-05-01\nDATE 2024-05-31\n",
+        )
+        .unwrap();
+        let command = EvalCommand::new(&file.commands[0].value).unwrap();
+        // The range contains the remind date but not the entry's start
+        let range = DateRange::new(ymd(2024, 5, 1), ymd(2024, 5, 10));
+        let mut entries = Entries::new(EntryMode::Relevant, range);
+        CommandState::new(command, Source::new(0, 0), range, 1, &HashMap::new())
+            .eval()
+            .unwrap()
+            .entries()
+            .into_iter()
+            .for_each(|entry| entries.add(entry));
+
+        let entries = entries.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].remind, Some(ymd(2024, 5, 1)));
+    }
+
+    #[test]
+    fn remind_on_date_repeating() {
+        let file = files::parse_file(
+            Path::new("test.today"),
+            "TASK Rent\nREMIND on 2024-05-01\nDATE 2024-05-31; +m\n",
+        )
+        .unwrap();
+        let command = EvalCommand::new(&file.commands[0].value).unwrap();
+        let range = DateRange::new(ymd(2024, 5, 1), ymd(2024, 7, 31));
+        let result =
+            CommandState::new(command, Source::new(0, 0), range, 1, &HashMap::new()).eval();
+        assert!(matches!(result, Err(Error::RemindDateRepeats { .. })));
+    }
+
+    #[test]
+    fn remind_business_days() {
+        let remind = |remind: &str| {
+            // 2024-05-06 is a monday
+            let input = format!("TASK Report\nREMIND {remind}\nDATE 2024-05-06\n");
+            let file = files::parse_file(Path::new("test.today"), &input).unwrap();
+            let command = EvalCommand::new(&file.commands[0].value).unwrap();
+            let range = DateRange::new(ymd(2024, 5, 1), ymd(2024, 5, 31));
+            let entries = CommandState::new(command, Source::new(0, 0), range, 1, &HashMap::new())
+                .eval()
+                .unwrap()
+                .entries();
+            entries[0].remind
+        };
+
+        assert_eq!(remind("-1b"), Some(ymd(2024, 5, 3)));
+        assert_eq!(remind("-2b"), Some(ymd(2024, 5, 2)));
+        assert_eq!(remind("-1d"), Some(ymd(2024, 5, 5)));
+    }
+
+    #[test]
+    fn future_only() {
+        let count = |input: &str, future_only: bool| {
+            let file = files::parse_file(Path::new("test.today"), input).unwrap();
+            let command = EvalCommand::new(&file.commands[0].value).unwrap();
+            let range = DateRange::new(ymd(2024, 3, 1), ymd(2024, 3, 10));
+            let anchors = HashMap::new();
+            let mut state = CommandState::new(command, Source::new(0, 0), range, 1, &anchors);
+            if future_only {
+                state = state.future_only(ymd(2024, 3, 6));
+            }
+            state.eval().unwrap().entries().len()
+        };
+
+        let daily = "TASK Water\nDATE 2024-03-01; +d\n";
+        assert_eq!(count(daily, false), 10);
+        assert_eq!(count(daily, true), 5);
+
+        // Done occurrences are kept
+        let done = "TASK Water\nDATE 2024-03-01; +d\nDONE [2024-03-03] 2024-03-03\n";
+        assert_eq!(count(done, false), 8);
+        assert_eq!(count(done, true), 6);
+
+        // Formulas are affected as well
+        let formula = "TASK Water\nFROM 2024-03-01\nDATE (true)\n";
+        assert_eq!(count(formula, false), 10);
+        assert_eq!(count(formula, true), 5);
+
+        // Notes are not
+        let note = "NOTE Water\nDATE 2024-03-01; +d\n";
+        assert_eq!(count(note, false), 10);
+        assert_eq!(count(note, true), 10);
+    }
+
+    #[test]
+    fn exclusive_end() {
+        let dates = |input: &str| {
+            let file = files::parse_file(Path::new("test.today"), input).unwrap();
+            let command = EvalCommand::new(&file.commands[0].value).unwrap();
+            let range = DateRange::new(ymd(2024, 3, 1), ymd(2024, 3, 31));
+            let entries = CommandState::new(command, Source::new(0, 0), range, 1, &HashMap::new())
+                .eval()
+                .unwrap()
+                .entries();
+            assert_eq!(entries.len(), 1);
+            format!("{}", entries[0].dates.unwrap())
+        };
+
+        assert_eq!(
+            dates("NOTE Trip\nDATE 2024-03-04 -- 2024-03-08\n"),
+            "2024-03-04 -- 2024-03-08"
+        );
+        assert_eq!(
+            dates("NOTE Trip\nDATE 2024-03-04 --] 2024-03-08\n"),
+            "2024-03-04 -- 2024-03-07"
+        );
+        assert_eq!(
+            dates("NOTE Meeting\nDATE 2024-03-04 10:00 -- 11:00\n"),
+            "2024-03-04 10:00 -- 11:00"
+        );
+        assert_eq!(
+            dates("NOTE Meeting\nDATE 2024-03-04 10:00 --] 11:00\n"),
+            "2024-03-04 10:00 -- 10:59"
+        );
+    }
+
+    fn ends(input: &str, range: DateRange) -> Vec<(NaiveDate, bool)> {
+        let file = files::parse_file(Path::new("test.today"), input).unwrap();
+        let command = EvalCommand::new(&file.commands[0].value).unwrap();
+        let mut ends = CommandState::new(command, Source::new(0, 0), range, 1, &HashMap::new())
+            .eval()
+            .unwrap()
+            .entries()
+            .into_iter()
+            .map(|entry| (entry.root().unwrap(), entry.ends))
+            .collect::<Vec<_>>();
+        ends.sort();
+        ends
+    }
+
+    #[test]
+    fn last_occurrence_ends() {
+        let range = DateRange::new(ymd(2024, 3, 1), ymd(2024, 3, 31));
+        assert_eq!(
+            ends("TASK Water\nUNTIL 2024-03-20\nDATE 2024-03-01; +w\n", range),
+            vec![
+                (ymd(2024, 3, 1), false),
+                (ymd(2024, 3, 8), false),
+                (ymd(2024, 3, 15), true),
+            ]
+        );
+
+        // The series may end after the range
+        let short = DateRange::new(ymd(2024, 3, 1), ymd(2024, 3, 10));
+        assert_eq!(
+            ends("TASK Water\nUNTIL 2024-03-20\nDATE 2024-03-01; +w\n", short),
+            vec![(ymd(2024, 3, 1), false), (ymd(2024, 3, 8), false)]
+        );
+
+        // Without UNTIL, no occurrence ends the series
+        assert!(ends("TASK Water\nDATE 2024-03-01; +w\n", range)
+            .iter()
+            .all(|(_, ends)| !ends));
+    }
+
+    #[test]
+    fn last_remaining_occurrence_ends() {
+        let range = DateRange::new(ymd(2024, 3, 1), ymd(2024, 3, 31));
+        assert_eq!(
+            ends(
+                "TASK Water\nUNTIL 2024-03-20\nDATE 2024-03-01; +w\nEXCEPT 2024-03-15\n",
+                range
+            ),
+            vec![(ymd(2024, 3, 1), false), (ymd(2024, 3, 8), true)]
+        );
+        assert_eq!(
+            ends(
+                "TASK Water\nUNTIL 2024-03-20\nDATE 2024-03-01; +w\nMOVE 2024-03-08 TO 2024-03-18\n",
+                range
+            ),
+            vec![
+                (ymd(2024, 3, 1), false),
+                (ymd(2024, 3, 15), false),
+                (ymd(2024, 3, 18), true),
+            ]
+        );
+
+        // Occurrences of other DATE statements don't end the series
+        assert_eq!(
+            ends(
+                "TASK Water\nUNTIL 2024-03-20\nDATE 2024-03-01; +w\nDATE 2024-03-18\n",
+                range
+            ),
+            vec![
+                (ymd(2024, 3, 1), false),
+                (ymd(2024, 3, 8), false),
+                (ymd(2024, 3, 15), true),
+                (ymd(2024, 3, 18), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn missed_occurrences() {
+        let file = files::parse_file(
+            Path::new("test.today"),
+            "TASK Water\nDATE 2024-01-01; done +w\nDONE [2024-01-01] 2024-01-01\n",
+        )
+        .unwrap();
+        let command = EvalCommand::new(&file.commands[0].value).unwrap();
+        let range = DateRange::new(ymd(2024, 1, 1), ymd(2024, 1, 31));
+        let mut entries = CommandState::new(command, Source::new(0, 0), range, 1, &HashMap::new())
+            .eval()
+            .unwrap()
+            .entries();
+        entries.sort_by_key(|entry| entry.root());
+
+        let missed = entries
+            .iter()
+            .filter(|entry| matches!(entry.kind, EntryKind::Task))
+            .map(|entry| (entry.root().unwrap(), entry.missed))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            missed,
+            vec![
+                (ymd(2024, 1, 8), 0),
+                (ymd(2024, 1, 15), 1),
+                (ymd(2024, 1, 22), 2),
+                (ymd(2024, 1, 29), 3),
+            ]
+        );
+    }
+
+    fn overlap(input: &str) -> Option<Error<FileSource>> {
+        let file = files::parse_file(Path::new("test.today"), input).unwrap();
+        let command = EvalCommand::new(&file.commands[0].value).unwrap();
+        match &command.statements()[0] {
+            Statement::Date(spec) => match &spec.value {
+                Spec::Date(date_spec) => {
+                    DateSpec::from(date_spec).overlap(Source::new(0, 0).file(), spec.span)
+                }
+                _ => panic!("expected a date spec"),
+            },
+            _ => panic!("expected a DATE statement"),
+        }
+    }
+
+    #[test]
+    fn overlapping_occurrences() {
+        let result = overlap("TASK Trip\nDATE 2024-01-01 -- 2024-01-10; +w\n");
+        assert!(matches!(
+            result,
+            Some(Error::OverlappingOccurrences {
+                span,
+                length: 9,
+                period: 7,
+                ..
+            }) if span.start == 10
+        ));
+    }
+
+    #[test]
+    fn overlapping_occurrences_at_boundary() {
+        // The inclusive end coincides with the start of the next occurrence
+        let result = overlap("TASK Trip\nDATE 2024-01-01 -- 2024-01-08; +w\n");
+        assert!(matches!(
+            result,
+            Some(Error::OverlappingOccurrences {
+                length: 7,
+                period: 7,
+                ..
+            })
+        ));
+
+        // With times, the end must also reach the time the next one starts
+        let result = overlap("TASK Trip\nDATE 2024-01-01 10:00 -- 2024-01-08 10:00; +w\n");
+        assert!(result.is_some());
+    }
+
+    #[test]
+    fn non_overlapping_occurrences() {
+        assert!(overlap("TASK Trip\nDATE 2024-01-01 -- 2024-01-03; +w\n").is_none());
+        assert!(overlap("TASK Trip\nDATE 2024-01-01 -- 2024-01-07; +w\n").is_none());
+        assert!(overlap("TASK Trip\nDATE 2024-01-01 --] 2024-01-08; +w\n").is_none());
+        assert!(overlap("TASK Trip\nDATE 2024-01-01 -- 2024-01-10\n").is_none());
+        assert!(overlap("TASK Trip\nDATE 2024-01-01 10:00 -- 2024-01-08 09:00; +w\n").is_none());
+    }
+
+    #[test]
+    fn start_in_skipped_time() {
+        // Samoa skipped 2011-12-30 entirely when moving across the date line
+        let files =
+            testing::load("TIMEZONE UTC\n\nNOTE Call\nDATE 2011-12-30 10:00 Pacific/Apia\n");
+
+        let range = DateRange::new(ymd(2011, 12, 29), ymd(2011, 12, 31));
+        let result = files.eval(EntryMode::Relevant, range);
+        assert!(matches!(result, Err(Error::NonexistentTime { .. })));
+    }
+
+    #[test]
+    fn start_in_other_time_zone() {
+        let range = DateRange::new(ymd(2024, 3, 9), ymd(2024, 3, 10));
+        let (_, entries) = testing::load_eval(
+            "TIMEZONE UTC\n\n\
+             NOTE Call\nDATE 2024-03-09 14:00 America/New_York -- 15:00; +d\n",
+            range,
+        );
+        let mut dates = entries
+            .into_iter()
+            .map(|entry| entry.dates.unwrap())
+            .collect::<Vec<_>>();
+        dates.sort_by_key(|dates| dates.root());
+
+        // DST starts in New York on 2024-03-10, moving from UTC-5 to UTC-4
+        assert_eq!(
+            dates
+                .iter()
+                .map(|dates| dates.to_string())
+                .collect::<Vec<_>>(),
+            ["2024-03-09 19:00 -- 20:00", "2024-03-10 18:00 -- 19:00"]
+        );
+    }
 }
@@ -40,16 +40,53 @@ pub enum Var {
     IsoYear,
     IsoYearLength,
     IsoWeek,
+    IsoMonthWeek,
+    FiscalYear(u32),
+    FiscalYearDay(u32),
     Weekday,
     Easter(Span),
+    EasterNext(Span),
+    EasterPrev(Span),
+    DaysToEaster(Span),
     IsWeekday,
     IsWeekend,
     IsLeapYear,
     IsIsoLeapYear,
 }
 
+/// The date of easter in the specified year.
+fn easter<S>(index: S, span: Span, date: NaiveDate, year: i32) -> Result<NaiveDate, Error<S>> {
+    let e = computus::gregorian(year).map_err(|e| Error::Easter {
+        index,
+        span,
+        date,
+        msg: e,
+    })?;
+    Ok(NaiveDate::from_ymd_opt(e.year, e.month, e.day).unwrap())
+}
+
+/// The date of the first easter on or after `date`.
+fn next_easter<S: Copy>(index: S, span: Span, date: NaiveDate) -> Result<NaiveDate, Error<S>> {
+    let e = easter(index, span, date, date.year())?;
+    if e >= date {
+        Ok(e)
+    } else {
+        easter(index, span, date, date.year() + 1)
+    }
+}
+
+/// The date of the last easter on or before `date`.
+fn prev_easter<S: Copy>(index: S, span: Span, date: NaiveDate) -> Result<NaiveDate, Error<S>> {
+    let e = easter(index, span, date, date.year())?;
+    if e <= date {
+        Ok(e)
+    } else {
+        easter(index, span, date, date.year() - 1)
+    }
+}
+
 impl Var {
-    fn eval<S>(self, index: S, date: NaiveDate) -> Result<i64, Error<S>> {
+    fn eval<S: Copy>(self, index: S, date: NaiveDate) -> Result<i64, Error<S>> {
         Ok(match self {
             Self::JulianDay => date.num_days_from_ce().into(),
             Self::Year => date.year().into(),
@@ -78,22 +115,23 @@ impl Var {
             Self::IsoYear => date.iso_week().year().into(),
             Self::IsoYearLength => util::iso_year_length(date.iso_week().year()).into(),
             Self::IsoWeek => date.iso_week().week().into(),
+            Self::IsoMonthWeek => {
+                let first = date.with_day(1).unwrap();
+                let offset = first.weekday().num_days_from_monday();
+                ((date.day0() + offset).div_euclid(7) + 1).into()
+            }
+            Self::FiscalYear(start) => util::fiscal_year(date, start).into(),
+            Self::FiscalYearDay(start) => {
+                (date - util::fiscal_year_start(date, start)).num_days() + 1
+            }
             Self::Weekday => {
                 let wd: Weekday = date.weekday().into();
                 wd.num().into()
             }
-            Self::Easter(span) => {
-                let e = computus::gregorian(date.year()).map_err(|e| Error::Easter {
-                    index,
-                    span,
-                    date,
-                    msg: e,
-                })?;
-                NaiveDate::from_ymd_opt(e.year, e.month, e.day)
-                    .unwrap()
-                    .ordinal()
-                    .into()
-            }
+            Self::Easter(span) => easter(index, span, date, date.year())?.ordinal().into(),
+            Self::EasterNext(span) => next_easter(index, span, date)?.ordinal().into(),
+            Self::EasterPrev(span) => prev_easter(index, span, date)?.ordinal().into(),
+            Self::DaysToEaster(span) => (next_easter(index, span, date)? - date).num_days(),
             Self::IsWeekday => {
                 let wd: Weekday = date.weekday().into();
                 b2i(!wd.is_weekend())
@@ -112,12 +150,14 @@ impl Var {
 pub enum Expr {
     Lit(i64),
     Var(Var),
-    Neg(Box<Expr>),
-    Add(Box<Expr>, Box<Expr>),
-    Sub(Box<Expr>, Box<Expr>),
-    Mul(Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>, Span),
+    Add(Box<Expr>, Box<Expr>, Span),
+    Sub(Box<Expr>, Box<Expr>, Span),
+    Mul(Box<Expr>, Box<Expr>, Span),
     Div(Box<Expr>, Box<Expr>, Span),
     Mod(Box<Expr>, Box<Expr>, Span),
+    Quot(Box<Expr>, Box<Expr>, Span),
+    Rem(Box<Expr>, Box<Expr>, Span),
     Eq(Box<Expr>, Box<Expr>),
     Neq(Box<Expr>, Box<Expr>),
     Lt(Box<Expr>, Box<Expr>),
@@ -130,11 +170,11 @@ pub enum Expr {
     Xor(Box<Expr>, Box<Expr>),
 }
 
-impl From<&Spanned<commands::Expr>> for Expr {
-    fn from(expr: &Spanned<commands::Expr>) -> Self {
-        fn conv(expr: &Spanned<commands::Expr>) -> Box<Expr> {
-            Box::new(expr.into())
-        }
+impl Expr {
+    /// Convert a parsed expression. Fiscal year variables are evaluated
+    /// relative to the month `fiscal_year` (1-12).
+    pub fn new(expr: &Spanned<commands::Expr>, fiscal_year: u32) -> Self {
+        let conv = |expr: &Spanned<commands::Expr>| Box::new(Self::new(expr, fiscal_year));
 
         match &expr.value {
             commands::Expr::Lit(l) => Self::Lit(*l),
@@ -164,20 +204,29 @@ impl From<&Spanned<commands::Expr>> for Expr {
                 commands::Var::IsoYear => Self::Var(Var::IsoYear),
                 commands::Var::IsoYearLength => Self::Var(Var::IsoYearLength),
                 commands::Var::IsoWeek => Self::Var(Var::IsoWeek),
+                commands::Var::IsoYearWeek => Self::Var(Var::IsoWeek),
+                commands::Var::IsoMonthWeek => Self::Var(Var::IsoMonthWeek),
+                commands::Var::FiscalYear => Self::Var(Var::FiscalYear(fiscal_year)),
+                commands::Var::FiscalYearDay => Self::Var(Var::FiscalYearDay(fiscal_year)),
                 commands::Var::Weekday => Self::Var(Var::Weekday),
                 commands::Var::Easter => Self::Var(Var::Easter(expr.span)),
+                commands::Var::EasterNext => Self::Var(Var::EasterNext(expr.span)),
+                commands::Var::EasterPrev => Self::Var(Var::EasterPrev(expr.span)),
+                commands::Var::DaysToEaster => Self::Var(Var::DaysToEaster(expr.span)),
                 commands::Var::IsWeekday => Self::Var(Var::IsWeekday),
                 commands::Var::IsWeekend => Self::Var(Var::IsWeekend),
                 commands::Var::IsLeapYear => Self::Var(Var::IsLeapYear),
                 commands::Var::IsIsoLeapYear => Self::Var(Var::IsIsoLeapYear),
             },
-            commands::Expr::Paren(i) => i.as_ref().into(),
-            commands::Expr::Neg(i) => Self::Neg(conv(i)),
-            commands::Expr::Add(a, b) => Self::Add(conv(a), conv(b)),
-            commands::Expr::Sub(a, b) => Self::Sub(conv(a), conv(b)),
-            commands::Expr::Mul(a, b) => Self::Mul(conv(a), conv(b)),
+            commands::Expr::Paren(i) => Self::new(i, fiscal_year),
+            commands::Expr::Neg(i) => Self::Neg(conv(i), expr.span),
+            commands::Expr::Add(a, b) => Self::Add(conv(a), conv(b), expr.span),
+            commands::Expr::Sub(a, b) => Self::Sub(conv(a), conv(b), expr.span),
+            commands::Expr::Mul(a, b) => Self::Mul(conv(a), conv(b), expr.span),
             commands::Expr::Div(a, b) => Self::Div(conv(a), conv(b), expr.span),
             commands::Expr::Mod(a, b) => Self::Mod(conv(a), conv(b), expr.span),
+            commands::Expr::Quot(a, b) => Self::Quot(conv(a), conv(b), expr.span),
+            commands::Expr::Rem(a, b) => Self::Rem(conv(a), conv(b), expr.span),
             commands::Expr::Eq(a, b) => Self::Eq(conv(a), conv(b)),
             commands::Expr::Neq(a, b) => Self::Neq(conv(a), conv(b)),
             commands::Expr::Lt(a, b) => Self::Lt(conv(a), conv(b)),
@@ -207,14 +256,32 @@ impl From<Weekday> for Expr {
 }
 
 impl Expr {
+    /// Whether the expression holds on `date`.
+    pub fn holds<S: Copy>(&self, index: S, date: NaiveDate) -> Result<bool, Error<S>> {
+        Ok(i2b(self.eval(index, date)?))
+    }
+
     fn eval<S: Copy>(&self, index: S, date: NaiveDate) -> Result<i64, Error<S>> {
+        let overflow = |span: Span| Error::ArithmeticOverflow { index, span, date };
         Ok(match self {
             Self::Lit(l) => *l,
             Self::Var(v) => v.eval(index, date)?,
-            Self::Neg(e) => -e.eval(index, date)?,
-            Self::Add(a, b) => a.eval(index, date)? + b.eval(index, date)?,
-            Self::Sub(a, b) => a.eval(index, date)? - b.eval(index, date)?,
-            Self::Mul(a, b) => a.eval(index, date)? * b.eval(index, date)?,
+            Self::Neg(e, span) => e
+                .eval(index, date)?
+                .checked_neg()
+                .ok_or_else(|| overflow(*span))?,
+            Self::Add(a, b, span) => a
+                .eval(index, date)?
+                .checked_add(b.eval(index, date)?)
+                .ok_or_else(|| overflow(*span))?,
+            Self::Sub(a, b, span) => a
+                .eval(index, date)?
+                .checked_sub(b.eval(index, date)?)
+                .ok_or_else(|| overflow(*span))?,
+            Self::Mul(a, b, span) => a
+                .eval(index, date)?
+                .checked_mul(b.eval(index, date)?)
+                .ok_or_else(|| overflow(*span))?,
             Self::Div(a, b, span) => {
                 let b = b.eval(index, date)?;
                 if b == 0 {
@@ -224,7 +291,9 @@ impl Expr {
                         date,
                     });
                 }
-                a.eval(index, date)?.div_euclid(b)
+                a.eval(index, date)?
+                    .checked_div_euclid(b)
+                    .ok_or_else(|| overflow(*span))?
             }
             Self::Mod(a, b, span) => {
                 let b = b.eval(index, date)?;
@@ -235,7 +304,35 @@ impl Expr {
                         date,
                     });
                 }
-                a.eval(index, date)?.rem_euclid(b)
+                a.eval(index, date)?
+                    .checked_rem_euclid(b)
+                    .ok_or_else(|| overflow(*span))?
+            }
+            Self::Quot(a, b, span) => {
+                let b = b.eval(index, date)?;
+                if b == 0 {
+                    return Err(Error::DivByZero {
+                        index,
+                        span: *span,
+                        date,
+                    });
+                }
+                a.eval(index, date)?
+                    .checked_div(b)
+                    .ok_or_else(|| overflow(*span))?
+            }
+            Self::Rem(a, b, span) => {
+                let b = b.eval(index, date)?;
+                if b == 0 {
+                    return Err(Error::ModByZero {
+                        index,
+                        span: *span,
+                        date,
+                    });
+                }
+                a.eval(index, date)?
+                    .checked_rem(b)
+                    .ok_or_else(|| overflow(*span))?
             }
             Self::Eq(a, b) => b2i(a.eval(index, date)? == b.eval(index, date)?),
             Self::Neq(a, b) => b2i(a.eval(index, date)? != b.eval(index, date)?),
@@ -258,10 +355,10 @@ pub struct FormulaSpec {
     pub end_delta: Delta,
 }
 
-impl From<&commands::FormulaSpec> for FormulaSpec {
-    fn from(spec: &commands::FormulaSpec) -> Self {
-        let start: Expr = match &spec.start {
-            Some(expr) => expr.into(),
+impl FormulaSpec {
+    pub fn new(spec: &commands::FormulaSpec, fiscal_year: u32) -> Self {
+        let start = match &spec.start {
+            Some(expr) => Expr::new(expr, fiscal_year),
             None => Expr::Lit(1), // Always true
         };
 
@@ -291,12 +388,31 @@ impl From<&commands::FormulaSpec> for FormulaSpec {
     }
 }
 
-impl From<&commands::WeekdaySpec> for FormulaSpec {
-    fn from(spec: &commands::WeekdaySpec) -> Self {
-        let start = Expr::Eq(
-            Box::new(Expr::Var(Var::Weekday)),
-            Box::new(spec.start.into()),
-        );
+impl FormulaSpec {
+    /// Convert a weekday spec. Fiscal year variables in its filter are
+    /// evaluated relative to the month `fiscal_year` (1-12).
+    pub fn from_weekday(spec: &commands::WeekdaySpec, fiscal_year: u32) -> Self {
+        let is_weekday =
+            |wd: Weekday| Expr::Eq(Box::new(Expr::Var(Var::Weekday)), Box::new(wd.into()));
+        let mut start = is_weekday(spec.start);
+        for &wd in &spec.others {
+            start = Expr::Or(Box::new(start), Box::new(is_weekday(wd)));
+        }
+        if let Some(nth) = spec.nth {
+            // The nth weekday of a month always lies in the nth week of the
+            // month as counted by `mw` (or `mW` from the end).
+            let (var, week) = if nth > 0 {
+                (Var::MonthWeek, nth)
+            } else {
+                (Var::MonthWeekReverse, -nth)
+            };
+            let week = Expr::Eq(Box::new(Expr::Var(var)), Box::new(Expr::Lit(week.into())));
+            start = Expr::And(Box::new(start), Box::new(week));
+        }
+        if let Some(filter) = &spec.filter {
+            let filter = Expr::new(filter, fiscal_year);
+            start = Expr::And(Box::new(start), Box::new(filter));
+        }
 
         let mut end_delta = Delta::default();
         if let Some(wd) = spec.end {
@@ -327,11 +443,8 @@ impl From<&commands::WeekdaySpec> for FormulaSpec {
 }
 
 impl FormulaSpec {
-    fn range(&self, s: &CommandState<'_>) -> Option<DateRange> {
-        let mut range = s
-            .range_with_remind()
-            .expand_by(&self.end_delta)
-            .move_by(&self.start_delta);
+    fn range(&self, s: &CommandState<'_>, range: DateRange) -> Option<DateRange> {
+        let mut range = range.expand_by(&self.end_delta).move_by(&self.start_delta);
 
         if let EvalCommand::Task(_) = s.command {
             if let Some(last_done_root) = s.command.last_done_root() {
@@ -363,31 +476,107 @@ impl FormulaSpec {
     fn eval(&self, index: FileSource, date: NaiveDate) -> Result<bool, Error<FileSource>> {
         Ok(i2b(self.start.eval(index, date)?))
     }
+
+    /// Whether the spec occurs on any day after `after` whose occurrence would
+    /// still start before or on `until`.
+    ///
+    /// At most `max_days` days are checked. If none of them match, the spec is
+    /// assumed to occur again.
+    fn occurs_until(
+        &self,
+        index: FileSource,
+        after: NaiveDate,
+        until: NaiveDate,
+        max_days: usize,
+    ) -> Result<bool, Error<FileSource>> {
+        let mut day = after;
+        for _ in 0..max_days {
+            day = day.succ_opt().unwrap();
+            if self.start_delta.apply_date(index, day)? > until {
+                return Ok(false);
+            }
+            if self.eval(index, day)? {
+                return Ok(true);
+            }
+        }
+        Ok(true)
+    }
 }
 
 impl CommandState<'_> {
-    pub fn eval_formula_spec(&mut self, spec: FormulaSpec) -> Result<(), Error<FileSource>> {
-        if let Some(range) = spec.range(self) {
+    pub fn eval_formula_spec(
+        &mut self,
+        spec: FormulaSpec,
+        span: Span,
+    ) -> Result<(), Error<FileSource>> {
+        let range = self.range_with_remind()?;
+        if let Some(range) = spec.range(self, range) {
+            self.trace_step(|| format!("Range {} -- {}", range.from(), range.until()));
             let index = self.source.file();
+            let mut roots = vec![];
             for day in range.days() {
                 if spec.eval(index, day)? {
+                    self.count_occurrence(span)?;
                     let dates = spec.dates(index, day)?;
-                    self.add(self.entry_with_remind(self.command.kind(), Some(dates))?);
+                    if self.is_past_occurrence(dates) {
+                        self.trace_step(|| format!("Occurrence {dates} is in the past"));
+                    } else {
+                        roots.push(dates.root());
+                        self.add(self.entry_with_remind(self.command.kind(), Some(dates))?);
+                    }
+                }
+            }
+            let ends = match self.until {
+                Some(until) => {
+                    !spec.occurs_until(index, range.until(), until, self.max_occurrences)?
                 }
+                None => false,
+            };
+            if ends {
+                self.series_ends.push(roots);
+            }
+        } else {
+            self.trace_step(|| "Range doesn't contain any occurrences".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn eval_except_expr(
+        &mut self,
+        expr: &Spanned<commands::Expr>,
+    ) -> Result<(), Error<FileSource>> {
+        let index = self.source.file();
+        let expr = Expr::new(expr, self.fiscal_year);
+
+        let mut excepted = vec![];
+        for date in self.dated.keys() {
+            if i2b(expr.eval(index, *date)?) {
+                excepted.push(*date);
             }
         }
+
+        for date in excepted {
+            self.dated.remove(&date);
+            self.trace_step(|| format!("Removed occurrence on {date}"));
+        }
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::panic;
+    use std::path::Path;
 
     use chrono::{Datelike, Duration, NaiveDate};
 
-    use crate::files::primitives::Span;
+    use crate::files::commands::{self, Command};
+    use crate::files::primitives::{Span, Spanned};
+    use crate::files::{self, Source};
 
+    use super::super::super::{DateRange, Error};
+    use super::super::{CommandState, EvalCommand};
     use super::{Expr, Var};
 
     fn expr(expr: &Expr, date: NaiveDate, target: i64) {
@@ -825,6 +1014,58 @@ mod tests {
         expr_ymd(&e, (1982, 1, 4), 1);
     }
 
+    #[test]
+    fn iso_year_week() {
+        let iyw = Expr::new(
+            &Spanned::dummy(commands::Expr::Var(commands::Var::IsoYearWeek)),
+            1,
+        );
+        let yw = Expr::Var(Var::YearWeek);
+
+        // 2021-01-01 is a friday, so the ISO and 7-day weeks disagree
+        expr_ymd(&iyw, (2021, 1, 1), 53);
+        expr_ymd(&yw, (2021, 1, 1), 1);
+        expr_ymd(&iyw, (2021, 1, 3), 53);
+        expr_ymd(&yw, (2021, 1, 3), 1);
+        expr_ymd(&iyw, (2021, 1, 4), 1);
+        expr_ymd(&yw, (2021, 1, 4), 1);
+        expr_ymd(&iyw, (2021, 1, 8), 1);
+        expr_ymd(&yw, (2021, 1, 8), 2);
+        expr_ymd(&iyw, (2021, 1, 11), 2);
+        expr_ymd(&yw, (2021, 1, 11), 2);
+
+        // 2024-01-01 is a monday, so they agree
+        for d in 1..=31 {
+            let iso = NaiveDate::from_ymd_opt(2024, 1, d)
+                .unwrap()
+                .iso_week()
+                .week();
+            expr_ymd(&iyw, (2024, 1, d), iso.into());
+            expr_ymd(&yw, (2024, 1, d), iso.into());
+        }
+    }
+
+    #[test]
+    fn iso_month_week() {
+        let e = Expr::Var(Var::IsoMonthWeek);
+
+        // 2021-01-01 is a friday
+        expr_ymd(&e, (2021, 1, 1), 1);
+        expr_ymd(&e, (2021, 1, 3), 1);
+        expr_ymd(&e, (2021, 1, 4), 2);
+        expr_ymd(&e, (2021, 1, 10), 2);
+        expr_ymd(&e, (2021, 1, 11), 3);
+        expr_ymd(&e, (2021, 1, 31), 5);
+
+        // 2024-01-01 is a monday, so it agrees with mw
+        let mw = Expr::Var(Var::MonthWeek);
+        for d in 1..=31 {
+            let week = (d - 1) / 7 + 1;
+            expr_ymd(&e, (2024, 1, d), week.into());
+            expr_ymd(&mw, (2024, 1, d), week.into());
+        }
+    }
+
     #[test]
     fn weekday() {
         let e = Expr::Var(Var::Weekday);
@@ -1024,4 +1265,305 @@ mod tests {
         expr_ymd(&e, (2029, 8, 1), 0);
         expr_ymd(&e, (2030, 8, 1), 0);
     }
+
+    fn overflows(e: &Expr) {
+        let date = NaiveDate::from_ymd_opt(2022, 1, 1).unwrap();
+        assert!(matches!(
+            e.eval((), date),
+            Err(Error::ArithmeticOverflow { .. })
+        ));
+    }
+
+    #[test]
+    fn arithmetic_overflow() {
+        let span = Span { start: 0, end: 0 };
+        let max = || Box::new(Expr::Lit(i64::MAX));
+        let min = || Box::new(Expr::Lit(i64::MIN));
+        let lit = |l| Box::new(Expr::Lit(l));
+
+        overflows(&Expr::Neg(min(), span));
+        overflows(&Expr::Add(max(), lit(1), span));
+        overflows(&Expr::Sub(min(), lit(1), span));
+        overflows(&Expr::Mul(max(), lit(2), span));
+        overflows(&Expr::Mul(
+            Box::new(Expr::Var(Var::JulianDay)),
+            lit(i64::MAX / 1000),
+            span,
+        ));
+        overflows(&Expr::Div(min(), lit(-1), span));
+        overflows(&Expr::Mod(min(), lit(-1), span));
+        overflows(&Expr::Quot(min(), lit(-1), span));
+        overflows(&Expr::Rem(min(), lit(-1), span));
+
+        expr_ymd(&Expr::Add(max(), lit(-1), span), (2022, 1, 1), i64::MAX - 1);
+    }
+
+    #[test]
+    fn negative_operands() {
+        let span = Span { start: 0, end: 0 };
+        let lit = |l| Box::new(Expr::Lit(l));
+        let check = |op: fn(Box<Expr>, Box<Expr>, Span) -> Expr, a, b, target| {
+            expr_ymd(&op(lit(a), lit(b), span), (2022, 1, 1), target);
+        };
+
+        // Euclidean, the default
+        check(Expr::Div, 7, 2, 3);
+        check(Expr::Div, -7, 2, -4);
+        check(Expr::Div, 7, -2, -3);
+        check(Expr::Div, -7, -2, 4);
+        check(Expr::Mod, 7, 2, 1);
+        check(Expr::Mod, -7, 2, 1);
+        check(Expr::Mod, 7, -2, 1);
+        check(Expr::Mod, -7, -2, 1);
+
+        // Truncating
+        check(Expr::Quot, 7, 2, 3);
+        check(Expr::Quot, -7, 2, -3);
+        check(Expr::Quot, 7, -2, -3);
+        check(Expr::Quot, -7, -2, 3);
+        check(Expr::Rem, 7, 2, 1);
+        check(Expr::Rem, -7, 2, -1);
+        check(Expr::Rem, 7, -2, 1);
+        check(Expr::Rem, -7, -2, -1);
+    }
+
+    #[test]
+    fn fiscal_year() {
+        let e = Expr::Var(Var::FiscalYear(4));
+
+        expr_ymd(&e, (2023, 1, 1), 2022);
+        expr_ymd(&e, (2023, 3, 31), 2022);
+        expr_ymd(&e, (2023, 4, 1), 2023);
+        expr_ymd(&e, (2023, 12, 31), 2023);
+        expr_ymd(&e, (2024, 3, 31), 2023);
+        expr_ymd(&e, (2024, 4, 1), 2024);
+
+        let e = Expr::Var(Var::FiscalYear(1));
+        for y in 2000..=2030 {
+            expr_ymd(&e, (y, 1, 1), y.into());
+            expr_ymd(&e, (y, 12, 31), y.into());
+        }
+    }
+
+    #[test]
+    fn fiscal_year_day() {
+        let e = Expr::Var(Var::FiscalYearDay(4));
+
+        expr_ymd(&e, (2023, 3, 31), 365);
+        expr_ymd(&e, (2023, 4, 1), 1);
+        expr_ymd(&e, (2023, 4, 2), 2);
+        expr_ymd(&e, (2023, 5, 1), 31);
+        expr_ymd(&e, (2024, 1, 1), 276);
+        expr_ymd(&e, (2024, 3, 31), 366);
+        expr_ymd(&e, (2024, 4, 1), 1);
+
+        let e = Expr::Var(Var::FiscalYearDay(1));
+        for i in 1..=365 {
+            expr_yo(&e, (2020, i), i.into());
+            expr_yo(&e, (2021, i), i.into());
+        }
+    }
+
+    #[test]
+    fn except_expr() {
+        let file = files::parse_file(
+            Path::new("test.today"),
+            "NOTE Daily\nDATE *\nEXCEPT (d % 2 = 0)\n",
+        )
+        .unwrap();
+        let note = match &file.commands[0].value {
+            Command::Note(note) => note,
+            _ => panic!("expected note"),
+        };
+
+        let from = NaiveDate::from_ymd_opt(2022, 1, 1).unwrap();
+        let until = NaiveDate::from_ymd_opt(2022, 2, 28).unwrap();
+        let range = DateRange::new(from, until);
+        let entries = CommandState::new(
+            EvalCommand::Note(note),
+            Source::new(0, 0),
+            range,
+            1,
+            &HashMap::new(),
+        )
+        .eval()
+        .unwrap()
+        .entries();
+
+        assert_eq!(entries.len(), 31 / 2 + 1 + 28 / 2);
+        for entry in entries {
+            assert!(entry.dates.unwrap().root().day() % 2 == 1);
+        }
+    }
+
+    #[test]
+    fn except_first_weekday_of_month() {
+        let ymd = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        let dates = note_dates(
+            "NOTE Standup\nDATE (isWeekday)\nEXCEPT (d = 1 | (wd = mon & d < 4))\n",
+            ymd(1, 1),
+            ymd(6, 30),
+        );
+
+        // 2024-01-01, 2024-02-01, 2024-03-01, 2024-04-01, 2024-05-01 and
+        // 2024-06-03 are skipped
+        assert_eq!(dates.len(), 130 - 6);
+        for skipped in [
+            ymd(1, 1),
+            ymd(2, 1),
+            ymd(3, 1),
+            ymd(4, 1),
+            ymd(5, 1),
+            ymd(6, 3),
+        ] {
+            assert!(!dates.contains(&skipped));
+        }
+        for kept in [ymd(1, 2), ymd(6, 4)] {
+            assert!(dates.contains(&kept));
+        }
+    }
+
+    #[test]
+    fn easter_relative() {
+        let span = Span { start: 0, end: 0 };
+        let next = Expr::Var(Var::EasterNext(span));
+        let prev = Expr::Var(Var::EasterPrev(span));
+        let days = Expr::Var(Var::DaysToEaster(span));
+
+        // Easter is on 2024-03-31 (day 91) and 2025-04-20 (day 110), and it
+        // was on 2023-04-09 (day 99)
+        expr_ymd(&next, (2024, 3, 30), 91);
+        expr_ymd(&next, (2024, 3, 31), 91);
+        expr_ymd(&next, (2024, 4, 1), 110);
+        expr_ymd(&prev, (2024, 3, 30), 99);
+        expr_ymd(&prev, (2024, 3, 31), 91);
+        expr_ymd(&prev, (2024, 4, 1), 91);
+        expr_ymd(&days, (2024, 3, 1), 30);
+        expr_ymd(&days, (2024, 3, 30), 1);
+        expr_ymd(&days, (2024, 3, 31), 0);
+        expr_ymd(&days, (2024, 4, 1), 384);
+        expr_ymd(&days, (2024, 12, 31), 110);
+    }
+
+    #[test]
+    fn last_weekday_occurrence_ends() {
+        let file = files::parse_file(
+            Path::new("test.today"),
+            "NOTE Standup\nUNTIL 2024-03-16\nDATE mon\n",
+        )
+        .unwrap();
+        let command = EvalCommand::new(&file.commands[0].value).unwrap();
+        let ymd = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+
+        // The last Monday before UNTIL is found even if the range ends earlier
+        for until in [ymd(12), ymd(31)] {
+            let mut ends = CommandState::new(
+                command,
+                Source::new(0, 0),
+                DateRange::new(ymd(1), until),
+                1,
+                &HashMap::new(),
+            )
+            .eval()
+            .unwrap()
+            .entries()
+            .into_iter()
+            .map(|entry| (entry.root().unwrap(), entry.ends))
+            .collect::<Vec<_>>();
+            ends.sort();
+            assert_eq!(ends, vec![(ymd(4), false), (ymd(11), true)]);
+        }
+    }
+
+    #[test]
+    fn end_search_is_bounded() {
+        let file = files::parse_file(
+            Path::new("test.today"),
+            "NOTE Anniversary\nUNTIL 2024-12-31\nDATE (m = 3 & d = 4)\n",
+        )
+        .unwrap();
+        let command = EvalCommand::new(&file.commands[0].value).unwrap();
+        let ymd = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        let ends = |max| {
+            CommandState::new(
+                command,
+                Source::new(0, 0),
+                DateRange::new(ymd(1), ymd(31)),
+                1,
+                &HashMap::new(),
+            )
+            .max_occurrences(Some(max))
+            .eval()
+            .unwrap()
+            .entries()
+            .into_iter()
+            .map(|entry| (entry.root().unwrap(), entry.ends))
+            .collect::<Vec<_>>()
+        };
+
+        // The remaining 275 days until UNTIL are only searched if allowed
+        assert_eq!(ends(1000), vec![(ymd(4), true)]);
+        assert_eq!(ends(10), vec![(ymd(4), false)]);
+    }
+
+    fn note_dates(input: &str, from: NaiveDate, until: NaiveDate) -> Vec<NaiveDate> {
+        let file = files::parse_file(Path::new("test.today"), input).unwrap();
+        let note = match &file.commands[0].value {
+            Command::Note(note) => note,
+            _ => panic!("expected note"),
+        };
+
+        let mut dates = CommandState::new(
+            EvalCommand::Note(note),
+            Source::new(0, 0),
+            DateRange::new(from, until),
+            1,
+            &HashMap::new(),
+        )
+        .eval()
+        .unwrap()
+        .entries()
+        .into_iter()
+        .map(|entry| entry.dates.unwrap().root())
+        .collect::<Vec<_>>();
+        dates.sort();
+        dates
+    }
+
+    #[test]
+    fn nth_weekday() {
+        let ymd = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        let (from, until) = (ymd(1, 1), ymd(4, 30));
+
+        assert_eq!(
+            note_dates("NOTE Meetup\nDATE mon #2\n", from, until),
+            [ymd(1, 8), ymd(2, 12), ymd(3, 11), ymd(4, 8)]
+        );
+        assert_eq!(
+            note_dates("NOTE Review\nDATE mon #-1\n", from, until),
+            [ymd(1, 29), ymd(2, 26), ymd(3, 25), ymd(4, 29)]
+        );
+        // Not every month has a fifth Friday
+        assert_eq!(
+            note_dates("NOTE Party\nDATE fri #5\n", from, until),
+            [ymd(3, 29)]
+        );
+    }
+
+    #[test]
+    fn weekday_set_with_filter() {
+        let ymd = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let input = "NOTE Gym\nDATE mon,thu (m < 4)\n";
+
+        let dates = note_dates(input, ymd(2023, 12, 25), ymd(2024, 1, 7));
+        assert_eq!(dates, [ymd(2024, 1, 1), ymd(2024, 1, 4)]);
+
+        let dates = note_dates(input, ymd(2024, 3, 25), ymd(2024, 4, 7));
+        assert_eq!(dates, [ymd(2024, 3, 25), ymd(2024, 3, 28)]);
+
+        // 13 mondays and 13 thursdays in the first quarter of 2024
+        let dates = note_dates(input, ymd(2024, 1, 1), ymd(2024, 12, 31));
+        assert_eq!(dates.len(), 26);
+        assert!(dates.iter().all(|date| date.month() <= 3));
+    }
 }
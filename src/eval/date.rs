@@ -1,6 +1,6 @@
 use std::fmt;
 
-use chrono::{Duration, NaiveDate};
+use chrono::{Duration, NaiveDate, TimeZone};
 
 use crate::files::commands::DoneDate;
 use crate::files::primitives::Time;
@@ -18,6 +18,31 @@ pub struct Dates {
     times: Option<Times>,
 }
 
+/// Convert a local date and time from one time zone to another.
+///
+/// Returns `None` if the local time doesn't exist in `from`, even after moving
+/// it forwards by an hour.
+fn convert_date_time<A: TimeZone, B: TimeZone>(
+    date: NaiveDate,
+    time: Time,
+    from: &A,
+    to: &B,
+) -> Option<(NaiveDate, Time)> {
+    let local = date.and_hms_opt(0, 0, 0).unwrap() + Duration::minutes(time.minutes());
+    // Times skipped by daylight savings time are moved forwards by an hour.
+    // Some time zones skipped entire days though, so this may still fail.
+    let converted = from
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            from.from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })?
+        .with_timezone(to)
+        .naive_local();
+    Some((converted.date(), converted.time().into()))
+}
+
 impl fmt::Display for Dates {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let done_date: DoneDate = (*self).into();
@@ -70,6 +95,29 @@ impl Dates {
         self.times.map(|times| (times.root, times.other))
     }
 
+    /// Interpret the dates' times as local times in the time zone `from` and
+    /// convert them to local times in the time zone `to`. Dates without times
+    /// are left unchanged.
+    ///
+    /// Fails with the offending date and time if one of the times doesn't exist
+    /// in `from`.
+    pub fn convert_timezone<A: TimeZone, B: TimeZone>(
+        self,
+        from: &A,
+        to: &B,
+    ) -> Result<Self, (NaiveDate, Time)> {
+        match self.times {
+            None => Ok(self),
+            Some(times) => {
+                let (root, root_time) = convert_date_time(self.root, times.root, from, to)
+                    .ok_or((self.root, times.root))?;
+                let (other, other_time) = convert_date_time(self.other, times.other, from, to)
+                    .ok_or((self.other, times.other))?;
+                Ok(Self::new_with_time(root, root_time, other, other_time))
+            }
+        }
+    }
+
     /// Flip `root` and `other`.
     fn flip(self) -> Self {
         Self {
@@ -162,3 +210,84 @@ impl From<Dates> for DoneDate {
         .simplified()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use tzfile::Tz;
+
+    use crate::files::primitives::Time;
+
+    use super::Dates;
+
+    fn convert(date: (i32, u32, u32), time: (u32, u32)) -> (NaiveDate, Time) {
+        let utc = Tz::named("UTC").unwrap();
+        let new_york = Tz::named("America/New_York").unwrap();
+        let date = NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap();
+        let time = Time::new(time.0, time.1);
+        let dates = Dates::new_with_time(date, time, date, time);
+        let (date, time) = dates
+            .convert_timezone(&&utc, &&new_york)
+            .unwrap()
+            .root_with_time();
+        (date, time.unwrap())
+    }
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn convert_timezone() {
+        // Standard time, UTC-5
+        assert_eq!(
+            convert((2024, 1, 15), (12, 0)),
+            (ymd(2024, 1, 15), Time::new(7, 0))
+        );
+        assert_eq!(
+            convert((2024, 1, 15), (3, 30)),
+            (ymd(2024, 1, 14), Time::new(22, 30))
+        );
+
+        // DST starts on 2024-03-10 at 02:00 local time
+        assert_eq!(
+            convert((2024, 3, 10), (6, 0)),
+            (ymd(2024, 3, 10), Time::new(1, 0))
+        );
+        assert_eq!(
+            convert((2024, 3, 10), (8, 0)),
+            (ymd(2024, 3, 10), Time::new(4, 0))
+        );
+
+        // Daylight savings time, UTC-4
+        assert_eq!(
+            convert((2024, 7, 1), (12, 0)),
+            (ymd(2024, 7, 1), Time::new(8, 0))
+        );
+    }
+
+    #[test]
+    fn convert_untimed() {
+        let utc = Tz::named("UTC").unwrap();
+        let new_york = Tz::named("America/New_York").unwrap();
+        let date = ymd(2024, 1, 15);
+        let dates = Dates::new(date, date)
+            .convert_timezone(&&utc, &&new_york)
+            .unwrap();
+        assert_eq!(dates.root_with_time(), (date, None));
+    }
+
+    #[test]
+    fn convert_skipped_day() {
+        // Samoa skipped 2011-12-30 entirely when moving across the date line
+        let apia = Tz::named("Pacific/Apia").unwrap();
+        let utc = Tz::named("UTC").unwrap();
+        let date = ymd(2011, 12, 30);
+        let time = Time::new(10, 0);
+        let dates = Dates::new_with_time(date, time, date, time);
+        assert_eq!(
+            dates.convert_timezone(&&apia, &&utc).unwrap_err(),
+            (date, time)
+        );
+    }
+}
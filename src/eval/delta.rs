@@ -16,6 +16,7 @@ pub enum DeltaStep {
     MonthReverse(i32),
     Day(i32),
     Week(i32),
+    BusinessDay(i32),
     Hour(i32),
     Minute(i32),
     Weekday(i32, Weekday),
@@ -32,6 +33,7 @@ impl From<commands::DeltaStep> for DeltaStep {
             commands::DeltaStep::MonthReverse(n) => Self::MonthReverse(n),
             commands::DeltaStep::Day(n) => Self::Day(n),
             commands::DeltaStep::Week(n) => Self::Week(n),
+            commands::DeltaStep::BusinessDay(n) => Self::BusinessDay(n),
             commands::DeltaStep::Hour(n) => Self::Hour(n),
             commands::DeltaStep::Minute(n) => Self::Minute(n),
             commands::DeltaStep::Weekday(n, wd) => Self::Weekday(n, wd),
@@ -59,6 +61,14 @@ impl DeltaStep {
             }
             Self::Day(n) => *n,
             Self::Week(n) => *n * 7,
+            // Every started group of 5 business days may cross a weekend
+            Self::BusinessDay(n) => {
+                if *n < 0 {
+                    *n + (*n / 5 - 1) * 2
+                } else {
+                    *n
+                }
+            }
             Self::Hour(n) => {
                 if *n < 0 {
                     *n / 24 + (*n % 24).signum()
@@ -101,6 +111,13 @@ impl DeltaStep {
             }
             Self::Day(n) => *n,
             Self::Week(n) => *n * 7,
+            Self::BusinessDay(n) => {
+                if *n > 0 {
+                    *n + (*n / 5 + 1) * 2
+                } else {
+                    *n
+                }
+            }
             Self::Hour(n) => {
                 if *n > 0 {
                     *n / 24 + (*n % 24).signum()
@@ -188,6 +205,7 @@ impl<S: Copy> DeltaEval<S> {
             DeltaStep::MonthReverse(n) => self.step_month_reverse(step.span, n)?,
             DeltaStep::Day(n) => self.step_day(n),
             DeltaStep::Week(n) => self.step_week(n),
+            DeltaStep::BusinessDay(n) => self.step_business_day(n),
             DeltaStep::Hour(n) => self.step_hour(step.span, n)?,
             DeltaStep::Minute(n) => self.step_minute(step.span, n)?,
             DeltaStep::Weekday(n, wd) => self.step_weekday(n, wd),
@@ -252,6 +270,19 @@ impl<S: Copy> DeltaEval<S> {
         self.curr += delta;
     }
 
+    fn step_business_day(&mut self, amount: i32) {
+        let step = Duration::days(amount.signum().into());
+        for _ in 0..amount.abs() {
+            self.curr += step;
+            while matches!(
+                self.curr.weekday(),
+                chrono::Weekday::Sat | chrono::Weekday::Sun
+            ) {
+                self.curr += step;
+            }
+        }
+    }
+
     fn step_hour(&mut self, span: Span, amount: i32) -> Result<(), Error<S>> {
         let time = match self.curr_time {
             Some(time) => time,
@@ -511,6 +542,30 @@ mod tests {
         test_dt(Step::Week(1), (2021, 7, 3, 12, 34), (2021, 7, 10, 12, 34));
     }
 
+    #[test]
+    fn delta_business_day() {
+        // 2022-03-17 is a thursday
+        test_d(Step::BusinessDay(-5), (2022, 3, 17), (2022, 3, 10));
+        test_d(Step::BusinessDay(-4), (2022, 3, 17), (2022, 3, 11));
+        test_d(Step::BusinessDay(-1), (2022, 3, 17), (2022, 3, 16));
+        test_d(Step::BusinessDay(0), (2022, 3, 17), (2022, 3, 17));
+        test_d(Step::BusinessDay(1), (2022, 3, 17), (2022, 3, 18));
+        test_d(Step::BusinessDay(2), (2022, 3, 17), (2022, 3, 21));
+        test_d(Step::BusinessDay(6), (2022, 3, 17), (2022, 3, 25));
+
+        // From and across weekends
+        test_d(Step::BusinessDay(-1), (2022, 3, 21), (2022, 3, 18));
+        test_d(Step::BusinessDay(-1), (2022, 3, 20), (2022, 3, 18));
+        test_d(Step::BusinessDay(1), (2022, 3, 19), (2022, 3, 21));
+
+        // Doesn't touch time
+        test_dt(
+            Step::BusinessDay(1),
+            (2022, 3, 18, 12, 34),
+            (2022, 3, 21, 12, 34),
+        );
+    }
+
     #[test]
     fn delta_hour() {
         test_dt(Step::Hour(-24), (2021, 7, 3, 12, 34), (2021, 7, 2, 12, 34));
@@ -8,9 +8,13 @@ use super::range::DateRange;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EntryKind {
     Task,
+    /// A task that is waiting on someone or something.
+    TaskWaiting,
     TaskDone(NaiveDate),
     TaskCanceled(NaiveDate),
     Note,
+    /// A note that is only displayed while it is active.
+    ActiveNote,
     Birthday(Option<i32>),
 }
 
@@ -25,6 +29,16 @@ pub struct Entry {
     /// Remind the user of an entry before it occurs. This date should always be
     /// before the entry's start date, or `None` if there is no start date.
     pub remind: Option<NaiveDate>,
+    /// When the entry's command was created, as set using `CREATED`.
+    pub created: Option<NaiveDate>,
+    /// How much of the entry is done in percent, as set using `PROGRESS`.
+    pub progress: Option<u8>,
+    /// Whether this is the last occurrence of a repeating command before its
+    /// `UNTIL` that remains after all `EXCEPT`s and `MOVE`s.
+    pub ends: bool,
+    /// For tasks repeating relative to their last completion, the number of
+    /// occurrences between that completion and this occurrence.
+    pub missed: usize,
 }
 
 impl Entry {
@@ -51,6 +65,10 @@ impl Entry {
             has_description,
             dates,
             remind,
+            created: None,
+            progress: None,
+            ends: false,
+            missed: 0,
         }
     }
 
@@ -135,7 +153,7 @@ impl Entries {
         }
 
         // Unfinished tasks before or inside the range
-        if let EntryKind::Task = entry.kind {
+        if let EntryKind::Task | EntryKind::TaskWaiting = entry.kind {
             if let Some(dates) = entry.dates {
                 let (start, _) = dates.sorted().dates();
                 if start <= self.range.until() {
@@ -46,6 +46,27 @@ pub enum Error<S> {
         from: NaiveDate,
         to: NaiveDate,
     },
+    /// A `REMIND on` date was not before the entry's start date.
+    #[error("remind date not before start")]
+    RemindNotBeforeStart {
+        index: S,
+        span: Span,
+        remind: NaiveDate,
+        start: NaiveDate,
+    },
+    /// A `REMIND on` statement was used with a repeating spec. Since the date
+    /// is fixed, it can't be before the start of every occurrence.
+    #[error("remind date used with repeating spec")]
+    RemindDateRepeats { index: S, span: Span },
+    /// A time could not be converted between time zones because it doesn't
+    /// exist in the time zone it was specified in.
+    #[error("time does not exist in time zone")]
+    NonexistentTime {
+        index: S,
+        span: Span,
+        date: NaiveDate,
+        time: Time,
+    },
     /// A `MOVE a TO b` statement was executed, but there was no entry at the
     /// date `a`.
     #[error("tried to move nonexisting entry")]
@@ -68,6 +89,51 @@ pub enum Error<S> {
         span: Span,
         date: NaiveDate,
     },
+    /// An arithmetic operation has overflowed.
+    #[error("arithmetic operation overflowed")]
+    ArithmeticOverflow {
+        index: S,
+        span: Span,
+        date: NaiveDate,
+    },
+    /// A command generated more occurrences than allowed.
+    #[error("command generated too many occurrences")]
+    TooManyOccurrences { index: S, span: Span, max: usize },
+    /// A `DATE after` referred to an ID that no anchor entry has.
+    #[error("unknown anchor")]
+    UnknownAnchor { index: S, span: Span, id: String },
+    /// Two different entries have the same ID, so `DATE after` can't tell
+    /// which one is meant.
+    #[error("duplicate ID")]
+    DuplicateId {
+        index1: S,
+        span1: Span,
+        index2: S,
+        span2: Span,
+        id: String,
+    },
+    /// A repeating `DATE`'s occurrences last longer than the time between
+    /// them, so consecutive occurrences overlap. This is only a warning.
+    #[error("repeating occurrences overlap")]
+    OverlappingOccurrences {
+        index: S,
+        span: Span,
+        /// Minimum length of an occurrence in days.
+        length: i32,
+        /// Minimum number of days between the starts of two occurrences.
+        period: i32,
+    },
+    /// Two different entries share the same title on the same day. This is
+    /// only a warning.
+    #[error("duplicate title")]
+    DuplicateTitle {
+        index1: S,
+        span1: Span,
+        index2: S,
+        span2: Span,
+        date: NaiveDate,
+        title: String,
+    },
     /// Easter calculation failed.
     #[error("easter calculation failed")]
     Easter {
@@ -87,10 +153,9 @@ impl<S> Error<S> {
     }
 }
 
-impl<'a, F: Files<'a>> Eprint<'a, F> for Error<F::FileId> {
-    #[allow(single_use_lifetimes)]
-    fn eprint<'f: 'a>(&self, files: &'f F, config: &Config) {
-        let diagnostic = match self {
+impl<S: Copy> Error<S> {
+    fn diagnostic(&self) -> Diagnostic<S> {
+        match self {
             Error::DeltaInvalidStep {
                 index,
                 span,
@@ -139,6 +204,32 @@ impl<'a, F: Files<'a>> Eprint<'a, F> for Error<F::FileId> {
                 .with_message("Remind delta did not move backwards")
                 .with_labels(vec![Label::primary(*index, span)])
                 .with_notes(vec![format!("Moved from {from} to {to}")]),
+            Error::RemindNotBeforeStart {
+                index,
+                span,
+                remind,
+                start,
+            } => Diagnostic::error()
+                .with_message("Remind date is not before start")
+                .with_labels(vec![Label::primary(*index, span)])
+                .with_notes(vec![format!("Reminding at {remind}, starting at {start}")]),
+            Error::RemindDateRepeats { index, span } => Diagnostic::error()
+                .with_message("Remind date used with repeating spec")
+                .with_labels(vec![Label::primary(*index, span)])
+                .with_notes(vec![
+                    "Use a remind delta like `REMIND -1w` instead".to_string()
+                ]),
+            Error::NonexistentTime {
+                index,
+                span,
+                date,
+                time,
+            } => Diagnostic::error()
+                .with_message("Time does not exist in time zone")
+                .with_labels(vec![Label::primary(*index, span)])
+                .with_notes(vec![format!(
+                    "{date} {time} was skipped by a time zone transition"
+                )]),
             Error::MoveWithoutSource { index, span } => Diagnostic::error()
                 .with_message("Tried to move nonexistent entry")
                 .with_labels(vec![Label::primary(*index, span)]),
@@ -153,6 +244,61 @@ impl<'a, F: Files<'a>> Eprint<'a, F> for Error<F::FileId> {
                 .with_message("Tried to modulo by zero")
                 .with_labels(vec![Label::primary(*index, span)])
                 .with_notes(vec![format!("At date: {date}")]),
+            Error::ArithmeticOverflow { index, span, date } => Diagnostic::error()
+                .with_message("Arithmetic operation overflowed")
+                .with_labels(vec![Label::primary(*index, span)])
+                .with_notes(vec![format!("At date: {date}")]),
+            Error::TooManyOccurrences { index, span, max } => Diagnostic::error()
+                .with_message("Command generated too many occurrences")
+                .with_labels(vec![Label::primary(*index, span)])
+                .with_notes(vec![
+                    format!("A command may generate at most {max} occurrences"),
+                    "Consider restricting it using FROM and UNTIL".to_string(),
+                    "The limit can be changed using --max-occurrences".to_string(),
+                ]),
+            Error::UnknownAnchor { index, span, id } => Diagnostic::error()
+                .with_message(format!("Unknown anchor {id}"))
+                .with_labels(vec![Label::primary(*index, span)])
+                .with_notes(vec![
+                    "Anchors must have an ID and a non-repeating DATE".to_string()
+                ]),
+            Error::DuplicateId {
+                index1,
+                span1,
+                index2,
+                span2,
+                id,
+            } => Diagnostic::error()
+                .with_message(format!("Duplicate ID {id}"))
+                .with_labels(vec![
+                    Label::primary(*index1, span1),
+                    Label::primary(*index2, span2),
+                ])
+                .with_notes(vec!["Each ID must only be used by one entry".to_string()]),
+            Error::OverlappingOccurrences {
+                index,
+                span,
+                length,
+                period,
+            } => Diagnostic::warning()
+                .with_message("Repeating occurrences overlap")
+                .with_labels(vec![Label::primary(*index, span)])
+                .with_notes(vec![format!(
+                    "Occurrences last at least {length} days but may repeat every {period} days"
+                )]),
+            Error::DuplicateTitle {
+                index1,
+                span1,
+                index2,
+                span2,
+                date,
+                title,
+            } => Diagnostic::warning()
+                .with_message(format!("Duplicate title {title} on {date}"))
+                .with_labels(vec![
+                    Label::primary(*index1, span1),
+                    Label::primary(*index2, span2),
+                ]),
             Error::Easter {
                 index,
                 span,
@@ -162,7 +308,18 @@ impl<'a, F: Files<'a>> Eprint<'a, F> for Error<F::FileId> {
                 .with_message("Failed to calculate easter")
                 .with_labels(vec![Label::primary(*index, span)])
                 .with_notes(vec![format!("At date: {date}"), format!("Reason: {msg}")]),
-        };
-        Self::eprint_diagnostic(files, config, &diagnostic);
+        }
+    }
+}
+
+impl<'a, F: Files<'a>> Eprint<'a, F> for Error<F::FileId> {
+    #[allow(single_use_lifetimes)]
+    fn eprint<'f: 'a>(&self, files: &'f F, config: &Config) {
+        Self::eprint_diagnostic(files, config, &self.diagnostic());
+    }
+
+    #[allow(single_use_lifetimes)]
+    fn json<'f: 'a>(&self, files: &'f F) -> String {
+        crate::error::json_diagnostic(files, &self.diagnostic())
     }
 }
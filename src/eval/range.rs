@@ -110,3 +110,34 @@ impl DateRange {
         // the range should never shrink. It can only move and expand.
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::DateRange;
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn containing() {
+        let today = ymd(2024, 6, 15);
+
+        // Range entirely in the past
+        let range = DateRange::new(ymd(2024, 1, 1), ymd(2024, 1, 31)).containing(today);
+        assert_eq!(range.from(), ymd(2024, 1, 1));
+        assert_eq!(range.until(), today);
+
+        // Range entirely in the future
+        let range = DateRange::new(ymd(2024, 7, 1), ymd(2024, 7, 31)).containing(today);
+        assert_eq!(range.from(), today);
+        assert_eq!(range.until(), ymd(2024, 7, 31));
+
+        // Range already containing today
+        let range = DateRange::new(ymd(2024, 6, 1), ymd(2024, 6, 30)).containing(today);
+        assert_eq!(range.from(), ymd(2024, 6, 1));
+        assert_eq!(range.until(), ymd(2024, 6, 30));
+    }
+}
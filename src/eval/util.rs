@@ -35,3 +35,17 @@ pub fn add_months(year: i32, month: u32, delta: i32) -> (i32, u32) {
     let month = month0.rem_euclid(12) as u32 + 1;
     (year, month)
 }
+
+/// The fiscal year `date` lies in, named after the calendar year it starts in.
+pub fn fiscal_year(date: NaiveDate, start_month: u32) -> i32 {
+    if date.month() < start_month {
+        date.year() - 1
+    } else {
+        date.year()
+    }
+}
+
+/// The first day of the fiscal year `date` lies in.
+pub fn fiscal_year_start(date: NaiveDate, start_month: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(fiscal_year(date, start_month), start_month, 1).unwrap()
+}
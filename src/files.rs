@@ -1,23 +1,34 @@
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::{fs, result};
+use std::{fs, io, result};
 
 use chrono::{DateTime, NaiveDate, Utc};
 use codespan_reporting::files::SimpleFiles;
+use similar::TextDiff;
 use tzfile::Tz;
 
-use self::commands::{Command, Done, File, Log};
+use self::commands::{CaptureKind, Command, Done, File, Log, Spec, Statement, Template};
 pub use self::error::{Error, ParseError, Result};
+pub use self::parse::TimesOfDay;
 use self::primitives::Spanned;
 
 pub mod cli;
 pub mod commands;
+mod describe;
 mod error;
 mod format;
 mod parse;
 pub mod primitives;
 
+/// Parse a single file without loading any of its includes.
+///
+/// The `path` is only used in error messages. Times of day like `morning`
+/// resolve to their defaults.
+pub fn parse_file(path: &Path, content: &str) -> result::Result<File, ParseError<()>> {
+    parse::parse(path, content, &TimesOfDay::new()).map_err(|error| ParseError::new((), error))
+}
+
 // TODO Move file content from `File` to `LoadedFile`
 #[derive(Debug)]
 struct LoadedFile {
@@ -47,14 +58,14 @@ impl LoadedFile {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Source {
     file: usize,
     command: usize,
 }
 
 // TODO Rename to `SourceFile`?
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct FileSource(usize);
 
 impl Source {
@@ -65,6 +76,11 @@ impl Source {
     pub fn file(&self) -> FileSource {
         FileSource(self.file)
     }
+
+    /// The index of the file and of the command within that file.
+    pub fn indices(&self) -> (usize, usize) {
+        (self.file, self.command)
+    }
 }
 
 #[derive(Debug)]
@@ -85,7 +101,17 @@ pub struct Files {
     /// Codespan-reporting file database.
     cs_files: SimpleFiles<String, String>,
     timezone: Option<Tz>,
-    capture: Option<usize>,
+    /// Time zones used by `DATE`s in addition to [`Self::timezone`], by name.
+    zones: HashMap<String, Tz>,
+    /// Capture files by the kind of entry they apply to. The capture file for
+    /// `None` applies to all kinds without their own capture file.
+    captures: HashMap<Option<CaptureKind>, usize>,
+    /// The month the fiscal year starts in.
+    fiscal_year: u32,
+    /// How many occurrences a single command may generate, if not the default.
+    max_occurrences: Option<usize>,
+    /// What times of day like `morning` resolve to, if not their defaults.
+    times: TimesOfDay,
     logs: HashMap<NaiveDate, Source>,
 }
 
@@ -125,6 +151,12 @@ impl<'a> codespan_reporting::files::Files<'a> for Files {
     }
 }
 
+impl Default for Files {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Files {
     /* Loading */
 
@@ -133,7 +165,11 @@ impl Files {
             files: vec![],
             cs_files: SimpleFiles::new(),
             timezone: None,
-            capture: None,
+            zones: HashMap::new(),
+            captures: HashMap::new(),
+            fiscal_year: 1,
+            max_occurrences: None,
+            times: TimesOfDay::new(),
             logs: HashMap::new(),
         }
     }
@@ -157,7 +193,9 @@ impl Files {
 
         self.load_file(&mut loaded, path)?;
         self.determine_timezone()?;
+        self.determine_zones()?;
         self.determine_capture()?;
+        self.determine_fiscal_year()?;
         self.collect_logs()?;
 
         Ok(())
@@ -182,7 +220,7 @@ impl Files {
             .add(name.to_string_lossy().to_string(), content.clone());
 
         // Using `name` instead of `path` for the unwrap below.
-        let file = match parse::parse(name, &content) {
+        let file = match parse::parse(name, &content, &self.times) {
             Ok(file) => file,
             Err(error) => {
                 // Using a dummy file. This should be fine since we return an
@@ -260,14 +298,47 @@ impl Files {
         Ok(())
     }
 
+    fn determine_zones(&mut self) -> Result<()> {
+        let mut zones = HashMap::new();
+        for command in self.commands() {
+            let statements = match &command.value.value {
+                Command::Task(task) => &task.statements,
+                Command::Note(note) => &note.statements,
+                _ => continue,
+            };
+            for statement in statements {
+                let zone = match statement {
+                    Statement::Date(spec) => match &spec.value {
+                        Spec::Date(spec) => spec.start_zone.as_ref(),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                if let Some(zone) = zone {
+                    if let Entry::Vacant(entry) = zones.entry(zone.value.clone()) {
+                        let tz = Tz::named(&zone.value).map_err(|error| Error::ResolveTz {
+                            file: command.source.file(),
+                            span: zone.span,
+                            tz: zone.value.clone(),
+                            error,
+                        })?;
+                        entry.insert(tz);
+                    }
+                }
+            }
+        }
+        self.zones = zones;
+        Ok(())
+    }
+
     fn determine_capture(&mut self) -> Result<()> {
-        assert_eq!(self.capture, None);
+        assert!(self.captures.is_empty());
 
-        let mut found: Option<Source> = None;
+        let mut found: HashMap<Option<CaptureKind>, Source> = HashMap::new();
 
         for command in self.commands() {
-            if let Command::Capture = &command.value.value {
-                if let Some(found) = &found {
+            if let Command::Capture(kind) = &command.value.value {
+                if let Some(found) = found.get(kind) {
                     let found_cmd = self.command(*found);
                     return Err(Error::MultipleCapture {
                         file1: found.file(),
@@ -276,12 +347,44 @@ impl Files {
                         span2: command.value.span,
                     });
                 } else {
-                    found = Some(command.source);
+                    found.insert(*kind, command.source);
                 }
             }
         }
 
-        self.capture = found.map(|s| s.file);
+        self.captures = found
+            .into_iter()
+            .map(|(kind, source)| (kind, source.file))
+            .collect();
+
+        Ok(())
+    }
+
+    fn determine_fiscal_year(&mut self) -> Result<()> {
+        let mut found: Option<(Source, Spanned<u32>)> = None;
+
+        for command in self.commands() {
+            if let Command::FiscalYear(month) = &command.value.value {
+                if let Some((found_source, found_month)) = &found {
+                    if month.value != found_month.value {
+                        return Err(Error::FiscalYearConflict {
+                            file1: found_source.file(),
+                            span1: found_month.span,
+                            month1: found_month.value,
+                            file2: command.source.file(),
+                            span2: month.span,
+                            month2: month.value,
+                        });
+                    }
+                } else {
+                    found = Some((command.source, *month));
+                }
+            }
+        }
+
+        if let Some((_, month)) = found {
+            self.fiscal_year = month.value;
+        }
 
         Ok(())
     }
@@ -316,16 +419,47 @@ impl Files {
 
     /* Saving */
 
-    pub fn save(&self) -> Result<()> {
+    /// Save all dirty files, reporting each one to `log`. If `diff` is true,
+    /// print a unified diff of the changes made to each file before saving it.
+    ///
+    /// Returns the paths of the files whose contents changed.
+    pub fn save(&self, diff: bool, log: &mut dyn io::Write) -> Result<Vec<PathBuf>> {
+        let mut changed = vec![];
         for file in &self.files {
-            if file.dirty {
-                self.save_file(file)?;
+            if file.dirty && self.save_file(file, diff, log)? {
+                changed.push(file.name.clone());
             }
         }
-        Ok(())
+        Ok(changed)
     }
 
-    fn save_file(&self, file: &LoadedFile) -> Result<()> {
+    fn diff(name: &Path, previous: &str, formatted: &str) -> String {
+        let name = name.to_string_lossy();
+        TextDiff::from_lines(previous, formatted)
+            .unified_diff()
+            .header(&name, &name)
+            .to_string()
+    }
+
+    /// The diffs of all files [`Self::save`] would change, without writing
+    /// anything to disk.
+    pub fn pending_diffs(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .filter(|file| file.dirty)
+            .filter_map(|file| {
+                let (previous, formatted) = self.format_file(file);
+                if previous == &formatted {
+                    None
+                } else {
+                    Some(Self::diff(&file.name, previous, &formatted))
+                }
+            })
+            .collect()
+    }
+
+    /// The previous and the newly formatted contents of a file.
+    fn format_file(&self, file: &LoadedFile) -> (&String, String) {
         // TODO Sort commands within file
 
         let previous = self
@@ -336,17 +470,28 @@ impl Files {
 
         let formatted = file.file.format(&file.removed);
 
+        (previous, formatted)
+    }
+
+    /// Save a file if its contents changed, returning whether they did.
+    fn save_file(&self, file: &LoadedFile, diff: bool, log: &mut dyn io::Write) -> Result<bool> {
+        let (previous, formatted) = self.format_file(file);
+
+        // Failing to report progress shouldn't prevent the file from being saved
         if previous == &formatted {
-            println!("Unchanged file {:?}", file.name);
+            let _ = writeln!(log, "Unchanged file {:?}", file.name);
+            Ok(false)
         } else {
-            println!("Saving file {:?}", file.name);
+            let _ = writeln!(log, "Saving file {:?}", file.name);
+            if diff {
+                print!("{}", Self::diff(&file.name, previous, &formatted));
+            }
             fs::write(&file.name, &formatted).map_err(|e| Error::WriteFile {
                 file: file.name.to_path_buf(),
                 error: e,
             })?;
+            Ok(true)
         }
-
-        Ok(())
     }
 
     /* Querying */
@@ -383,6 +528,45 @@ impl Files {
         }
     }
 
+    /// The first template with the given name, in the order files were loaded.
+    pub fn template(&self, name: &str) -> Option<Sourced<'_, Template>> {
+        self.commands()
+            .into_iter()
+            .find_map(|command| match &command.value.value {
+                Command::Template(template) if template.name.value == name => {
+                    Some(Sourced::new(command.source, template))
+                }
+                _ => None,
+            })
+    }
+
+    /// Where the byte at `offset` of a template's body is located in its file.
+    /// The body is the template's description with its lines joined by
+    /// newlines, as parsed when creating an entry from the template.
+    pub fn template_offset(&self, source: Source, offset: usize) -> usize {
+        let span = self.command(source).value.span;
+        let text = self
+            .cs_files
+            .get(self.files[source.file].cs_id)
+            .expect("cs id is valid")
+            .source();
+
+        let mut offset = offset;
+        let mut lines = text[span.start..span.end].split_inclusive('\n');
+        // The first line is the TEMPLATE line itself
+        let mut start = span.start + lines.next().map_or(0, str::len);
+        for line in lines {
+            let prefix = if line.starts_with("# ") { 2 } else { 1 };
+            let len = line.trim_end_matches(['\r', '\n']).len() - prefix;
+            if offset <= len {
+                return start + prefix + offset;
+            }
+            offset -= len + 1;
+            start += line.len();
+        }
+        span.end
+    }
+
     fn latest_log(&self) -> Option<(NaiveDate, Source)> {
         self.logs
             .iter()
@@ -398,8 +582,71 @@ impl Files {
             .max_by_key(|(d, _)| *d)
     }
 
-    pub fn capture(&self) -> Option<FileSource> {
-        self.capture.map(FileSource)
+    /// The file new entries of the specified kind should be added to.
+    pub fn capture(&self, kind: CaptureKind) -> Option<FileSource> {
+        self.captures
+            .get(&Some(kind))
+            .or_else(|| self.captures.get(&None))
+            .copied()
+            .map(FileSource)
+    }
+
+    /// The path a file was loaded from.
+    pub fn path(&self, file: FileSource) -> &Path {
+        &self.files[file.0].name
+    }
+
+    /// The loaded file at `path`, comparing canonicalized paths.
+    pub fn file_source(&self, path: &Path) -> Option<FileSource> {
+        let path = path.canonicalize().ok()?;
+        self.files
+            .iter()
+            .position(|file| file.name.canonicalize().ok().as_ref() == Some(&path))
+            .map(FileSource)
+    }
+
+    /// The paths and parsed contents of all loaded files, in load order.
+    pub fn parsed(&self) -> Vec<(&Path, &File)> {
+        self.files
+            .iter()
+            .map(|file| (file.name.as_path(), &file.file))
+            .collect()
+    }
+
+    /// The month the fiscal year starts in, as set by `FISCALYEAR`.
+    pub fn fiscal_year(&self) -> u32 {
+        self.fiscal_year
+    }
+
+    /// How many occurrences a single command may generate, if not the default.
+    pub fn max_occurrences(&self) -> Option<usize> {
+        self.max_occurrences
+    }
+
+    pub fn set_max_occurrences(&mut self, max: Option<usize>) {
+        self.max_occurrences = max;
+    }
+
+    /// What times of day like `morning` resolve to, if not their defaults.
+    pub fn times_of_day(&self) -> &TimesOfDay {
+        &self.times
+    }
+
+    /// Override what times of day resolve to. Must be called before
+    /// [`Self::load`] to affect the loaded files.
+    pub fn set_times_of_day(&mut self, times: TimesOfDay) {
+        self.times = times;
+    }
+
+    pub fn timezone(&self) -> &Tz {
+        self.timezone
+            .as_ref()
+            .expect("Called Files::timezone before Files::load")
+    }
+
+    /// The time zones used by `DATE`s, see [`commands::DateSpec::start_zone`].
+    pub fn zones(&self) -> &HashMap<String, Tz> {
+        &self.zones
     }
 
     pub fn now(&self) -> DateTime<&Tz> {
@@ -451,6 +698,21 @@ impl Files {
         true
     }
 
+    /// Update the `PROGRESS` of the task identified by `source`.
+    ///
+    /// Tasks without a `PROGRESS` statement are left untouched.
+    pub fn set_progress(&mut self, source: Source, percent: u8) {
+        let file = &mut self.files[source.file];
+        if let Command::Task(t) = &mut file.file.commands[source.command].value {
+            for statement in &mut t.statements {
+                if let Statement::Progress(p) = statement {
+                    *p = percent;
+                    file.dirty = true;
+                }
+            }
+        }
+    }
+
     pub fn set_log(&mut self, date: NaiveDate, desc: Vec<String>) {
         if let Some(source) = self.logs.get(&date).cloned() {
             if desc.is_empty() {
@@ -481,3 +743,158 @@ impl Files {
         self.files[file.0].cs_id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use chrono::NaiveDate;
+
+    use crate::testing::TempDir;
+
+    use super::commands::{CaptureKind, Command, Done, DoneDate, DoneKind};
+    use super::error::Error;
+    use super::primitives::{Time, TimeOfDay};
+    use super::{parse_file, FileSource, Files, Source, TimesOfDay};
+
+    /// Write `main.today` and `other.today` into `dir`, with `main.today`
+    /// including `other.today`.
+    fn write_files(dir: &TempDir, main: &str, other: &str) -> PathBuf {
+        let main = format!("TIMEZONE UTC\nINCLUDE other.today\n{main}");
+        dir.write("other.today", other);
+        dir.write("main.today", &main)
+    }
+
+    #[test]
+    fn diff_after_done() {
+        let path = Path::new("test.today");
+        let previous = "TASK Laundry\nDATE 2024-01-01\n";
+        let mut file = parse_file(path, previous).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        match &mut file.commands[0].value {
+            Command::Task(task) => task.done.push(Done {
+                kind: DoneKind::Done,
+                date: Some(DoneDate::Date { root: date }),
+                done_at: date,
+                spent: None,
+            }),
+            _ => panic!("expected task"),
+        }
+        let formatted = file.format(&Default::default());
+
+        let diff = Files::diff(path, previous, &formatted);
+        assert!(diff.starts_with("--- test.today\n+++ test.today\n"));
+        assert!(diff.contains("\n+DONE [2024-01-01] 2024-01-01\n"));
+        assert!(!diff.contains("\n-"));
+    }
+
+    #[test]
+    fn times_of_day_in_includes() {
+        let dir = TempDir::new();
+        let path = write_files(&dir, "", "TASK Run\nDATE 2024-01-01 morning\n");
+        let mut files = Files::new();
+        files.set_times_of_day(TimesOfDay::from([(TimeOfDay::Morning, Time::new(7, 0))]));
+        files.load(&path).unwrap();
+        let (_, other) = files.parsed()[1];
+        assert_eq!(
+            other.format(&Default::default()),
+            "TASK Run\nDATE 2024-01-01 07:00\n"
+        );
+    }
+
+    #[test]
+    fn template_offsets() {
+        let dir = TempDir::new();
+        let main = "TEMPLATE review\n# TASK Review\n#\n# DATE x\n";
+        let path = write_files(&dir, main, "");
+        let mut files = Files::new();
+        files.load(&path).unwrap();
+        let source = files.template("review").unwrap().source;
+        let text = fs::read_to_string(&path).unwrap();
+
+        // Body: "TASK Review\n\nDATE x\n"
+        let at = |offset| &text[files.template_offset(source, offset)..];
+        assert!(at(0).starts_with("TASK Review\n"));
+        assert!(at(12).starts_with("\n# DATE x"));
+        assert!(at(18).starts_with("x\n"));
+        assert_eq!(at(20), "");
+    }
+
+    #[test]
+    fn capture_by_kind() {
+        let dir = TempDir::new();
+        let path = write_files(&dir, "CAPTURE tasks\n", "CAPTURE notes\n");
+        let mut files = Files::new();
+        files.load(&path).unwrap();
+        assert_eq!(files.capture(CaptureKind::Tasks), Some(FileSource(0)));
+        assert_eq!(files.capture(CaptureKind::Notes), Some(FileSource(1)));
+    }
+
+    #[test]
+    fn capture_fallback() {
+        let dir = TempDir::new();
+        let path = write_files(&dir, "CAPTURE\n", "CAPTURE notes\n");
+        let mut files = Files::new();
+        files.load(&path).unwrap();
+        assert_eq!(files.capture(CaptureKind::Tasks), Some(FileSource(0)));
+        assert_eq!(files.capture(CaptureKind::Notes), Some(FileSource(1)));
+
+        let dir = TempDir::new();
+        let path = write_files(&dir, "CAPTURE tasks\n", "");
+        let mut files = Files::new();
+        files.load(&path).unwrap();
+        assert_eq!(files.capture(CaptureKind::Tasks), Some(FileSource(0)));
+        assert_eq!(files.capture(CaptureKind::Notes), None);
+    }
+
+    #[test]
+    fn capture_conflict() {
+        let dir = TempDir::new();
+        let path = write_files(&dir, "CAPTURE notes\n", "CAPTURE tasks\nCAPTURE notes\n");
+        let mut files = Files::new();
+        let result = files.load(&path);
+        assert!(matches!(result, Err(Error::MultipleCapture { .. })));
+    }
+
+    #[test]
+    fn pending_diffs_leave_file_unchanged() {
+        let dir = TempDir::new();
+        let content = "TIMEZONE UTC\n\nTASK Laundry\nDATE 2024-01-01\n";
+        let path = dir.write("main.today", content);
+
+        let mut files = Files::new();
+        files.load(&path).unwrap();
+        assert!(files.pending_diffs().is_empty());
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let done = Done {
+            kind: DoneKind::Done,
+            date: Some(DoneDate::Date { root: date }),
+            done_at: date,
+            spent: None,
+        };
+        assert!(files.add_done(Source::new(0, 1), done));
+
+        let diffs = files.pending_diffs();
+        assert_eq!(diffs.len(), 1);
+        assert!(diffs[0].contains("\n+DONE [2024-01-01] 2024-01-01\n"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+    }
+
+    #[test]
+    fn save_log() {
+        let dir = TempDir::new();
+        let path = dir.write("main.today", "TIMEZONE UTC\n");
+
+        let mut files = Files::new();
+        files.load(&path).unwrap();
+        files.mark_all_dirty();
+
+        let mut log = vec![];
+        let changed = files.save(false, &mut log).unwrap();
+        let log = String::from_utf8(log).unwrap();
+        assert_eq!(log, format!("Unchanged file {path:?}\n"));
+        assert!(changed.is_empty());
+    }
+}
@@ -5,8 +5,9 @@ use chrono::NaiveDate;
 use pest::iterators::Pair;
 use pest::Parser;
 
-use super::commands::{Command, Delta};
-use super::parse::{self, Result, Rule, TodayfileParser};
+use super::commands::{Command, Delta, Expr};
+use super::parse::{self, Result, Rule, TimesOfDay, TodayfileParser};
+use super::primitives::Spanned;
 use super::ParseError;
 
 fn from_str_via_parse<P, R>(s: &str, rule: Rule, parse: P) -> result::Result<R, ParseError<()>>
@@ -176,19 +177,36 @@ impl FromStr for CliRange {
     }
 }
 
+/// A formula in parentheses, like `(wd = fri)`.
 #[derive(Debug)]
-pub struct CliCommand(pub Command);
+pub struct CliExpr(pub Spanned<Expr>);
 
-fn parse_cli_command(p: Pair<'_, Rule>) -> Result<CliCommand> {
-    assert_eq!(p.as_rule(), Rule::cli_command);
+fn parse_cli_expr_arg(p: Pair<'_, Rule>) -> Result<CliExpr> {
+    assert_eq!(p.as_rule(), Rule::cli_expr_arg);
     let p = p.into_inner().next().unwrap();
-    Ok(CliCommand(parse::parse_command(p)?.value))
+    Ok(CliExpr(parse::parse_paren_expr(p)))
 }
 
-impl FromStr for CliCommand {
+impl FromStr for CliExpr {
     type Err = ParseError<()>;
 
     fn from_str(s: &str) -> result::Result<Self, ParseError<()>> {
-        from_str_via_parse(s, Rule::cli_command, parse_cli_command)
+        from_str_via_parse(s, Rule::cli_expr_arg, parse_cli_expr_arg)
+    }
+}
+
+#[derive(Debug)]
+pub struct CliCommand(pub Command);
+
+fn parse_cli_command(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<CliCommand> {
+    assert_eq!(p.as_rule(), Rule::cli_command);
+    let p = p.into_inner().next().unwrap();
+    Ok(CliCommand(parse::parse_command(p, times)?.value))
+}
+
+impl CliCommand {
+    /// Parse a command, resolving times of day like `morning` using `times`.
+    pub fn parse(s: &str, times: &TimesOfDay) -> result::Result<Self, ParseError<()>> {
+        from_str_via_parse(s, Rule::cli_command, |p| parse_cli_command(p, times))
     }
 }
@@ -1,8 +1,9 @@
 use chrono::NaiveDate;
+use serde::Serialize;
 
 use super::primitives::{Span, Spanned, Time, Weekday};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize)]
 pub enum DeltaStep {
     /// `y`, move by a year, keeping the same month and day
     Year(i32),
@@ -14,6 +15,8 @@ pub enum DeltaStep {
     Day(i32),
     /// `w`, move by 7 days
     Week(i32),
+    /// `b`, move by a business day (monday to friday), skipping weekends
+    BusinessDay(i32),
     /// `h`
     Hour(i32),
     /// `m`
@@ -32,6 +35,7 @@ impl DeltaStep {
             Self::MonthReverse(i) => *i,
             Self::Day(i) => *i,
             Self::Week(i) => *i,
+            Self::BusinessDay(i) => *i,
             Self::Hour(i) => *i,
             Self::Minute(i) => *i,
             Self::Weekday(i, _) => *i,
@@ -45,6 +49,7 @@ impl DeltaStep {
             Self::MonthReverse(_) => "M",
             Self::Day(_) => "d",
             Self::Week(_) => "w",
+            Self::BusinessDay(_) => "b",
             Self::Hour(_) => "h",
             Self::Minute(_) => "min",
             Self::Weekday(_, wd) => wd.name(),
@@ -52,10 +57,25 @@ impl DeltaStep {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct Delta(pub Vec<Spanned<DeltaStep>>);
 
-#[derive(Debug)]
+impl Delta {
+    /// The length of the delta in minutes, or `None` if it contains steps
+    /// other than hours and minutes.
+    pub fn minutes(&self) -> Option<i64> {
+        self.0
+            .iter()
+            .map(|step| match step.value {
+                DeltaStep::Hour(h) => Some(i64::from(h) * 60),
+                DeltaStep::Minute(m) => Some(i64::from(m)),
+                _ => None,
+            })
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Repeat {
     /// Start at the date when the latest `DONE` was created instead of the
     /// task's previous occurrence.
@@ -63,28 +83,40 @@ pub struct Repeat {
     pub delta: Spanned<Delta>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DateSpec {
     pub start: NaiveDate,
     pub start_delta: Option<Delta>,
     pub start_time: Option<Time>,
+    /// Time zone the times are given in if it differs from the file's, like
+    /// `America/New_York`.
+    pub start_zone: Option<Spanned<String>>,
     pub end: Option<Spanned<NaiveDate>>,
     pub end_delta: Option<Delta>,
     pub end_time: Option<Spanned<Time>>,
+    /// Span of the `--]` marking the end as exclusive, if any.
+    pub end_exclusive: Option<Span>,
     pub repeat: Option<Repeat>,
     // TODO Allow specifying amount of repetitions
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct WeekdaySpec {
     pub start: Weekday,
+    /// Further weekdays matched in addition to `start`, as in `mon,thu`.
+    pub others: Vec<Weekday>,
+    /// Only the nth occurrence of the weekday within its month, counting from
+    /// the end of the month if negative.
+    pub nth: Option<i32>,
+    /// Only the days on which this expression is true.
+    pub filter: Option<Spanned<Expr>>,
     pub start_time: Option<Time>,
     pub end: Option<Spanned<Weekday>>,
     pub end_delta: Option<Delta>,
     pub end_time: Option<Spanned<Time>>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize)]
 pub enum Var {
     /// `true`, always 1
     True,
@@ -150,10 +182,34 @@ pub enum Var {
     IsoYearLength,
     /// `iw`, ISO 8601 week
     IsoWeek,
+    /// `iyw`, week of the year aligned to ISO 8601 weeks
+    ///
+    /// Equal to `iw`. Unlike `yw`, weeks always start on a monday, so the first
+    /// days of January may belong to week 52 or 53 of the previous year.
+    IsoYearWeek,
+    /// `imw`, week of the month aligned to weeks starting on monday, 1 during
+    /// the week containing the first day of the month, 2 during the next etc.
+    ///
+    /// Unlike `mw`, the first week of the month may be shorter than 7 days.
+    IsoMonthWeek,
+    /// `fy`, fiscal year, named after the calendar year it starts in
+    ///
+    /// The month the fiscal year starts in is set using `FISCALYEAR`.
+    FiscalYear,
+    /// `fyd`, day of the fiscal year
+    FiscalYearDay,
     /// `wd`, day of the week, starting at monday with 1
     Weekday,
     /// `e`, day of the year that easter falls on
     Easter,
+    /// `eNext`, day of the year of the next easter, which is in the following
+    /// year if this year's easter has already passed
+    EasterNext,
+    /// `ePrev`, day of the year of the previous easter, which is in the
+    /// preceding year if this year's easter hasn't happened yet
+    EasterPrev,
+    /// `daysToEaster`, number of days until the next easter, 0 on easter
+    DaysToEaster,
     /// `isWeekday`, whether the current day is one of mon-fri
     IsWeekday,
     /// `isWeekend`, whether the current day is one of sat-sun
@@ -194,8 +250,15 @@ impl Var {
             Self::IsoYear => "iy",
             Self::IsoYearLength => "iyl",
             Self::IsoWeek => "iw",
+            Self::IsoYearWeek => "iyw",
+            Self::IsoMonthWeek => "imw",
+            Self::FiscalYear => "fy",
+            Self::FiscalYearDay => "fyd",
             Self::Weekday => "wd",
             Self::Easter => "e",
+            Self::EasterNext => "eNext",
+            Self::EasterPrev => "ePrev",
+            Self::DaysToEaster => "daysToEaster",
             // Variables with "boolean" values
             Self::IsWeekday => "isWeekday",
             Self::IsWeekend => "isWeekend",
@@ -205,7 +268,7 @@ impl Var {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Expr {
     Lit(i64),
     Var(Var),
@@ -215,8 +278,16 @@ pub enum Expr {
     Add(Box<Spanned<Expr>>, Box<Spanned<Expr>>),
     Sub(Box<Spanned<Expr>>, Box<Spanned<Expr>>),
     Mul(Box<Spanned<Expr>>, Box<Spanned<Expr>>),
+    /// Euclidean division, `/`. The remainder is never negative, so `-7 / 2`
+    /// is `-4`.
     Div(Box<Spanned<Expr>>, Box<Spanned<Expr>>),
+    /// Euclidean remainder, `%`. Never negative, so `-7 % 2` is `1`.
     Mod(Box<Spanned<Expr>>, Box<Spanned<Expr>>),
+    /// Division rounding towards zero, `quot`, so `-7 quot 2` is `-3`.
+    Quot(Box<Spanned<Expr>>, Box<Spanned<Expr>>),
+    /// Remainder of [`Self::Quot`], `rem`. Has the sign of the dividend, so
+    /// `-7 rem 2` is `-1`.
+    Rem(Box<Spanned<Expr>>, Box<Spanned<Expr>>),
     // Comparisons
     Eq(Box<Spanned<Expr>>, Box<Spanned<Expr>>),
     Neq(Box<Spanned<Expr>>, Box<Spanned<Expr>>),
@@ -231,7 +302,7 @@ pub enum Expr {
     Xor(Box<Spanned<Expr>>, Box<Spanned<Expr>>),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct FormulaSpec {
     pub start: Option<Spanned<Expr>>, // None: *
     pub start_delta: Option<Delta>,
@@ -240,39 +311,80 @@ pub struct FormulaSpec {
     pub end_time: Option<Spanned<Time>>,
 }
 
-#[derive(Debug)]
+/// A date relative to the date of the entry with the ID `id`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AfterSpec {
+    pub id: Spanned<String>,
+    pub start_delta: Option<Delta>,
+    pub start_time: Option<Time>,
+    pub end_delta: Option<Delta>,
+    pub end_time: Option<Spanned<Time>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub enum Spec {
     Date(DateSpec),
     Weekday(WeekdaySpec),
     Formula(FormulaSpec),
+    After(AfterSpec),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BirthdaySpec {
     pub date: NaiveDate,
     pub year_known: bool, // If year is unknown, use NaiveDate of year 0
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
+pub enum Remind {
+    /// `REMIND <delta>`, relative to the entry's start date
+    Delta(Spanned<Delta>),
+    /// `REMIND on <date>`
+    Date(Spanned<NaiveDate>),
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub enum Statement {
-    Date(Spec),
+    Date(Spanned<Spec>),
     BDate(BirthdaySpec),
+    /// A note that is displayed on the current day while it is active.
+    Active {
+        from: NaiveDate,
+        until: NaiveDate,
+    },
     // TODO Allow specifying delta and repetitions for FROM and UNTIL
     From(Option<NaiveDate>),
     Until(Option<NaiveDate>),
     // TODO Allow excluding ranges (maybe with --range syntax?)
     Except(NaiveDate),
+    /// Remove all occurrences generated so far whose root date satisfies the
+    /// expression.
+    ///
+    /// Written as `EXCEPT (expr)`. `SKIP (expr)` is rejected with a hint to use
+    /// `EXCEPT (expr)` instead.
+    ExceptExpr(Spanned<Expr>),
     Move {
         span: Span,
         from: NaiveDate,
         to: Option<NaiveDate>,
         to_time: Option<Spanned<Time>>,
     },
-    Remind(Option<Spanned<Delta>>),
+    Remind(Option<Remind>),
+    /// When the entry was created.
+    Created(NaiveDate),
+    /// An ID other entries can use to refer to this entry.
+    Id(String),
+    Tag(Vec<String>),
+    /// The task is waiting on someone or something.
+    Waiting(String),
+    /// How much of the task is done, in percent (0-100).
+    Progress(u8),
+    /// How long the task is estimated to take.
+    Effort(Delta),
 }
 
 #[allow(clippy::enum_variant_names)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize)]
 pub enum DoneDate {
     Date {
         root: NaiveDate,
@@ -337,22 +449,24 @@ impl DoneDate {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub enum DoneKind {
     Done,
     Canceled,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Done {
     pub kind: DoneKind,
     /// The date of the task the DONE refers to.
     pub date: Option<DoneDate>,
     /// When the task was actually completed.
     pub done_at: NaiveDate,
+    /// How much time was actually spent on the task.
+    pub spent: Option<Delta>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Task {
     pub title: String,
     pub statements: Vec<Statement>,
@@ -360,30 +474,124 @@ pub struct Task {
     pub desc: Vec<String>,
 }
 
-#[derive(Debug)]
+impl Task {
+    /// Who or what the task is waiting on, as set by the last `WAITING`.
+    pub fn waiting_on(&self) -> Option<&str> {
+        self.statements.iter().rev().find_map(|s| match s {
+            Statement::Waiting(on) => Some(on.as_str()),
+            _ => None,
+        })
+    }
+
+    /// How long the task is estimated to take, as set by the last `EFFORT`.
+    pub fn effort(&self) -> Option<&Delta> {
+        self.statements.iter().rev().find_map(|s| match s {
+            Statement::Effort(effort) => Some(effort),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
 pub struct Note {
     pub title: String,
     pub statements: Vec<Statement>,
     pub desc: Vec<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Log {
     pub date: Spanned<NaiveDate>,
     pub desc: Vec<String>,
 }
 
-#[derive(Debug)]
+/// A named snippet new entries can be based on, see `today new`.
+#[derive(Debug, Serialize)]
+pub struct Template {
+    pub name: Spanned<String>,
+    /// The text new entries start out with, one line per element.
+    pub body: Vec<String>,
+}
+
+/// The kind of new entries a `CAPTURE` applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum CaptureKind {
+    Tasks,
+    Notes,
+}
+
+impl CaptureKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Tasks => "tasks",
+            Self::Notes => "notes",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
 pub enum Command {
     Include(Spanned<String>),
     Timezone(Spanned<String>),
-    Capture, // TODO Set capture file by template?
+    /// Mark the file as target for new entries, optionally only for a
+    /// specific kind of entry.
+    Capture(Option<CaptureKind>),
+    /// The month (1-12) the fiscal year starts in.
+    FiscalYear(Spanned<u32>),
     Task(Task),
     Note(Note),
     Log(Log),
+    Template(Template),
+}
+
+impl Command {
+    /// The ID set by the first `ID` statement.
+    pub fn id(&self) -> Option<&str> {
+        let statements = match self {
+            Self::Task(task) => &task.statements,
+            Self::Note(note) => &note.statements,
+            _ => return None,
+        };
+
+        statements.iter().find_map(|s| match s {
+            Statement::Id(id) => Some(id.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The description of tasks, notes and logs.
+    pub fn desc(&self) -> &[String] {
+        match self {
+            Self::Task(task) => &task.desc,
+            Self::Note(note) => &note.desc,
+            Self::Log(log) => &log.desc,
+            _ => &[],
+        }
+    }
+
+    /// All tags set using `TAG` statements, without duplicates.
+    pub fn tags(&self) -> Vec<&str> {
+        let statements = match self {
+            Self::Task(task) => &task.statements,
+            Self::Note(note) => &note.statements,
+            _ => return vec![],
+        };
+
+        let mut tags = vec![];
+        for statement in statements {
+            if let Statement::Tag(names) = statement {
+                for name in names {
+                    if !tags.contains(&name.as_str()) {
+                        tags.push(name.as_str());
+                    }
+                }
+            }
+        }
+        tags
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct File {
     pub commands: Vec<Spanned<Command>>,
 }
@@ -0,0 +1,198 @@
+use super::commands::{
+    Command, DateSpec, Delta, DeltaStep, FormulaSpec, Spec, Statement, WeekdaySpec,
+};
+
+fn ordinal(n: i32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+/// Join items like `a, b and c`.
+fn enumerate(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [item] => item.clone(),
+        [init @ .., last] => format!("{} and {last}", init.join(", ")),
+    }
+}
+
+fn describe_step(step: &DeltaStep) -> String {
+    let amount = step.amount().abs();
+    let unit = match step {
+        DeltaStep::Year(_) => "year",
+        DeltaStep::Month(_) => "month",
+        DeltaStep::MonthReverse(_) => "month",
+        DeltaStep::Day(_) => "day",
+        DeltaStep::Week(_) => "week",
+        DeltaStep::BusinessDay(_) => "business day",
+        DeltaStep::Hour(_) => "hour",
+        DeltaStep::Minute(_) => "minute",
+        DeltaStep::Weekday(_, wd) => wd.full_name(),
+    };
+    if amount == 1 {
+        unit.to_string()
+    } else {
+        format!("{amount} {unit}s")
+    }
+}
+
+impl Delta {
+    /// How often a repeat with this delta occurs, like `every 2 weeks`.
+    fn describe_repeat(&self) -> String {
+        let steps = self
+            .0
+            .iter()
+            .map(|step| describe_step(&step.value))
+            .collect::<Vec<_>>();
+        let mut result = format!("every {}", enumerate(&steps));
+        if self
+            .0
+            .iter()
+            .any(|step| matches!(step.value, DeltaStep::MonthReverse(_)))
+        {
+            result.push_str(", counting days from the end of the month");
+        }
+        result
+    }
+}
+
+impl DateSpec {
+    fn describe(&self) -> Option<String> {
+        let repeat = self.repeat.as_ref()?;
+        let mut result = repeat.delta.value.describe_repeat();
+        if repeat.start_at_done {
+            result.push_str(" after completion");
+        }
+        Some(result)
+    }
+}
+
+impl WeekdaySpec {
+    fn describe(&self) -> String {
+        let days = Some(self.start)
+            .into_iter()
+            .chain(self.others.iter().copied())
+            .map(|wd| wd.full_name().to_string())
+            .collect::<Vec<_>>();
+        let days = enumerate(&days);
+        let mut result = match self.nth {
+            None => format!("every {days}"),
+            Some(-1) => format!("last {days} of each month"),
+            Some(n) if n < 0 => format!("{} to last {days} of each month", ordinal(-n)),
+            Some(n) => format!("{} {days} of each month", ordinal(n)),
+        };
+        if let Some(filter) = &self.filter {
+            result.push_str(&format!(" where {}", filter.value));
+        }
+        result
+    }
+}
+
+impl FormulaSpec {
+    fn describe(&self) -> String {
+        match &self.start {
+            None => "every day".to_string(),
+            Some(expr) => format!("every day where {}", expr.value),
+        }
+    }
+}
+
+impl Spec {
+    /// A human-readable description of how this spec recurs, or `None` if it
+    /// only occurs once.
+    pub fn describe(&self) -> Option<String> {
+        match self {
+            Self::Date(spec) => spec.describe(),
+            Self::Weekday(spec) => Some(spec.describe()),
+            Self::Formula(spec) => Some(spec.describe()),
+            Self::After(_) => None,
+        }
+    }
+}
+
+impl Command {
+    /// Descriptions of how the command's `DATE`s recur, joined by `; `.
+    pub fn recurrence(&self) -> Option<String> {
+        let statements = match self {
+            Self::Task(task) => &task.statements,
+            Self::Note(note) => &note.statements,
+            _ => return None,
+        };
+
+        let descriptions = statements
+            .iter()
+            .filter_map(|s| match s {
+                Statement::Date(spec) => spec.value.describe(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if descriptions.is_empty() {
+            None
+        } else {
+            Some(descriptions.join("; "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::super::parse::{self, TimesOfDay};
+
+    fn describe(spec: &str) -> Option<String> {
+        let input = format!("NOTE Test\nDATE {spec}\n");
+        let file = parse::parse(Path::new("test.today"), &input, &TimesOfDay::new()).unwrap();
+        file.commands[0].value.recurrence()
+    }
+
+    #[test]
+    fn date_specs() {
+        assert_eq!(describe("2024-01-01"), None);
+        assert_eq!(describe("2024-01-01; +d").as_deref(), Some("every day"));
+        assert_eq!(
+            describe("2024-01-01; +2w").as_deref(),
+            Some("every 2 weeks")
+        );
+        assert_eq!(
+            describe("2024-01-01; +1m+2d").as_deref(),
+            Some("every month and 2 days")
+        );
+        assert_eq!(
+            describe("2024-01-01; done +3d").as_deref(),
+            Some("every 3 days after completion")
+        );
+    }
+
+    #[test]
+    fn weekday_specs() {
+        assert_eq!(describe("mon").as_deref(), Some("every Monday"));
+        assert_eq!(
+            describe("mon,wed,fri").as_deref(),
+            Some("every Monday, Wednesday and Friday")
+        );
+        assert_eq!(
+            describe("mon #2").as_deref(),
+            Some("2nd Monday of each month")
+        );
+        assert_eq!(
+            describe("fri #-1").as_deref(),
+            Some("last Friday of each month")
+        );
+    }
+
+    #[test]
+    fn formula_specs() {
+        assert_eq!(describe("*").as_deref(), Some("every day"));
+        assert_eq!(
+            describe("(d = 1)").as_deref(),
+            Some("every day where d = 1")
+        );
+    }
+}
@@ -23,6 +23,18 @@ impl<S> ParseError<S> {
         Self { file, error }
     }
 
+    /// Attribute the error to `file` instead, moving its location with
+    /// `offset`. Useful for text that was parsed separately from the file it
+    /// was taken from.
+    pub fn relocate<T>(self, file: T, offset: impl Fn(usize) -> usize) -> ParseError<T> {
+        let mut error = self.error;
+        error.location = match error.location {
+            InputLocation::Pos(at) => InputLocation::Pos(offset(at)),
+            InputLocation::Span((from, to)) => InputLocation::Span((offset(from), offset(to))),
+        };
+        ParseError::new(file, error)
+    }
+
     fn rule_name(rule: parse::Rule) -> String {
         // TODO Rename rules to be more readable?
         format!("{:?}", rule)
@@ -65,22 +77,36 @@ impl<S> ParseError<S> {
     }
 }
 
-impl<'a, F> Eprint<'a, F> for ParseError<F::FileId>
-where
-    F: codespan_reporting::files::Files<'a>,
-{
+impl<S: Copy> ParseError<S> {
     #[allow(single_use_lifetimes)]
-    fn eprint<'f: 'a>(&self, files: &'f F, config: &Config) {
+    fn diagnostic<'a, 'f: 'a, F>(&self, files: &'f F) -> Diagnostic<S>
+    where
+        F: codespan_reporting::files::Files<'a, FileId = S>,
+    {
         let range = match self.error.location {
             InputLocation::Pos(at) => at..at,
             InputLocation::Span((from, to)) => from..to,
         };
         let name = files.name(self.file).expect("file exists");
-        let diagnostic = Diagnostic::error()
+        Diagnostic::error()
             .with_message(format!("Could not parse {name}"))
             .with_labels(vec![Label::primary(self.file, range)])
-            .with_notes(self.notes());
-        Self::eprint_diagnostic(files, config, &diagnostic);
+            .with_notes(self.notes())
+    }
+}
+
+impl<'a, F> Eprint<'a, F> for ParseError<F::FileId>
+where
+    F: codespan_reporting::files::Files<'a>,
+{
+    #[allow(single_use_lifetimes)]
+    fn eprint<'f: 'a>(&self, files: &'f F, config: &Config) {
+        Self::eprint_diagnostic(files, config, &self.diagnostic(files));
+    }
+
+    #[allow(single_use_lifetimes)]
+    fn json<'f: 'a>(&self, files: &'f F) -> String {
+        crate::error::json_diagnostic(files, &self.diagnostic(files))
     }
 }
 
@@ -115,6 +141,15 @@ pub enum Error {
         span2: Span,
         tz2: String,
     },
+    #[error("Conflicting fiscal years starting in month {month1} and {month2}")]
+    FiscalYearConflict {
+        file1: FileSource,
+        span1: Span,
+        month1: u32,
+        file2: FileSource,
+        span2: Span,
+        month2: u32,
+    },
     #[error("Multiple capture commands")]
     MultipleCapture {
         file1: FileSource,
@@ -132,41 +167,25 @@ pub enum Error {
     },
 }
 
-impl<'a> Eprint<'a, Files> for Error {
-    #[allow(single_use_lifetimes)]
-    fn eprint<'f: 'a>(&self, files: &'f Files, config: &Config) {
-        match self {
-            Self::ResolvePath { path, error } => {
-                eprintln!("Could not resolve path {path:?}:");
-                eprintln!("  {error}");
-            }
-            Self::ReadFile { file, error } => {
-                eprintln!("Could not read file {file:?}:");
-                eprintln!("  {error}");
-            }
-            Self::WriteFile { file, error } => {
-                eprintln!("Could not write file {file:?}:");
-                eprintln!("  {error}");
-            }
+impl Error {
+    /// The diagnostic for errors pointing at a location in the files, or
+    /// `None` for errors without a location and for parse errors.
+    fn diagnostic(&self) -> Option<Diagnostic<FileSource>> {
+        let diagnostic = match self {
+            Self::ResolvePath { .. }
+            | Self::ReadFile { .. }
+            | Self::WriteFile { .. }
+            | Self::LocalTz { .. }
+            | Self::Parse { .. } => return None,
             Self::ResolveTz {
                 file,
                 span,
                 tz,
                 error,
-            } => {
-                let diagnostic = Diagnostic::error()
-                    .with_message(format!("Could not resolve time zone {tz}"))
-                    .with_labels(vec![Label::primary(*file, span)])
-                    .with_notes(vec![format!("{error}")]);
-                Self::eprint_diagnostic(files, config, &diagnostic);
-            }
-            Self::LocalTz { error } => {
-                eprintln!("Could not determine local timezone:");
-                eprintln!("  {error}");
-            }
-            Self::Parse { file, error } => {
-                ParseError::new(*file, error.clone()).eprint(files, config)
-            }
+            } => Diagnostic::error()
+                .with_message(format!("Could not resolve time zone {tz}"))
+                .with_labels(vec![Label::primary(*file, span)])
+                .with_notes(vec![format!("{error}")]),
             Self::TzConflict {
                 file1,
                 span1,
@@ -174,51 +193,104 @@ impl<'a> Eprint<'a, Files> for Error {
                 file2,
                 span2,
                 tz2,
-            } => {
-                let diagnostic = Diagnostic::error()
-                    .with_message(format!("Time zone conflict between {tz1} and {tz2}"))
-                    .with_labels(vec![
-                        Label::primary(*file1, span1),
-                        Label::primary(*file2, span2),
-                    ])
-                    .with_notes(vec![
-                        "All TIMEZONE commands must set the same time zone.".to_string()
-                    ]);
-                Self::eprint_diagnostic(files, config, &diagnostic);
-            }
+            } => Diagnostic::error()
+                .with_message(format!("Time zone conflict between {tz1} and {tz2}"))
+                .with_labels(vec![
+                    Label::primary(*file1, span1),
+                    Label::primary(*file2, span2),
+                ])
+                .with_notes(vec![
+                    "All TIMEZONE commands must set the same time zone.".to_string()
+                ]),
+            Self::FiscalYearConflict {
+                file1,
+                span1,
+                month1,
+                file2,
+                span2,
+                month2,
+            } => Diagnostic::error()
+                .with_message(format!(
+                    "Fiscal year conflict between months {month1} and {month2}"
+                ))
+                .with_labels(vec![
+                    Label::primary(*file1, span1),
+                    Label::primary(*file2, span2),
+                ])
+                .with_notes(vec![
+                    "All FISCALYEAR commands must set the same month.".to_string()
+                ]),
             Self::MultipleCapture {
                 file1,
                 span1,
                 file2,
                 span2,
-            } => {
-                let diagnostic = Diagnostic::error()
-                    .with_message("Multiple capture commands")
-                    .with_labels(vec![
-                        Label::primary(*file1, span1),
-                        Label::primary(*file2, span2),
-                    ])
-                    .with_notes(vec![
-                        "There must be at most one CAPTURE command.".to_string()
-                    ]);
-                Self::eprint_diagnostic(files, config, &diagnostic);
-            }
+            } => Diagnostic::error()
+                .with_message("Multiple capture commands")
+                .with_labels(vec![
+                    Label::primary(*file1, span1),
+                    Label::primary(*file2, span2),
+                ])
+                .with_notes(vec![
+                    "There must be at most one CAPTURE command per kind.".to_string()
+                ]),
             Self::LogConflict {
                 file1,
                 span1,
                 file2,
                 span2,
                 date,
-            } => {
-                let diagnostic = Diagnostic::error()
-                    .with_message(format!("Duplicate log entries for {date}"))
-                    .with_labels(vec![
-                        Label::primary(*file1, span1),
-                        Label::primary(*file2, span2),
-                    ])
-                    .with_notes(vec!["A day can have at most one LOG entry.".to_string()]);
-                Self::eprint_diagnostic(files, config, &diagnostic);
+            } => Diagnostic::error()
+                .with_message(format!("Duplicate log entries for {date}"))
+                .with_labels(vec![
+                    Label::primary(*file1, span1),
+                    Label::primary(*file2, span2),
+                ])
+                .with_notes(vec!["A day can have at most one LOG entry.".to_string()]),
+        };
+        Some(diagnostic)
+    }
+}
+
+impl<'a> Eprint<'a, Files> for Error {
+    #[allow(single_use_lifetimes)]
+    fn eprint<'f: 'a>(&self, files: &'f Files, config: &Config) {
+        if let Some(diagnostic) = self.diagnostic() {
+            Self::eprint_diagnostic(files, config, &diagnostic);
+            return;
+        }
+        match self {
+            Self::ResolvePath { path, error } => {
+                eprintln!("Could not resolve path {path:?}:");
+                eprintln!("  {error}");
+            }
+            Self::ReadFile { file, error } => {
+                eprintln!("Could not read file {file:?}:");
+                eprintln!("  {error}");
+            }
+            Self::WriteFile { file, error } => {
+                eprintln!("Could not write file {file:?}:");
+                eprintln!("  {error}");
+            }
+            Self::LocalTz { error } => {
+                eprintln!("Could not determine local timezone:");
+                eprintln!("  {error}");
+            }
+            Self::Parse { file, error } => {
+                ParseError::new(*file, error.clone()).eprint(files, config)
             }
+            _ => unreachable!("errors with a location are printed as diagnostic"),
+        }
+    }
+
+    #[allow(single_use_lifetimes)]
+    fn json<'f: 'a>(&self, files: &'f Files) -> String {
+        if let Self::Parse { file, error } = self {
+            return ParseError::new(*file, error.clone()).json(files);
+        }
+        match self.diagnostic() {
+            Some(diagnostic) => crate::error::json_diagnostic(files, &diagnostic),
+            None => crate::error::json_message(&self.to_string()),
         }
     }
 }
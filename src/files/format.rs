@@ -6,8 +6,8 @@ use chrono::Datelike;
 use crate::files::commands::DoneKind;
 
 use super::commands::{
-    BirthdaySpec, Command, DateSpec, Delta, DeltaStep, Done, DoneDate, Expr, File, FormulaSpec,
-    Log, Note, Repeat, Spec, Statement, Task, Var, WeekdaySpec,
+    AfterSpec, BirthdaySpec, Command, DateSpec, Delta, DeltaStep, Done, DoneDate, Expr, File,
+    FormulaSpec, Log, Note, Remind, Repeat, Spec, Statement, Task, Template, Var, WeekdaySpec,
 };
 use super::primitives::{Spanned, Time, Weekday};
 
@@ -28,6 +28,15 @@ fn format_desc(f: &mut fmt::Formatter<'_>, desc: &[String]) -> fmt::Result {
     Ok(())
 }
 
+fn format_include(f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+    if name.contains(char::is_whitespace) || name.starts_with('"') {
+        let escaped = name.replace('\\', "\\\\").replace('"', "\\\"");
+        writeln!(f, "INCLUDE \"{escaped}\"")
+    } else {
+        writeln!(f, "INCLUDE {name}")
+    }
+}
+
 impl fmt::Display for Time {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:02}:{:02}", self.hour, self.min)
@@ -81,10 +90,17 @@ impl fmt::Display for DateSpec {
         if let Some(time) = &self.start_time {
             write!(f, " {time}")?;
         }
+        if let Some(zone) = &self.start_zone {
+            write!(f, " {}", zone.value)?;
+        }
 
         // End
         if self.end.is_some() || self.end_delta.is_some() || self.end_time.is_some() {
-            write!(f, " --")?;
+            if self.end_exclusive.is_some() {
+                write!(f, " --]")?;
+            } else {
+                write!(f, " --")?;
+            }
             if let Some(date) = self.end {
                 write!(f, " {date}")?;
             }
@@ -109,6 +125,15 @@ impl fmt::Display for WeekdaySpec {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Start
         write!(f, "{}", self.start)?;
+        for wd in &self.others {
+            write!(f, ",{wd}")?;
+        }
+        if let Some(nth) = self.nth {
+            write!(f, " #{nth}")?;
+        }
+        if let Some(expr) = &self.filter {
+            write!(f, " ({expr})")?;
+        }
         if let Some(time) = &self.start_time {
             write!(f, " {time}")?;
         }
@@ -149,6 +174,8 @@ impl fmt::Display for Expr {
             Self::Mul(a, b) => write!(f, "{a} * {b}"),
             Self::Div(a, b) => write!(f, "{a} / {b}"),
             Self::Mod(a, b) => write!(f, "{a} % {b}"),
+            Self::Quot(a, b) => write!(f, "{a} quot {b}"),
+            Self::Rem(a, b) => write!(f, "{a} rem {b}"),
             Self::Eq(a, b) => write!(f, "{a} = {b}"),
             Self::Neq(a, b) => write!(f, "{a} != {b}"),
             Self::Lt(a, b) => write!(f, "{a} < {b}"),
@@ -193,12 +220,39 @@ impl fmt::Display for FormulaSpec {
     }
 }
 
+impl fmt::Display for AfterSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Start
+        write!(f, "after {}", self.id)?;
+        if let Some(delta) = &self.start_delta {
+            write!(f, " {delta}")?;
+        }
+        if let Some(time) = &self.start_time {
+            write!(f, " {time}")?;
+        }
+
+        // End
+        if self.end_delta.is_some() || self.end_time.is_some() {
+            write!(f, " --")?;
+            if let Some(delta) = &self.end_delta {
+                write!(f, " {delta}")?;
+            }
+            if let Some(time) = &self.end_time {
+                write!(f, " {time}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl fmt::Display for Spec {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Date(spec) => write!(f, "{spec}"),
             Self::Weekday(spec) => write!(f, "{spec}"),
             Self::Formula(spec) => write!(f, "{spec}"),
+            Self::After(spec) => write!(f, "{spec}"),
         }
     }
 }
@@ -218,11 +272,13 @@ impl fmt::Display for Statement {
         match self {
             Self::Date(spec) => writeln!(f, "DATE {spec}"),
             Self::BDate(spec) => writeln!(f, "BDATE {spec}"),
+            Self::Active { from, until } => writeln!(f, "ACTIVE {from} -- {until}"),
             Self::From(Some(date)) => writeln!(f, "FROM {date}"),
             Self::From(None) => writeln!(f, "FROM *"),
             Self::Until(Some(date)) => writeln!(f, "UNTIL {date}"),
             Self::Until(None) => writeln!(f, "UNTIL *"),
             Self::Except(date) => writeln!(f, "EXCEPT {date}"),
+            Self::ExceptExpr(expr) => writeln!(f, "EXCEPT ({expr})"),
             Self::Move {
                 from, to, to_time, ..
             } => match (to, to_time) {
@@ -231,8 +287,15 @@ impl fmt::Display for Statement {
                 (None, Some(to_time)) => writeln!(f, "MOVE {from} TO {to_time}"),
                 (Some(to), Some(to_time)) => writeln!(f, "MOVE {from} TO {to} {to_time}"),
             },
-            Self::Remind(Some(delta)) => writeln!(f, "REMIND {delta}"),
+            Self::Remind(Some(Remind::Delta(delta))) => writeln!(f, "REMIND {delta}"),
+            Self::Remind(Some(Remind::Date(date))) => writeln!(f, "REMIND on {date}"),
             Self::Remind(None) => writeln!(f, "REMIND *"),
+            Self::Created(date) => writeln!(f, "CREATED {date}"),
+            Self::Id(id) => writeln!(f, "ID {id}"),
+            Self::Tag(tags) => writeln!(f, "TAG {}", tags.join(" ")),
+            Self::Waiting(on) => writeln!(f, "WAITING {on}"),
+            Self::Progress(percent) => writeln!(f, "PROGRESS {percent}%"),
+            Self::Effort(effort) => writeln!(f, "EFFORT {effort}"),
         }
     }
 }
@@ -268,6 +331,9 @@ impl fmt::Display for Done {
         if let Some(date) = &self.date {
             write!(f, " {date}")?;
         }
+        if let Some(spent) = &self.spent {
+            write!(f, " spent {spent}")?;
+        }
         writeln!(f)
     }
 }
@@ -305,15 +371,26 @@ impl fmt::Display for Log {
     }
 }
 
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "TEMPLATE {}", self.name)?;
+        format_desc(f, &self.body)?;
+        Ok(())
+    }
+}
+
 impl fmt::Display for Command {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Include(name) => writeln!(f, "INCLUDE {name}"),
+            Self::Include(name) => format_include(f, &name.value),
             Self::Timezone(name) => writeln!(f, "TIMEZONE {name}"),
-            Self::Capture => writeln!(f, "CAPTURE"),
+            Self::Capture(None) => writeln!(f, "CAPTURE"),
+            Self::Capture(Some(kind)) => writeln!(f, "CAPTURE {}", kind.name()),
+            Self::FiscalYear(month) => writeln!(f, "FISCALYEAR {month}"),
             Self::Task(task) => write!(f, "{task}"),
             Self::Note(note) => write!(f, "{note}"),
             Self::Log(log) => write!(f, "{log}"),
+            Self::Template(template) => write!(f, "{template}"),
         }
     }
 }
@@ -324,13 +401,15 @@ impl File {
         // 1. Imports, sorted alphabetically
         // 2. Time zone(s)
         // 3. Captures
-        // 4. Log entries, sorted by date (ascending)
-        // 5. Tasks and notes, in original order
+        // 4. Fiscal year(s)
+        // 5. Log entries, sorted by date (ascending)
+        // 6. Templates, in original order
+        // 7. Tasks and notes, in original order
 
-        // There should always be at most one time zone, so we don't care about
-        // their order.
+        // There should always be at most one time zone and fiscal year, so we
+        // don't care about their order.
 
-        // In the individual steps we must use a stable sort so the order of 4.
+        // In the individual steps we must use a stable sort so the order of 5.
         // is not lost.
 
         // Order imports alphabetically
@@ -349,9 +428,11 @@ impl File {
         commands.sort_by_key(|c| match c {
             Command::Include(_) => 0,
             Command::Timezone(_) => 1,
-            Command::Capture => 2,
-            Command::Log(_) => 3,
-            Command::Task(_) | Command::Note(_) => 4,
+            Command::Capture(_) => 2,
+            Command::FiscalYear(_) => 3,
+            Command::Log(_) => 4,
+            Command::Template(_) => 5,
+            Command::Task(_) | Command::Note(_) => 6,
         });
     }
 
@@ -384,3 +465,152 @@ impl File {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::Path;
+
+    use super::super::commands::Command;
+    use super::super::parse::{self, TimesOfDay};
+    use super::super::primitives::{Time, TimeOfDay};
+
+    fn include_name(input: &str) -> String {
+        let file = parse::parse(Path::new("test.today"), input, &TimesOfDay::new()).unwrap();
+        match &file.commands[0].value {
+            Command::Include(name) => name.value.clone(),
+            _ => panic!("expected include"),
+        }
+    }
+
+    fn round_trip(input: &str) {
+        let file = parse::parse(Path::new("test.today"), input, &TimesOfDay::new()).unwrap();
+        assert_eq!(file.format(&HashSet::new()), input);
+    }
+
+    #[test]
+    fn include_quoted() {
+        assert_eq!(
+            include_name("INCLUDE \"my tasks.today\"\n"),
+            "my tasks.today"
+        );
+        assert_eq!(
+            include_name("INCLUDE \"a \\\"b\\\" \\\\c\"\n"),
+            "a \"b\" \\c"
+        );
+        assert_eq!(include_name("INCLUDE tasks.today\n"), "tasks.today");
+    }
+
+    #[test]
+    fn include_round_trip() {
+        round_trip("INCLUDE \"my tasks.today\"\n");
+        round_trip("INCLUDE \"a \\\"b\\\" \\\\c\"\n");
+        round_trip("INCLUDE tasks.today\n");
+    }
+
+    #[test]
+    fn anchored_date_round_trip() {
+        round_trip("NOTE Kickoff\nID kickoff\nDATE 2024-03-01\n");
+        round_trip("TASK Follow up\nDATE after kickoff\n");
+        round_trip("TASK Follow up\nDATE after kickoff +3d 10:00 -- 11:00\n");
+    }
+
+    #[test]
+    fn time_zone_round_trip() {
+        round_trip("NOTE Call\nDATE 2024-03-10 14:00 America/New_York\n");
+        round_trip("NOTE Call\nDATE 2024-03-10 14:00 Etc/GMT+5 -- 15:00; +w\n");
+    }
+
+    #[test]
+    fn truncating_operators_round_trip() {
+        round_trip("NOTE Test\nDATE (d quot 7 = 2)\n");
+        round_trip("NOTE Test\nDATE (-y rem 4 = 0 & y % 4 = 0)\n");
+    }
+
+    #[test]
+    fn effort_round_trip() {
+        round_trip("TASK Report\nEFFORT +2h30min\n");
+        round_trip("TASK Report\nDATE 2024-01-02\nDONE [2024-01-02] spent +2h\n");
+        round_trip("TASK Report\nDATE 2024-01-02\nDONE [2024-01-03] 2024-01-02 spent +45min\n");
+    }
+
+    #[test]
+    fn remind_round_trip() {
+        round_trip("TASK Taxes\nDATE 2024-05-31\nREMIND -2w\n");
+        round_trip("TASK Taxes\nDATE 2024-05-31\nREMIND on 2024-05-01\n");
+        round_trip("TASK Taxes\nDATE 2024-05-31\nREMIND *\n");
+    }
+
+    #[test]
+    fn capture_round_trip() {
+        round_trip("CAPTURE\n");
+        round_trip("CAPTURE tasks\n");
+        round_trip("CAPTURE notes\n");
+    }
+
+    #[test]
+    fn exclusive_end_round_trip() {
+        round_trip("NOTE Trip\nDATE 2024-03-04 --] 2024-03-08\n");
+        round_trip("NOTE Meeting\nDATE 2024-03-04 10:00 --] 11:00\n");
+        round_trip("TASK Sprint\nDATE 2024-03-04 --] +2w; +2w\n");
+    }
+
+    #[test]
+    fn time_of_day_canonicalized() {
+        let input = "TASK Run\nDATE 2024-01-01 morning -- night\n";
+        let times = TimesOfDay::from([(TimeOfDay::Morning, Time::new(8, 0))]);
+        let file = parse::parse(Path::new("test.today"), input, &times).unwrap();
+        assert_eq!(
+            file.format(&HashSet::new()),
+            "TASK Run\nDATE 2024-01-01 08:00 -- 21:00\n"
+        );
+    }
+
+    #[test]
+    fn nth_weekday_round_trip() {
+        round_trip("NOTE Meetup\nDATE mon #2 19:00\n");
+        round_trip("NOTE Review\nDATE fri #-1 -- 12:00\n");
+        assert!(parse::parse(
+            Path::new("test.today"),
+            "NOTE A\nDATE mon #6\n",
+            &TimesOfDay::new()
+        )
+        .is_err());
+        assert!(parse::parse(
+            Path::new("test.today"),
+            "NOTE A\nDATE mon #0\n",
+            &TimesOfDay::new()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn progress_round_trip() {
+        round_trip("TASK Thesis\nDATE 2024-01-15\nPROGRESS 60%\n");
+        round_trip("TASK Thesis\nPROGRESS 0%\n");
+        assert!(parse::parse(
+            Path::new("test.today"),
+            "TASK A\nPROGRESS 101%\n",
+            &TimesOfDay::new()
+        )
+        .is_err());
+        assert!(parse::parse(
+            Path::new("test.today"),
+            "NOTE A\nPROGRESS 50%\n",
+            &TimesOfDay::new()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn weekday_set_round_trip() {
+        round_trip("NOTE Gym\nDATE mon,thu 18:00\n");
+        round_trip("NOTE Gym\nDATE mon,wed,fri (m < 4) 18:00 -- 19:30\n");
+        round_trip("NOTE Meetup\nDATE tue,thu #1 (y = 2024)\n");
+    }
+
+    #[test]
+    fn template_round_trip() {
+        round_trip("TEMPLATE review\n# TASK Weekly review\n# DATE today\n#\n# # Inbox\n");
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::Path;
 use std::result;
 
@@ -8,10 +9,11 @@ use pest::pratt_parser::{Assoc, Op, PrattParser};
 use pest::{Parser, Span};
 
 use super::commands::{
-    BirthdaySpec, Command, DateSpec, Delta, DeltaStep, Done, DoneDate, DoneKind, Expr, File,
-    FormulaSpec, Log, Note, Repeat, Spec, Statement, Task, Var, WeekdaySpec,
+    AfterSpec, BirthdaySpec, CaptureKind, Command, DateSpec, Delta, DeltaStep, Done, DoneDate,
+    DoneKind, Expr, File, FormulaSpec, Log, Note, Remind, Repeat, Spec, Statement, Task, Template,
+    Var, WeekdaySpec,
 };
-use super::primitives::{Spanned, Time, Weekday};
+use super::primitives::{Spanned, Time, TimeOfDay, Weekday};
 
 #[derive(pest_derive::Parser)]
 #[grammar = "files/grammar.pest"]
@@ -20,6 +22,10 @@ pub struct TodayfileParser;
 pub type Error = pest::error::Error<Rule>;
 pub type Result<T> = result::Result<T, Box<Error>>;
 
+/// Times of day overridden in the config. All others resolve to their
+/// [`TimeOfDay::default_time`].
+pub type TimesOfDay = HashMap<TimeOfDay, Time>;
+
 fn error<S: Into<String>>(span: Span<'_>, message: S) -> Error {
     Error::new_from_span(
         ErrorVariant::CustomError {
@@ -33,11 +39,32 @@ fn fail<S: Into<String>, T>(span: Span<'_>, message: S) -> Result<T> {
     Err(Box::new(error(span, message)))
 }
 
+fn parse_include_quoted(p: Pair<'_, Rule>) -> String {
+    assert_eq!(p.as_rule(), Rule::include_quoted);
+    let quoted = p.as_str();
+    let mut name = String::new();
+    let mut chars = quoted[1..quoted.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            // The grammar guarantees that a backslash is always followed by
+            // another character.
+            name.push(chars.next().unwrap());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
 fn parse_include(p: Pair<'_, Rule>) -> Spanned<String> {
     assert_eq!(p.as_rule(), Rule::include);
     let p = p.into_inner().next().unwrap();
     let span = (&p.as_span()).into();
-    let name = p.as_str().to_string();
+    let name = match p.as_rule() {
+        Rule::include_quoted => parse_include_quoted(p),
+        Rule::rest_some => p.as_str().to_string(),
+        _ => unreachable!(),
+    };
     Spanned::new(span, name)
 }
 
@@ -49,6 +76,29 @@ fn parse_timezone(p: Pair<'_, Rule>) -> Spanned<String> {
     Spanned::new(span, name)
 }
 
+fn parse_capture(p: Pair<'_, Rule>) -> Option<CaptureKind> {
+    assert_eq!(p.as_rule(), Rule::capture);
+    let p = p.into_inner().next()?;
+    assert_eq!(p.as_rule(), Rule::capture_kind);
+    match p.as_str() {
+        "tasks" => Some(CaptureKind::Tasks),
+        "notes" => Some(CaptureKind::Notes),
+        _ => unreachable!(),
+    }
+}
+
+fn parse_fiscalyear(p: Pair<'_, Rule>) -> Result<Spanned<u32>> {
+    assert_eq!(p.as_rule(), Rule::fiscalyear);
+    let p = p.into_inner().next().unwrap();
+    let pspan = p.as_span();
+    let span = (&pspan).into();
+    let month = parse_number(p);
+    if !(1..=12).contains(&month) {
+        return fail(pspan, "invalid month");
+    }
+    Ok(Spanned::new(span, month as u32))
+}
+
 pub fn parse_number(p: Pair<'_, Rule>) -> i32 {
     assert_eq!(p.as_rule(), Rule::number);
     p.as_str().parse().unwrap()
@@ -68,19 +118,27 @@ pub fn parse_datum(p: Pair<'_, Rule>) -> Result<Spanned<NaiveDate>> {
     let mut p = p.into_inner();
 
     let year = p.next().unwrap().as_str().parse().unwrap();
-    let month = p.next().unwrap().as_str().parse().unwrap();
-    let day = p.next().unwrap().as_str().parse().unwrap();
+    let p2 = p.next().unwrap();
+    let date = match p2.as_rule() {
+        Rule::ordinal => NaiveDate::from_yo_opt(year, p2.as_str().parse().unwrap()),
+        Rule::month => {
+            let month = p2.as_str().parse().unwrap();
+            let day = p.next().unwrap().as_str().parse().unwrap();
+            NaiveDate::from_ymd_opt(year, month, day)
+        }
+        _ => unreachable!(),
+    };
 
     assert_eq!(p.next(), None);
 
-    match NaiveDate::from_ymd_opt(year, month, day) {
+    match date {
         Some(date) => Ok(Spanned::new(span, date)),
         None => fail(pspan, "invalid date"),
     }
 }
 
-fn parse_time(p: Pair<'_, Rule>) -> Result<Spanned<Time>> {
-    assert_eq!(p.as_rule(), Rule::time);
+fn parse_clock(p: Pair<'_, Rule>) -> Result<Spanned<Time>> {
+    assert_eq!(p.as_rule(), Rule::clock);
     let pspan = p.as_span();
     let span = (&pspan).into();
     let mut p = p.into_inner();
@@ -98,6 +156,37 @@ fn parse_time(p: Pair<'_, Rule>) -> Result<Spanned<Time>> {
     }
 }
 
+fn parse_time_of_day(p: Pair<'_, Rule>) -> Spanned<TimeOfDay> {
+    assert_eq!(p.as_rule(), Rule::time_of_day);
+    let span = (&p.as_span()).into();
+    let tod = match p.as_str() {
+        "morning" => TimeOfDay::Morning,
+        "noon" => TimeOfDay::Noon,
+        "afternoon" => TimeOfDay::Afternoon,
+        "evening" => TimeOfDay::Evening,
+        "night" => TimeOfDay::Night,
+        _ => unreachable!(),
+    };
+    Spanned::new(span, tod)
+}
+
+fn parse_time(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<Spanned<Time>> {
+    assert_eq!(p.as_rule(), Rule::time);
+    let p = p.into_inner().next().unwrap();
+    match p.as_rule() {
+        Rule::clock => parse_clock(p),
+        Rule::time_of_day => {
+            let tod = parse_time_of_day(p);
+            let time = times
+                .get(&tod.value)
+                .copied()
+                .unwrap_or_else(|| tod.value.default_time());
+            Ok(Spanned::new(tod.span, time))
+        }
+        _ => unreachable!(),
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum Sign {
     Positive,
@@ -194,6 +283,7 @@ fn parse_delta_step(
             | Rule::delta_months_reverse
             | Rule::delta_days
             | Rule::delta_weeks
+            | Rule::delta_business_days
             | Rule::delta_hours
             | Rule::delta_minutes
     ));
@@ -227,6 +317,9 @@ pub fn parse_delta(p: Pair<'_, Rule>) -> Result<Spanned<Delta>> {
             }
             Rule::delta_days => steps.push(parse_delta_step(p, &mut sign, DeltaStep::Day)?),
             Rule::delta_weeks => steps.push(parse_delta_step(p, &mut sign, DeltaStep::Week)?),
+            Rule::delta_business_days => {
+                steps.push(parse_delta_step(p, &mut sign, DeltaStep::BusinessDay)?)
+            }
             Rule::delta_hours => steps.push(parse_delta_step(p, &mut sign, DeltaStep::Hour)?),
             _ => unreachable!(),
         }
@@ -235,14 +328,32 @@ pub fn parse_delta(p: Pair<'_, Rule>) -> Result<Spanned<Delta>> {
     Ok(Spanned::new(span, Delta(steps)))
 }
 
-fn parse_date_fixed_start(p: Pair<'_, Rule>, spec: &mut DateSpec) -> Result<()> {
+/// Parse a delta that only consists of hours and minutes, like `+1h30min`.
+fn parse_duration(p: Pair<'_, Rule>) -> Result<Delta> {
+    let span = p.as_span();
+    let delta = parse_delta(p)?.value;
+    match delta.minutes() {
+        Some(minutes) if minutes >= 0 => Ok(delta),
+        Some(_) => fail(span, "duration must not be negative"),
+        None => fail(span, "duration must only consist of hours and minutes"),
+    }
+}
+
+fn parse_date_fixed_start(
+    p: Pair<'_, Rule>,
+    spec: &mut DateSpec,
+    times: &TimesOfDay,
+) -> Result<()> {
     assert_eq!(p.as_rule(), Rule::date_fixed_start);
 
     for p in p.into_inner() {
         match p.as_rule() {
             Rule::datum => spec.start = parse_datum(p)?.value,
             Rule::delta => spec.start_delta = Some(parse_delta(p)?.value),
-            Rule::time => spec.start_time = Some(parse_time(p)?.value),
+            Rule::time => spec.start_time = Some(parse_time(p, times)?.value),
+            Rule::time_zone => {
+                spec.start_zone = Some(Spanned::new((&p.as_span()).into(), p.as_str().to_string()))
+            }
             _ => unreachable!(),
         }
     }
@@ -250,14 +361,14 @@ fn parse_date_fixed_start(p: Pair<'_, Rule>, spec: &mut DateSpec) -> Result<()>
     Ok(())
 }
 
-fn parse_date_fixed_end(p: Pair<'_, Rule>, spec: &mut DateSpec) -> Result<()> {
+fn parse_date_fixed_end(p: Pair<'_, Rule>, spec: &mut DateSpec, times: &TimesOfDay) -> Result<()> {
     assert_eq!(p.as_rule(), Rule::date_fixed_end);
 
     for p in p.into_inner() {
         match p.as_rule() {
             Rule::datum => spec.end = Some(parse_datum(p)?),
             Rule::delta => spec.end_delta = Some(parse_delta(p)?.value),
-            Rule::time => spec.end_time = Some(parse_time(p)?),
+            Rule::time => spec.end_time = Some(parse_time(p, times)?),
             _ => unreachable!(),
         }
     }
@@ -289,23 +400,26 @@ fn parse_date_fixed_repeat(p: Pair<'_, Rule>, spec: &mut DateSpec) -> Result<()>
     Ok(())
 }
 
-fn parse_date_fixed(p: Pair<'_, Rule>) -> Result<DateSpec> {
+fn parse_date_fixed(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<DateSpec> {
     assert_eq!(p.as_rule(), Rule::date_fixed);
 
     let mut spec = DateSpec {
         start: NaiveDate::from_ymd_opt(0, 1, 1).unwrap(),
         start_delta: None,
         start_time: None,
+        start_zone: None,
         end: None,
         end_delta: None,
         end_time: None,
+        end_exclusive: None,
         repeat: None,
     };
 
     for p in p.into_inner() {
         match p.as_rule() {
-            Rule::date_fixed_start => parse_date_fixed_start(p, &mut spec)?,
-            Rule::date_fixed_end => parse_date_fixed_end(p, &mut spec)?,
+            Rule::date_fixed_start => parse_date_fixed_start(p, &mut spec, times)?,
+            Rule::date_fixed_exclusive => spec.end_exclusive = Some((&p.as_span()).into()),
+            Rule::date_fixed_end => parse_date_fixed_end(p, &mut spec, times)?,
             Rule::date_fixed_repeat => parse_date_fixed_repeat(p, &mut spec)?,
             _ => unreachable!(),
         }
@@ -342,8 +456,15 @@ fn parse_variable(p: Pair<'_, Rule>) -> Var {
         "iy" => Var::IsoYear,
         "iyl" => Var::IsoYearLength,
         "iw" => Var::IsoWeek,
+        "iyw" => Var::IsoYearWeek,
+        "imw" => Var::IsoMonthWeek,
+        "fy" => Var::FiscalYear,
+        "fyd" => Var::FiscalYearDay,
         "wd" => Var::Weekday,
         "e" => Var::Easter,
+        "eNext" => Var::EasterNext,
+        "ePrev" => Var::EasterPrev,
+        "daysToEaster" => Var::DaysToEaster,
         "mon" => Var::Monday,
         "tue" => Var::Tuesday,
         "wed" => Var::Wednesday,
@@ -359,7 +480,7 @@ fn parse_variable(p: Pair<'_, Rule>) -> Var {
     }
 }
 
-fn parse_paren_expr(p: Pair<'_, Rule>) -> Spanned<Expr> {
+pub fn parse_paren_expr(p: Pair<'_, Rule>) -> Spanned<Expr> {
     assert_eq!(p.as_rule(), Rule::paren_expr);
     let span = (&p.as_span()).into();
     let inner = parse_expr(p.into_inner().next().unwrap());
@@ -398,6 +519,8 @@ fn parse_infix(l: Spanned<Expr>, p: Pair<'_, Rule>, r: Spanned<Expr>) -> Spanned
         Rule::infix_mul => Expr::Mul(Box::new(l), Box::new(r)),
         Rule::infix_div => Expr::Div(Box::new(l), Box::new(r)),
         Rule::infix_mod => Expr::Mod(Box::new(l), Box::new(r)),
+        Rule::infix_quot => Expr::Quot(Box::new(l), Box::new(r)),
+        Rule::infix_rem => Expr::Rem(Box::new(l), Box::new(r)),
 
         // Comparisons
         Rule::infix_eq => Expr::Eq(Box::new(l), Box::new(r)),
@@ -430,7 +553,9 @@ fn parse_expr(p: Pair<'_, Rule>) -> Spanned<Expr> {
             | Op::infix(Rule::infix_gte, Assoc::Left))
         .op(Op::infix(Rule::infix_mul, Assoc::Left)
             | Op::infix(Rule::infix_div, Assoc::Left)
-            | Op::infix(Rule::infix_mod, Assoc::Left))
+            | Op::infix(Rule::infix_mod, Assoc::Left)
+            | Op::infix(Rule::infix_quot, Assoc::Left)
+            | Op::infix(Rule::infix_rem, Assoc::Left))
         .op(Op::infix(Rule::infix_add, Assoc::Left) | Op::infix(Rule::infix_sub, Assoc::Left))
         .op(Op::prefix(Rule::prefix_neg) | Op::prefix(Rule::prefix_not))
         .map_primary(parse_term)
@@ -439,14 +564,18 @@ fn parse_expr(p: Pair<'_, Rule>) -> Spanned<Expr> {
         .parse(p.into_inner())
 }
 
-fn parse_date_expr_start(p: Pair<'_, Rule>, spec: &mut FormulaSpec) -> Result<()> {
+fn parse_date_expr_start(
+    p: Pair<'_, Rule>,
+    spec: &mut FormulaSpec,
+    times: &TimesOfDay,
+) -> Result<()> {
     assert_eq!(p.as_rule(), Rule::date_expr_start);
 
     for p in p.into_inner() {
         match p.as_rule() {
             Rule::paren_expr => spec.start = Some(parse_expr(p.into_inner().next().unwrap())),
             Rule::delta => spec.start_delta = Some(parse_delta(p)?.value),
-            Rule::time => spec.start_time = Some(parse_time(p)?.value),
+            Rule::time => spec.start_time = Some(parse_time(p, times)?.value),
             _ => unreachable!(),
         }
     }
@@ -454,13 +583,17 @@ fn parse_date_expr_start(p: Pair<'_, Rule>, spec: &mut FormulaSpec) -> Result<()
     Ok(())
 }
 
-fn parse_date_expr_end(p: Pair<'_, Rule>, spec: &mut FormulaSpec) -> Result<()> {
+fn parse_date_expr_end(
+    p: Pair<'_, Rule>,
+    spec: &mut FormulaSpec,
+    times: &TimesOfDay,
+) -> Result<()> {
     assert_eq!(p.as_rule(), Rule::date_expr_end);
 
     for p in p.into_inner() {
         match p.as_rule() {
             Rule::delta => spec.end_delta = Some(parse_delta(p)?.value),
-            Rule::time => spec.end_time = Some(parse_time(p)?),
+            Rule::time => spec.end_time = Some(parse_time(p, times)?),
             _ => unreachable!(),
         }
     }
@@ -468,7 +601,7 @@ fn parse_date_expr_end(p: Pair<'_, Rule>, spec: &mut FormulaSpec) -> Result<()>
     Ok(())
 }
 
-fn parse_date_expr(p: Pair<'_, Rule>) -> Result<FormulaSpec> {
+fn parse_date_expr(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<FormulaSpec> {
     assert_eq!(p.as_rule(), Rule::date_expr);
 
     let mut spec = FormulaSpec {
@@ -481,8 +614,8 @@ fn parse_date_expr(p: Pair<'_, Rule>) -> Result<FormulaSpec> {
 
     for p in p.into_inner() {
         match p.as_rule() {
-            Rule::date_expr_start => parse_date_expr_start(p, &mut spec)?,
-            Rule::date_expr_end => parse_date_expr_end(p, &mut spec)?,
+            Rule::date_expr_start => parse_date_expr_start(p, &mut spec, times)?,
+            Rule::date_expr_end => parse_date_expr_end(p, &mut spec, times)?,
             _ => unreachable!(),
         }
     }
@@ -490,28 +623,56 @@ fn parse_date_expr(p: Pair<'_, Rule>) -> Result<FormulaSpec> {
     Ok(spec)
 }
 
-fn parse_date_weekday_start(p: Pair<'_, Rule>, spec: &mut WeekdaySpec) -> Result<()> {
+fn parse_weekday_nth(p: Pair<'_, Rule>) -> Result<i32> {
+    assert_eq!(p.as_rule(), Rule::weekday_nth);
+    let nth: i32 = p.as_str()[1..].parse().unwrap();
+    if (1..=5).contains(&nth.abs()) {
+        Ok(nth)
+    } else {
+        fail(
+            p.as_span(),
+            "weekday occurrence must be #1 to #5 or #-1 to #-5",
+        )
+    }
+}
+
+fn parse_date_weekday_start(
+    p: Pair<'_, Rule>,
+    spec: &mut WeekdaySpec,
+    times: &TimesOfDay,
+) -> Result<()> {
     assert_eq!(p.as_rule(), Rule::date_weekday_start);
 
+    let mut weekdays = vec![];
     for p in p.into_inner() {
         match p.as_rule() {
-            Rule::weekday => spec.start = parse_weekday(p).value,
-            Rule::time => spec.start_time = Some(parse_time(p)?.value),
+            Rule::weekday => weekdays.push(parse_weekday(p).value),
+            Rule::weekday_nth => spec.nth = Some(parse_weekday_nth(p)?),
+            Rule::paren_expr => spec.filter = Some(parse_expr(p.into_inner().next().unwrap())),
+            Rule::time => spec.start_time = Some(parse_time(p, times)?.value),
             _ => unreachable!(),
         }
     }
 
+    // The grammar guarantees at least one weekday
+    spec.start = weekdays.remove(0);
+    spec.others = weekdays;
+
     Ok(())
 }
 
-fn parse_date_weekday_end(p: Pair<'_, Rule>, spec: &mut WeekdaySpec) -> Result<()> {
+fn parse_date_weekday_end(
+    p: Pair<'_, Rule>,
+    spec: &mut WeekdaySpec,
+    times: &TimesOfDay,
+) -> Result<()> {
     assert_eq!(p.as_rule(), Rule::date_weekday_end);
 
     for p in p.into_inner() {
         match p.as_rule() {
             Rule::weekday => spec.end = Some(parse_weekday(p)),
             Rule::delta => spec.end_delta = Some(parse_delta(p)?.value),
-            Rule::time => spec.end_time = Some(parse_time(p)?),
+            Rule::time => spec.end_time = Some(parse_time(p, times)?),
             _ => unreachable!(),
         }
     }
@@ -519,11 +680,14 @@ fn parse_date_weekday_end(p: Pair<'_, Rule>, spec: &mut WeekdaySpec) -> Result<(
     Ok(())
 }
 
-fn parse_date_weekday(p: Pair<'_, Rule>) -> Result<WeekdaySpec> {
+fn parse_date_weekday(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<WeekdaySpec> {
     assert_eq!(p.as_rule(), Rule::date_weekday);
 
     let mut spec = WeekdaySpec {
         start: Weekday::Monday,
+        others: vec![],
+        nth: None,
+        filter: None,
         start_time: None,
         end: None,
         end_delta: None,
@@ -532,8 +696,63 @@ fn parse_date_weekday(p: Pair<'_, Rule>) -> Result<WeekdaySpec> {
 
     for p in p.into_inner() {
         match p.as_rule() {
-            Rule::date_weekday_start => parse_date_weekday_start(p, &mut spec)?,
-            Rule::date_weekday_end => parse_date_weekday_end(p, &mut spec)?,
+            Rule::date_weekday_start => parse_date_weekday_start(p, &mut spec, times)?,
+            Rule::date_weekday_end => parse_date_weekday_end(p, &mut spec, times)?,
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(spec)
+}
+
+fn parse_date_after_start(
+    p: Pair<'_, Rule>,
+    spec: &mut AfterSpec,
+    times: &TimesOfDay,
+) -> Result<()> {
+    assert_eq!(p.as_rule(), Rule::date_after_start);
+
+    for p in p.into_inner() {
+        match p.as_rule() {
+            Rule::tag => spec.id = Spanned::new((&p.as_span()).into(), p.as_str().to_string()),
+            Rule::delta => spec.start_delta = Some(parse_delta(p)?.value),
+            Rule::time => spec.start_time = Some(parse_time(p, times)?.value),
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_date_after_end(p: Pair<'_, Rule>, spec: &mut AfterSpec, times: &TimesOfDay) -> Result<()> {
+    assert_eq!(p.as_rule(), Rule::date_after_end);
+
+    for p in p.into_inner() {
+        match p.as_rule() {
+            Rule::delta => spec.end_delta = Some(parse_delta(p)?.value),
+            Rule::time => spec.end_time = Some(parse_time(p, times)?),
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_date_after(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<AfterSpec> {
+    assert_eq!(p.as_rule(), Rule::date_after);
+
+    let mut spec = AfterSpec {
+        id: Spanned::dummy(String::new()),
+        start_delta: None,
+        start_time: None,
+        end_delta: None,
+        end_time: None,
+    };
+
+    for p in p.into_inner() {
+        match p.as_rule() {
+            Rule::date_after_start => parse_date_after_start(p, &mut spec, times)?,
+            Rule::date_after_end => parse_date_after_end(p, &mut spec, times)?,
             _ => unreachable!(),
         }
     }
@@ -541,16 +760,18 @@ fn parse_date_weekday(p: Pair<'_, Rule>) -> Result<WeekdaySpec> {
     Ok(spec)
 }
 
-fn parse_stmt_date(p: Pair<'_, Rule>) -> Result<Statement> {
+fn parse_stmt_date(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<Statement> {
     assert_eq!(p.as_rule(), Rule::stmt_date);
+    let span = (&p.as_span()).into();
     let p = p.into_inner().next().unwrap();
     let spec = match p.as_rule() {
-        Rule::date_fixed => Spec::Date(parse_date_fixed(p)?),
-        Rule::date_expr => Spec::Formula(parse_date_expr(p)?),
-        Rule::date_weekday => Spec::Weekday(parse_date_weekday(p)?),
+        Rule::date_fixed => Spec::Date(parse_date_fixed(p, times)?),
+        Rule::date_expr => Spec::Formula(parse_date_expr(p, times)?),
+        Rule::date_weekday => Spec::Weekday(parse_date_weekday(p, times)?),
+        Rule::date_after => Spec::After(parse_date_after(p, times)?),
         _ => unreachable!(),
     };
-    Ok(Statement::Date(spec))
+    Ok(Statement::Date(Spanned::new(span, spec)))
 }
 
 fn parse_bdatum(p: Pair<'_, Rule>) -> Result<BirthdaySpec> {
@@ -606,13 +827,40 @@ fn parse_stmt_until(p: Pair<'_, Rule>) -> Result<Statement> {
     Ok(Statement::Until(datum))
 }
 
+fn parse_stmt_active(p: Pair<'_, Rule>) -> Result<Statement> {
+    assert_eq!(p.as_rule(), Rule::stmt_active);
+    let span = p.as_span();
+    let mut p = p.into_inner();
+    let from = parse_datum(p.next().unwrap())?.value;
+    let until = parse_datum(p.next().unwrap())?.value;
+    assert_eq!(p.next(), None);
+    if until < from {
+        return fail(span, "ACTIVE must not end before it starts");
+    }
+    Ok(Statement::Active { from, until })
+}
+
 fn parse_stmt_except(p: Pair<'_, Rule>) -> Result<Statement> {
     assert_eq!(p.as_rule(), Rule::stmt_except);
-    let datum = parse_datum(p.into_inner().next().unwrap())?.value;
-    Ok(Statement::Except(datum))
+    let p = p.into_inner().next().unwrap();
+    match p.as_rule() {
+        Rule::datum => Ok(Statement::Except(parse_datum(p)?.value)),
+        Rule::paren_expr => Ok(Statement::ExceptExpr(parse_expr(
+            p.into_inner().next().unwrap(),
+        ))),
+        _ => unreachable!(),
+    }
+}
+
+fn parse_stmt_skip(p: Pair<'_, Rule>) -> Result<Statement> {
+    assert_eq!(p.as_rule(), Rule::stmt_skip);
+    fail(
+        p.as_span(),
+        "SKIP is not supported, use EXCEPT (expr) instead",
+    )
 }
 
-fn parse_stmt_move(p: Pair<'_, Rule>) -> Result<Statement> {
+fn parse_stmt_move(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<Statement> {
     assert_eq!(p.as_rule(), Rule::stmt_move);
     let span = (&p.as_span()).into();
     let mut p = p.into_inner();
@@ -623,7 +871,7 @@ fn parse_stmt_move(p: Pair<'_, Rule>) -> Result<Statement> {
     for p in p {
         match p.as_rule() {
             Rule::datum => to = Some(parse_datum(p)?.value),
-            Rule::time => to_time = Some(parse_time(p)?),
+            Rule::time => to_time = Some(parse_time(p, times)?),
             _ => unreachable!(),
         }
     }
@@ -639,34 +887,87 @@ fn parse_stmt_move(p: Pair<'_, Rule>) -> Result<Statement> {
 fn parse_stmt_remind(p: Pair<'_, Rule>) -> Result<Statement> {
     assert_eq!(p.as_rule(), Rule::stmt_remind);
     let mut p = p.into_inner();
-    let delta = match p.next() {
-        Some(p) => Some(parse_delta(p)?),
+    let remind = match p.next() {
+        Some(p) if p.as_rule() == Rule::datum => Some(Remind::Date(parse_datum(p)?)),
+        Some(p) => Some(Remind::Delta(parse_delta(p)?)),
         None => None,
     };
     assert_eq!(p.next(), None);
-    Ok(Statement::Remind(delta))
+    Ok(Statement::Remind(remind))
+}
+
+fn parse_stmt_created(p: Pair<'_, Rule>) -> Result<Statement> {
+    assert_eq!(p.as_rule(), Rule::stmt_created);
+    let p = p.into_inner().next().unwrap();
+    Ok(Statement::Created(parse_datum(p)?.value))
+}
+
+fn parse_stmt_id(p: Pair<'_, Rule>) -> Statement {
+    assert_eq!(p.as_rule(), Rule::stmt_id);
+    let p = p.into_inner().next().unwrap();
+    Statement::Id(p.as_str().to_string())
+}
+
+fn parse_stmt_tag(p: Pair<'_, Rule>) -> Statement {
+    assert_eq!(p.as_rule(), Rule::stmt_tag);
+    let tags = p.into_inner().map(|p| p.as_str().to_string()).collect();
+    Statement::Tag(tags)
+}
+
+fn parse_stmt_waiting(p: Pair<'_, Rule>) -> Statement {
+    assert_eq!(p.as_rule(), Rule::stmt_waiting);
+    let p = p.into_inner().next().unwrap();
+    Statement::Waiting(p.as_str().trim().to_string())
+}
+
+fn parse_stmt_progress(p: Pair<'_, Rule>) -> Result<Statement> {
+    assert_eq!(p.as_rule(), Rule::stmt_progress);
+    let p = p.into_inner().next().unwrap();
+    let span = p.as_span();
+    match parse_number(p) {
+        n @ 0..=100 => Ok(Statement::Progress(n as u8)),
+        _ => fail(span, "progress must be between 0% and 100%"),
+    }
+}
+
+fn parse_stmt_effort(p: Pair<'_, Rule>) -> Result<Statement> {
+    assert_eq!(p.as_rule(), Rule::stmt_effort);
+    let p = p.into_inner().next().unwrap();
+    Ok(Statement::Effort(parse_duration(p)?))
 }
 
-fn parse_statements(p: Pair<'_, Rule>, task: bool) -> Result<Vec<Statement>> {
+fn parse_statements(p: Pair<'_, Rule>, task: bool, times: &TimesOfDay) -> Result<Vec<Statement>> {
     assert_eq!(p.as_rule(), Rule::statements);
     let mut statements = vec![];
     for p in p.into_inner() {
         statements.push(match p.as_rule() {
-            Rule::stmt_date => parse_stmt_date(p)?,
+            Rule::stmt_date => parse_stmt_date(p, times)?,
             Rule::stmt_bdate if task => fail(p.as_span(), "BDATE not allowed in TASKs")?,
             Rule::stmt_bdate => parse_stmt_bdate(p)?,
+            Rule::stmt_active if task => fail(p.as_span(), "ACTIVE not allowed in TASKs")?,
+            Rule::stmt_active => parse_stmt_active(p)?,
             Rule::stmt_from => parse_stmt_from(p)?,
             Rule::stmt_until => parse_stmt_until(p)?,
             Rule::stmt_except => parse_stmt_except(p)?,
-            Rule::stmt_move => parse_stmt_move(p)?,
+            Rule::stmt_skip => parse_stmt_skip(p)?,
+            Rule::stmt_move => parse_stmt_move(p, times)?,
             Rule::stmt_remind => parse_stmt_remind(p)?,
+            Rule::stmt_created => parse_stmt_created(p)?,
+            Rule::stmt_id => parse_stmt_id(p),
+            Rule::stmt_tag => parse_stmt_tag(p),
+            Rule::stmt_waiting if !task => fail(p.as_span(), "WAITING not allowed in NOTEs")?,
+            Rule::stmt_waiting => parse_stmt_waiting(p),
+            Rule::stmt_progress if !task => fail(p.as_span(), "PROGRESS not allowed in NOTEs")?,
+            Rule::stmt_progress => parse_stmt_progress(p)?,
+            Rule::stmt_effort if !task => fail(p.as_span(), "EFFORT not allowed in NOTEs")?,
+            Rule::stmt_effort => parse_stmt_effort(p)?,
             _ => unreachable!(),
         });
     }
     Ok(statements)
 }
 
-fn parse_donedate(p: Pair<'_, Rule>) -> Result<DoneDate> {
+fn parse_donedate(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<DoneDate> {
     assert_eq!(p.as_rule(), Rule::donedate);
     let mut ps = p.into_inner().collect::<Vec<_>>();
 
@@ -679,7 +980,7 @@ fn parse_donedate(p: Pair<'_, Rule>) -> Result<DoneDate> {
         },
         2 => match ps[1].as_rule() {
             Rule::time => DoneDate::DateTime {
-                root_time: parse_time(ps.pop().unwrap())?.value,
+                root_time: parse_time(ps.pop().unwrap(), times)?.value,
                 root: parse_datum(ps.pop().unwrap())?.value,
             },
             Rule::datum => DoneDate::DateToDate {
@@ -689,14 +990,14 @@ fn parse_donedate(p: Pair<'_, Rule>) -> Result<DoneDate> {
             _ => unreachable!(),
         },
         3 => DoneDate::DateTimeToTime {
-            other_time: parse_time(ps.pop().unwrap())?.value,
-            root_time: parse_time(ps.pop().unwrap())?.value,
+            other_time: parse_time(ps.pop().unwrap(), times)?.value,
+            root_time: parse_time(ps.pop().unwrap(), times)?.value,
             root: parse_datum(ps.pop().unwrap())?.value,
         },
         4 => DoneDate::DateTimeToDateTime {
-            other_time: parse_time(ps.pop().unwrap())?.value,
+            other_time: parse_time(ps.pop().unwrap(), times)?.value,
             other: parse_datum(ps.pop().unwrap())?.value,
-            root_time: parse_time(ps.pop().unwrap())?.value,
+            root_time: parse_time(ps.pop().unwrap(), times)?.value,
             root: parse_datum(ps.pop().unwrap())?.value,
         },
         _ => unreachable!(),
@@ -712,32 +1013,44 @@ fn parse_done_kind(p: Pair<'_, Rule>) -> DoneKind {
     }
 }
 
-fn parse_done(p: Pair<'_, Rule>) -> Result<Done> {
+fn parse_done(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<Done> {
     assert_eq!(p.as_rule(), Rule::done);
     let mut p = p.into_inner();
 
     let kind = parse_done_kind(p.next().unwrap());
-    let done_at = parse_datum(p.next().unwrap())?.value;
-    let date = if let Some(p) = p.next() {
-        Some(parse_donedate(p)?)
-    } else {
-        None
-    };
 
-    assert_eq!(p.next(), None);
+    let p_done_at = p.next().unwrap();
+    if p_done_at.as_rule() == Rule::done_at_unbracketed {
+        return fail(
+            p_done_at.as_span(),
+            "completion date must be in brackets, \
+             the syntax is `DONE [completion-date] [occurrence]`",
+        );
+    }
+    let done_at = parse_datum(p_done_at)?.value;
+    let mut date = None;
+    let mut spent = None;
+    for p in p {
+        match p.as_rule() {
+            Rule::donedate => date = Some(parse_donedate(p, times)?),
+            Rule::done_spent => spent = Some(parse_duration(p.into_inner().next().unwrap())?),
+            _ => unreachable!(),
+        }
+    }
 
     Ok(Done {
         kind,
         date,
         done_at,
+        spent,
     })
 }
 
-fn parse_dones(p: Pair<'_, Rule>) -> Result<Vec<Done>> {
+fn parse_dones(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<Vec<Done>> {
     assert_eq!(p.as_rule(), Rule::dones);
     let mut dones = vec![];
     for p in p.into_inner() {
-        dones.push(parse_done(p)?);
+        dones.push(parse_done(p, times)?);
     }
     Ok(dones)
 }
@@ -758,13 +1071,13 @@ fn parse_description(p: Pair<'_, Rule>) -> Result<Vec<String>> {
     p.into_inner().map(parse_desc_line).collect()
 }
 
-fn parse_task(p: Pair<'_, Rule>) -> Result<Task> {
+fn parse_task(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<Task> {
     assert_eq!(p.as_rule(), Rule::task);
     let mut p = p.into_inner();
 
     let title = parse_title(p.next().unwrap());
-    let statements = parse_statements(p.next().unwrap(), true)?;
-    let done = parse_dones(p.next().unwrap())?;
+    let statements = parse_statements(p.next().unwrap(), true, times)?;
+    let done = parse_dones(p.next().unwrap(), times)?;
     let desc = parse_description(p.next().unwrap())?;
 
     assert_eq!(p.next(), None);
@@ -777,12 +1090,12 @@ fn parse_task(p: Pair<'_, Rule>) -> Result<Task> {
     })
 }
 
-fn parse_note(p: Pair<'_, Rule>) -> Result<Note> {
+fn parse_note(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<Note> {
     assert_eq!(p.as_rule(), Rule::note);
     let mut p = p.into_inner();
 
     let title = parse_title(p.next().unwrap());
-    let statements = parse_statements(p.next().unwrap(), false)?;
+    let statements = parse_statements(p.next().unwrap(), false, times)?;
     let desc = parse_description(p.next().unwrap())?;
 
     assert_eq!(p.next(), None);
@@ -811,7 +1124,22 @@ fn parse_log(p: Pair<'_, Rule>) -> Result<Log> {
     Ok(Log { date, desc })
 }
 
-pub fn parse_command(p: Pair<'_, Rule>) -> Result<Spanned<Command>> {
+fn parse_template(p: Pair<'_, Rule>) -> Result<Template> {
+    assert_eq!(p.as_rule(), Rule::template);
+    let mut p = p.into_inner();
+
+    let head = p.next().unwrap();
+    assert_eq!(head.as_rule(), Rule::template_head);
+    let name = head.into_inner().next().unwrap();
+    let name = Spanned::new((&name.as_span()).into(), name.as_str().to_string());
+    let body = parse_description(p.next().unwrap())?;
+
+    assert_eq!(p.next(), None);
+
+    Ok(Template { name, body })
+}
+
+pub fn parse_command(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<Spanned<Command>> {
     assert_eq!(p.as_rule(), Rule::command);
 
     let p = p.into_inner().next().unwrap();
@@ -819,16 +1147,18 @@ pub fn parse_command(p: Pair<'_, Rule>) -> Result<Spanned<Command>> {
     let command = match p.as_rule() {
         Rule::include => Command::Include(parse_include(p)),
         Rule::timezone => Command::Timezone(parse_timezone(p)),
-        Rule::capture => Command::Capture,
-        Rule::task => Command::Task(parse_task(p)?),
-        Rule::note => Command::Note(parse_note(p)?),
+        Rule::capture => Command::Capture(parse_capture(p)),
+        Rule::fiscalyear => Command::FiscalYear(parse_fiscalyear(p)?),
+        Rule::task => Command::Task(parse_task(p, times)?),
+        Rule::note => Command::Note(parse_note(p, times)?),
         Rule::log => Command::Log(parse_log(p)?),
+        Rule::template => Command::Template(parse_template(p)?),
         _ => unreachable!(),
     };
     Ok(Spanned::new(span, command))
 }
 
-pub fn parse_file(p: Pair<'_, Rule>) -> Result<File> {
+pub fn parse_file(p: Pair<'_, Rule>, times: &TimesOfDay) -> Result<File> {
     assert_eq!(p.as_rule(), Rule::file);
 
     let mut commands = vec![];
@@ -838,18 +1168,183 @@ pub fn parse_file(p: Pair<'_, Rule>) -> Result<File> {
             break;
         }
 
-        commands.push(parse_command(p)?);
+        commands.push(parse_command(p, times)?);
     }
 
     Ok(File { commands })
 }
 
-pub fn parse(path: &Path, input: &str) -> Result<File> {
+pub fn parse(path: &Path, input: &str, times: &TimesOfDay) -> Result<File> {
     let pathstr = path.to_string_lossy();
 
     let mut pairs = TodayfileParser::parse(Rule::file, input).map_err(|e| e.with_path(&pathstr))?;
     let file_pair = pairs.next().unwrap();
     assert_eq!(pairs.next(), None);
 
-    parse_file(file_pair).map_err(|e| Box::new(e.with_path(&pathstr)))
+    parse_file(file_pair, times).map_err(|e| Box::new(e.with_path(&pathstr)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use chrono::NaiveDate;
+
+    use crate::files::commands::{Command, Spec, Statement};
+    use crate::files::primitives::{Time, TimeOfDay};
+
+    use super::{parse, Result, TimesOfDay};
+
+    #[test]
+    fn iso_week_variable() {
+        use crate::files::commands::{Expr, Var};
+
+        let input = "NOTE Standup\nDATE *\nEXCEPT (iw = 1)\n";
+        let file = parse(Path::new("test.today"), input, &TimesOfDay::new()).unwrap();
+        let note = match &file.commands[0].value {
+            Command::Note(note) => note,
+            _ => panic!("expected a note"),
+        };
+        let expr = match &note.statements[1] {
+            Statement::ExceptExpr(expr) => &expr.value,
+            _ => panic!("expected EXCEPT"),
+        };
+        let expr = match expr {
+            Expr::Paren(expr) => &expr.value,
+            expr => expr,
+        };
+        assert!(matches!(
+            expr,
+            Expr::Eq(lhs, _) if matches!(lhs.value, Expr::Var(Var::IsoWeek))
+        ));
+    }
+
+    #[test]
+    fn done_without_brackets() {
+        let input = "TASK Laundry\nDATE 2024-01-01\nDONE 2024-01-02\n";
+        let error = parse(Path::new("test.today"), input, &TimesOfDay::new()).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("completion date must be in brackets"));
+
+        let input = "TASK Laundry\nDATE 2024-01-01\nDONE [2024-01-02]\n";
+        assert!(parse(Path::new("test.today"), input, &TimesOfDay::new()).is_ok());
+    }
+
+    #[test]
+    fn skip_rejected() {
+        let input = "NOTE Standup\nDATE (isWeekday)\nSKIP (d = 1)\n";
+        let error = parse(Path::new("test.today"), input, &TimesOfDay::new()).unwrap_err();
+        assert!(error.to_string().contains("use EXCEPT (expr) instead"));
+
+        let input = "NOTE Standup\nDATE (isWeekday)\nEXCEPT (d = 1)\n";
+        assert!(parse(Path::new("test.today"), input, &TimesOfDay::new()).is_ok());
+    }
+
+    #[test]
+    fn spent_time() {
+        let input = "TASK Report\nEFFORT +2h30min\nDONE [2024-01-02] spent +2h\n";
+        let file = parse(Path::new("test.today"), input, &TimesOfDay::new()).unwrap();
+        let task = match &file.commands[0].value {
+            Command::Task(task) => task,
+            _ => panic!("expected a task"),
+        };
+        assert_eq!(task.effort().and_then(|e| e.minutes()), Some(150));
+        assert_eq!(
+            task.done[0].spent.as_ref().and_then(|s| s.minutes()),
+            Some(120)
+        );
+
+        let error = |input: &str| {
+            parse(Path::new("test.today"), input, &TimesOfDay::new())
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(error("TASK Report\nEFFORT +1d\n").contains("only consist of hours and minutes"));
+        assert!(
+            error("TASK Report\nDONE [2024-01-02] spent -1h\n").contains("must not be negative")
+        );
+        assert!(error("NOTE Report\nEFFORT +1h\n").contains("EFFORT not allowed in NOTEs"));
+    }
+
+    #[test]
+    fn created() {
+        let input = "TASK Laundry\nCREATED 2024-01-01\nDATE 2024-01-05\n";
+        let file = parse(Path::new("test.today"), input, &TimesOfDay::new()).unwrap();
+        let task = match &file.commands[0].value {
+            Command::Task(task) => task,
+            _ => panic!("expected a task"),
+        };
+        assert!(matches!(
+            task.statements[0],
+            Statement::Created(date) if date == NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()
+        ));
+    }
+
+    #[test]
+    fn ordinal_date() {
+        let date = |input: &str| -> Result<NaiveDate> {
+            let input = format!("TASK Laundry\nCREATED {input}\n");
+            let file = parse(Path::new("test.today"), &input, &TimesOfDay::new())?;
+            Ok(match &file.commands[0].value {
+                Command::Task(task) => match task.statements[0] {
+                    Statement::Created(date) => date,
+                    _ => panic!("expected a CREATED statement"),
+                },
+                _ => panic!("expected a task"),
+            })
+        };
+
+        let ymd = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(date("2024-001").unwrap(), ymd(2024, 1, 1));
+        assert_eq!(date("2024-060").unwrap(), ymd(2024, 2, 29));
+        assert_eq!(date("2024-366").unwrap(), ymd(2024, 12, 31));
+        assert_eq!(date("2023-365").unwrap(), ymd(2023, 12, 31));
+        assert_eq!(date("2024-03-01").unwrap(), ymd(2024, 3, 1));
+        assert!(date("2023-366").is_err());
+        assert!(date("2024-000").is_err());
+    }
+
+    #[test]
+    fn time_of_day() {
+        let times_with = |input: &str, times: &TimesOfDay| -> (Option<Time>, Option<Time>) {
+            let file = parse(Path::new("test.today"), input, times).unwrap();
+            let task = file
+                .commands
+                .iter()
+                .find_map(|c| match &c.value {
+                    Command::Task(task) => Some(task),
+                    _ => None,
+                })
+                .expect("expected a task");
+            match &task.statements[0] {
+                Statement::Date(spec) => match &spec.value {
+                    Spec::Date(spec) => (spec.start_time, spec.end_time.map(|t| t.value)),
+                    _ => panic!("expected a fixed date"),
+                },
+                _ => panic!("expected a DATE statement"),
+            }
+        };
+        let times = |input: &str| times_with(input, &TimesOfDay::new());
+
+        assert_eq!(
+            times("TASK Run\nDATE 2024-01-01 morning -- evening\n"),
+            (Some(Time::new(9, 0)), Some(Time::new(18, 0)))
+        );
+        // Overridden times of day replace only their defaults
+        let overrides = TimesOfDay::from([(TimeOfDay::Morning, Time::new(6, 30))]);
+        assert_eq!(
+            times_with("TASK Run\nDATE 2024-01-01 morning -- noon\n", &overrides),
+            (Some(Time::new(6, 30)), Some(Time::new(12, 0)))
+        );
+        // Times of day directly after dates and deltas
+        assert_eq!(
+            times("TASK Run\nDATE 2024-01-01 -- 2024-01-02 morning\n"),
+            (None, Some(Time::new(9, 0)))
+        );
+        assert_eq!(
+            times("TASK Run\nDATE 2024-01-01 morning -- +1d morning\n"),
+            (Some(Time::new(9, 0)), Some(Time::new(9, 0)))
+        );
+    }
 }
@@ -2,8 +2,9 @@ use std::cmp::{self, Ordering};
 use std::{fmt, ops};
 
 use chrono::{NaiveTime, Timelike};
+use serde::Serialize;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize)]
 pub struct Span {
     pub start: usize,
     pub end: usize,
@@ -37,7 +38,7 @@ impl Span {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize)]
 pub struct Spanned<T> {
     pub span: Span,
     pub value: T,
@@ -67,7 +68,7 @@ impl<T> Spanned<T> {
 //     }
 // }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
 pub struct Time {
     pub hour: u8,
     pub min: u8,
@@ -113,7 +114,7 @@ impl Time {
     }
 
     /// How many minutes into the day this time is.
-    fn minutes(&self) -> i64 {
+    pub fn minutes(&self) -> i64 {
         (self.hour as i64) * 60 + (self.min as i64)
     }
 
@@ -163,7 +164,41 @@ impl Time {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// A part of the day that can be used instead of a time, like `morning`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeOfDay {
+    Morning,
+    Noon,
+    Afternoon,
+    Evening,
+    Night,
+}
+
+impl TimeOfDay {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Morning => "morning",
+            Self::Noon => "noon",
+            Self::Afternoon => "afternoon",
+            Self::Evening => "evening",
+            Self::Night => "night",
+        }
+    }
+
+    /// The time this part of the day resolves to unless overridden in the
+    /// config.
+    pub fn default_time(self) -> Time {
+        match self {
+            Self::Morning => Time::new(9, 0),
+            Self::Noon => Time::new(12, 0),
+            Self::Afternoon => Time::new(15, 0),
+            Self::Evening => Time::new(18, 0),
+            Self::Night => Time::new(21, 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
 pub enum Weekday {
     Monday,
     Tuesday,
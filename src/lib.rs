@@ -0,0 +1,17 @@
+//! A library for specifying and tracking (recurring) dates and tasks.
+//!
+//! This is the library behind the `today` command line tool. Files can be
+//! parsed individually using [`parse_file`], or loaded together with all their
+//! includes using [`Files::load`](files::Files::load). Loaded files can then be
+//! evaluated using [`evaluate`].
+
+mod cli;
+mod error;
+pub mod eval;
+pub mod files;
+#[cfg(test)]
+mod testing;
+
+pub use self::cli::run;
+pub use self::eval::evaluate;
+pub use self::files::parse_file;
@@ -1,8 +1,6 @@
-mod cli;
-mod error;
-mod eval;
-mod files;
+// The dependencies are used by the library, not by the binary itself.
+#![allow(unused_crate_dependencies)]
 
 fn main() {
-    cli::run();
+    today::run();
 }
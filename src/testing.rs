@@ -0,0 +1,77 @@
+//! Helpers shared by the unit tests.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::{env, fs, process};
+
+use crate::eval::{DateRange, Entry, EntryMode};
+use crate::files::Files;
+
+/// A temporary directory unique to a single test, removed when dropped.
+///
+/// Tests run in parallel, so they must never share files.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNT.fetch_add(1, Ordering::Relaxed);
+        let dir = env::temp_dir().join(format!("today-test-{}-{count}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Write a file named `name` into the directory, returning its path.
+    pub fn write(&self, name: &str, text: &str) -> PathBuf {
+        let path = self.0.join(name);
+        fs::write(&path, text).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Force colored output on or off until the returned guard is dropped.
+///
+/// The setting is global, so tests relying on it must not run at the same
+/// time as each other.
+pub fn colors(enabled: bool) -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    let guard = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    colored::control::set_override(enabled);
+    guard
+}
+
+/// Load several files from a temporary directory. The first file is loaded
+/// and may include the others by their names.
+pub fn load_files(files: &[(&str, &str)]) -> Files {
+    let dir = TempDir::new();
+    for (name, text) in files {
+        dir.write(name, text);
+    }
+    let mut result = Files::new();
+    result.load(&dir.path().join(files[0].0)).unwrap();
+    result
+}
+
+/// Load a single file containing `text`.
+pub fn load(text: &str) -> Files {
+    load_files(&[("main.today", text)])
+}
+
+/// Load a single file containing `text` and evaluate it, returning the entries
+/// relevant to `range`.
+pub fn load_eval(text: &str, range: DateRange) -> (Files, Vec<Entry>) {
+    let files = load(text);
+    let entries = files.eval(EntryMode::Relevant, range).unwrap();
+    (files, entries)
+}
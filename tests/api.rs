@@ -0,0 +1,116 @@
+// Not all dependencies of the library are used by this test.
+#![allow(unused_crate_dependencies)]
+
+use std::path::Path;
+use std::{env, fs};
+
+use chrono::NaiveDate;
+use today::eval::{DateRange, EntryKind};
+use today::files::{Files, Source};
+
+const SAMPLE: &str = "\
+TIMEZONE UTC
+
+TASK Water the plants
+DATE 2024-01-01; +w
+
+NOTE Max
+BDATE 1990-01-03
+";
+
+fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+#[test]
+fn parse_sample() {
+    let file = today::parse_file(Path::new("sample.today"), SAMPLE).unwrap();
+    assert_eq!(file.commands.len(), 3);
+}
+
+#[test]
+fn parse_invalid() {
+    assert!(today::parse_file(Path::new("invalid.today"), "TASK\n").is_err());
+}
+
+/// Load `text` from a file only used by the test called `name`, since tests
+/// run in parallel.
+fn load(name: &str, text: &str) -> Files {
+    let path = env::temp_dir().join(format!("today-api-{name}.today"));
+    fs::write(&path, text).unwrap();
+
+    let mut files = Files::new();
+    files.load(&path).unwrap();
+    files
+}
+
+#[test]
+fn evaluate_sample() {
+    let files = load("evaluate-sample", SAMPLE);
+
+    let range = DateRange::new(ymd(2024, 1, 1), ymd(2024, 1, 14));
+    let entries = today::evaluate(&files, range).unwrap();
+
+    let mut tasks = entries
+        .iter()
+        .filter(|e| e.kind == EntryKind::Task)
+        .filter_map(|e| e.root())
+        .collect::<Vec<_>>();
+    tasks.sort();
+    assert_eq!(tasks, vec![ymd(2024, 1, 1), ymd(2024, 1, 8)]);
+
+    let birthdays = entries
+        .iter()
+        .filter(|e| matches!(e.kind, EntryKind::Birthday(_)))
+        .map(|e| (e.kind, e.root()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        birthdays,
+        vec![(EntryKind::Birthday(Some(34)), Some(ymd(2024, 1, 3)))]
+    );
+}
+
+const NEXT: &str = "\
+TIMEZONE UTC
+
+TASK Water the plants
+DATE 2024-01-01; +w
+
+NOTE Dentist
+DATE 2024-01-10
+
+TASK Someday
+";
+
+#[test]
+fn next_occurrence_recurring() {
+    let files = load("next-recurring", NEXT);
+    let source = Source::new(0, 1);
+
+    let next = today::eval::next_occurrence(&files, source, ymd(2024, 1, 1)).unwrap();
+    assert_eq!(next.map(|d| d.root()), Some(ymd(2024, 1, 8)));
+
+    let next = today::eval::next_occurrence(&files, source, ymd(2025, 6, 1)).unwrap();
+    assert_eq!(next.map(|d| d.root()), Some(ymd(2025, 6, 2)));
+}
+
+#[test]
+fn next_occurrence_one_off() {
+    let files = load("next-one-off", NEXT);
+    let source = Source::new(0, 2);
+
+    let next = today::eval::next_occurrence(&files, source, ymd(2024, 1, 1)).unwrap();
+    assert_eq!(next.map(|d| d.root()), Some(ymd(2024, 1, 10)));
+
+    let next = today::eval::next_occurrence(&files, source, ymd(2024, 1, 10)).unwrap();
+    assert!(next.is_none());
+}
+
+#[test]
+fn next_occurrence_undated() {
+    let files = load("next-undated", NEXT);
+    let source = Source::new(0, 3);
+
+    let next = today::eval::next_occurrence(&files, source, ymd(2024, 1, 1)).unwrap();
+    assert!(next.is_none());
+}